@@ -2,6 +2,7 @@ use std::collections::HashMap;
 use crate::frame::errors::CommandError;
 use crate::frame::config::FontConfig;
 use crate::frame::SashId;
+use crate::pane::WindowManipulation;
 
 /// Global commands that operate at the Frame level
 #[derive(Debug, Clone, PartialEq)]
@@ -41,6 +42,8 @@ pub enum GlobalCommand {
     ToggleFullscreen,
     MinimizeAllWindows,
     RestoreAllWindows,
+    /// Program-requested change to the current window (XTWINOPS)
+    ManipulateWindow(WindowManipulation),
     
     // Debug and development
     ShowDebugInfo,
@@ -48,6 +51,32 @@ pub enum GlobalCommand {
     DumpState,
 }
 
+/// Groups of window manipulations that can be allowed in `GlobalConfig`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WindowManipulationKind {
+    Iconify,
+    Move,
+    Resize,
+    Stacking,
+    Maximize,
+    Fullscreen,
+}
+
+impl From<&WindowManipulation> for WindowManipulationKind {
+    fn from(manipulation: &WindowManipulation) -> Self {
+        match manipulation {
+            WindowManipulation::Deiconify | WindowManipulation::Iconify => WindowManipulationKind::Iconify,
+            WindowManipulation::Move { .. } => WindowManipulationKind::Move,
+            WindowManipulation::ResizePixels { .. } | WindowManipulation::ResizeCells { .. } =>
+                WindowManipulationKind::Resize,
+            WindowManipulation::Raise | WindowManipulation::Lower => WindowManipulationKind::Stacking,
+            WindowManipulation::Maximize(_) => WindowManipulationKind::Maximize,
+            WindowManipulation::Fullscreen(_) | WindowManipulation::ToggleFullscreen =>
+                WindowManipulationKind::Fullscreen,
+        }
+    }
+}
+
 /// Commands that operate at the Sash (window) level
 #[derive(Debug, Clone, PartialEq)]
 pub enum SashCommand {
@@ -133,6 +162,7 @@ pub enum GlobalCommandType {
     GlobalSettings,
     CrossWindow,
     Fullscreen,
+    WindowManipulation,
     Debug,
 }
 
@@ -163,6 +193,8 @@ impl From<&GlobalCommand> for GlobalCommandType {
             GlobalCommand::ToggleFullscreen | GlobalCommand::MinimizeAllWindows |
             GlobalCommand::RestoreAllWindows => GlobalCommandType::Fullscreen,
             
+            GlobalCommand::ManipulateWindow(_) => GlobalCommandType::WindowManipulation,
+            
             GlobalCommand::ShowDebugInfo | GlobalCommand::ReloadThemes | 
             GlobalCommand::DumpState => GlobalCommandType::Debug,
         }
@@ -1,6 +1,8 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 use crate::frame::errors::ConfigError;
+use crate::frame::commands::{GlobalCommand, WindowManipulationKind};
+use crate::pane::WindowManipulation;
 
 /// Key combination for shortcuts
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -46,6 +48,8 @@ pub struct NotificationSettings {
     pub show_system_notifications: bool,
     pub play_sound: bool,
     pub flash_window: bool,
+    /// Minimum time between notifications from the same pane
+    pub rate_limit_ms: u64,
 }
 
 impl Default for NotificationSettings {
@@ -55,6 +59,7 @@ impl Default for NotificationSettings {
             show_system_notifications: true,
             play_sound: false,
             flash_window: true,
+            rate_limit_ms: 2000,
         }
     }
 }
@@ -140,6 +145,8 @@ pub struct GlobalConfig {
     pub allow_multiple_windows: bool,
     pub restore_windows_on_startup: bool,
     pub confirm_before_quit: bool,
+    /// Window manipulations programs may request through XTWINOPS; none by default
+    pub allowed_window_manipulations: HashSet<WindowManipulationKind>,
     
     // Global shortcuts and behavior
     pub global_shortcuts: HashMap<KeyCombination, crate::frame::commands::GlobalCommand>,
@@ -164,6 +171,7 @@ impl Default for GlobalConfig {
             allow_multiple_windows: true,
             restore_windows_on_startup: false,
             confirm_before_quit: true,
+            allowed_window_manipulations: HashSet::new(),
             global_shortcuts: HashMap::new(),
             shell_integration: true,
             notification_settings: NotificationSettings::default(),
@@ -215,6 +223,17 @@ impl GlobalConfig {
         Ok(())
     }
     
+    /// Command for a program-requested window manipulation, or `None` if it is not allowed
+    pub fn window_manipulation_command(&self, manipulation: WindowManipulation) -> Option<GlobalCommand> {
+        if !self.allowed_window_manipulations.contains(&WindowManipulationKind::from(&manipulation)) {
+            return None;
+        }
+        match manipulation {
+            WindowManipulation::ToggleFullscreen => Some(GlobalCommand::ToggleFullscreen),
+            _ => Some(GlobalCommand::ManipulateWindow(manipulation)),
+        }
+    }
+    
     /// Validate configuration settings
     pub fn validate(&self) -> Result<(), ConfigError> {
         // Validate font size
@@ -299,6 +318,20 @@ mod tests {
         assert!(config.validate().is_err());
     }
     
+    #[test]
+    fn test_window_manipulation_allow_list() {
+        let mut config = GlobalConfig::default();
+        assert_eq!(config.window_manipulation_command(WindowManipulation::Raise), None);
+        
+        config.allowed_window_manipulations.insert(WindowManipulationKind::Stacking);
+        config.allowed_window_manipulations.insert(WindowManipulationKind::Fullscreen);
+        assert_eq!(config.window_manipulation_command(WindowManipulation::Lower),
+                   Some(GlobalCommand::ManipulateWindow(WindowManipulation::Lower)));
+        assert_eq!(config.window_manipulation_command(WindowManipulation::ToggleFullscreen),
+                   Some(GlobalCommand::ToggleFullscreen));
+        assert_eq!(config.window_manipulation_command(WindowManipulation::Move { x: 0, y: 0 }), None);
+    }
+    
     #[test]
     fn test_key_combination() {
         let combo = KeyCombination {
@@ -219,6 +219,16 @@ pub enum ResourceError {
     DependencyError(String),
 }
 
+/// Desktop notification errors
+#[derive(Debug, Error)]
+pub enum NotificationError {
+    #[error("Notification backend unavailable: {0}")]
+    BackendUnavailable(String),
+    
+    #[error("Notification delivery failed: {0}")]
+    DeliveryFailed(String),
+}
+
 /// Utility trait for converting errors to user-friendly messages
 pub trait UserFriendlyError {
     fn user_message(&self) -> String;
@@ -53,6 +53,9 @@ pub enum GlobalEvent {
     MenuItemSelected(String),
     PreferencesRequested,
     
+    // Notification events
+    NotificationRequested(crate::frame::notifications::Notification),
+    
     // Error and warning events
     ErrorOccurred(String),
     WarningOccurred(String),
@@ -81,6 +84,7 @@ pub enum GlobalEventType {
     Configuration,
     System,
     UserInteraction,
+    Notification,
     Error,
     Custom,
 }
@@ -110,6 +114,8 @@ impl From<&GlobalEvent> for GlobalEventType {
             GlobalEvent::ShortcutTriggered(_) | GlobalEvent::MenuItemSelected(_) |
             GlobalEvent::PreferencesRequested => GlobalEventType::UserInteraction,
             
+            GlobalEvent::NotificationRequested(_) => GlobalEventType::Notification,
+            
             GlobalEvent::ErrorOccurred(_) | GlobalEvent::WarningOccurred(_) => 
                 GlobalEventType::Error,
             
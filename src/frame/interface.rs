@@ -134,6 +134,9 @@ impl FrameInterface for Frame {
         // Initialize command router with default handlers
         frame.command_router = crate::frame::commands::CommandRouter::new();
         
+        // Route desktop notifications to the platform backend
+        frame.set_notifier(crate::frame::notifications::default_notifier());
+        
         // Set application state to running
         frame.app_state = crate::frame::ApplicationState::Running;
         
@@ -151,6 +154,8 @@ impl FrameInterface for Frame {
         
         // Main event loop
         while self.should_continue() {
+            self.process_pane_events()?;
+            
             // TODO: Process platform events
             // TODO: Handle input
             // TODO: Update windows
@@ -354,6 +359,8 @@ impl FrameInterface for Frame {
         config.validate()
             .map_err(|e| FrameError::ConfigurationError(e))?;
         
+        let notifications_changed =
+            config.notification_settings != self.global_config.notification_settings;
         self.global_config = config;
         
         let _ = self.event_dispatcher.dispatch(
@@ -362,6 +369,16 @@ impl FrameInterface for Frame {
             )
         );
         
+        if notifications_changed {
+            let _ = self.event_dispatcher.dispatch(
+                GlobalEvent::ConfigurationChanged(
+                    crate::frame::events::ConfigChange::NotificationSettings(
+                        self.global_config.notification_settings.clone()
+                    )
+                )
+            );
+        }
+        
         Ok(())
     }
     
@@ -3,6 +3,8 @@ pub mod commands;
 pub mod events;
 pub mod errors;
 pub mod interface;
+pub mod notifications;
+pub mod pane_events;
 
 #[cfg(test)]
 mod tests;
@@ -12,8 +14,12 @@ pub use commands::*;
 pub use events::*;
 pub use errors::*;
 pub use interface::*;
+pub use notifications::*;
+pub use pane_events::*;
 
 use std::collections::HashMap;
+use crate::pane::{PaneEvent, WindowManipulation};
+use crate::sash::PaneId;
 
 /// Unique identifier for a Sash (window)
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
@@ -57,6 +63,7 @@ pub struct Frame {
     // Event and command coordination
     command_router: CommandRouter,
     event_dispatcher: EventDispatcher,
+    pane_events: PaneEventQueue,
     
     // Shutdown coordination
     shutdown_requested: bool,
@@ -74,6 +81,7 @@ impl Frame {
             global_config: GlobalConfig::default(),
             command_router: CommandRouter::new(),
             event_dispatcher: EventDispatcher::new(),
+            pane_events: PaneEventQueue::default(),
             shutdown_requested: false,
             cleanup_handlers: Vec::new(),
         })
@@ -96,8 +104,64 @@ impl Frame {
         self.shutdown_requested = true;
         self.app_state = ApplicationState::ShuttingDown;
     }
+    
+    /// Carry out a window manipulation requested by a program, if the config allows it
+    ///
+    /// Disallowed requests are dropped silently, as xterm does.
+    pub fn request_window_manipulation(&mut self, manipulation: WindowManipulation) -> Result<(), FrameError> {
+        match self.global_config.window_manipulation_command(manipulation) {
+            Some(command) => self.execute_command(command),
+            None => Ok(()),
+        }
+    }
+    
+    /// Install the backend used to show desktop notifications
+    pub fn set_notifier(&mut self, notifier: Box<dyn Notifier>) {
+        self.event_dispatcher.unsubscribe_all(NOTIFICATION_CENTER_LISTENER_ID);
+        
+        let center = NotificationCenter::new(
+            NOTIFICATION_CENTER_LISTENER_ID,
+            notifier,
+            self.global_config.notification_settings.clone(),
+        );
+        self.event_dispatcher.subscribe(GlobalEventType::Notification, Box::new(center.clone()));
+        self.event_dispatcher.subscribe(GlobalEventType::Configuration, Box::new(center));
+    }
+    
+    /// Listener to register on a pane so its notifications and window requests reach the frame
+    pub fn pane_event_forwarder(&self, pane_id: PaneId) -> PaneEventForwarder {
+        PaneEventForwarder::new(pane_id, self.pane_events.clone())
+    }
+    
+    /// Act on the pane events queued by forwarders since the last call
+    pub fn process_pane_events(&mut self) -> Result<(), FrameError> {
+        let events = match self.pane_events.lock() {
+            Ok(mut queue) => std::mem::take(&mut *queue),
+            Err(_) => return Ok(()),
+        };
+        for (pane_id, event) in events {
+            self.handle_pane_event(pane_id, event)?;
+        }
+        Ok(())
+    }
+    
+    /// Turn a pane event into the matching global event or command
+    pub fn handle_pane_event(&mut self, pane_id: PaneId, event: PaneEvent) -> Result<(), FrameError> {
+        match event {
+            PaneEvent::NotificationRequested(notification) => {
+                let notification = Notification::new(notification.title, notification.body)
+                    .from_pane(pane_id.as_u64());
+                self.emit_event(GlobalEvent::NotificationRequested(notification))
+            }
+            PaneEvent::WindowManipulationRequested(manipulation) => self.request_window_manipulation(manipulation),
+            _ => Ok(()),
+        }
+    }
 }
 
+/// Listener id reserved for the built-in notification center
+pub const NOTIFICATION_CENTER_LISTENER_ID: ListenerId = ListenerId(0);
+
 /// Placeholder trait for Sash interface - will be defined in sash layer
 #[cfg_attr(test, mockall::automock)]
 pub trait SashInterface: Send + Sync {
@@ -0,0 +1,146 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use crate::frame::config::NotificationSettings;
+use crate::frame::errors::{EventError, NotificationError};
+use crate::frame::events::{ConfigChange, EventListener, GlobalEvent, GlobalEventType, ListenerId};
+
+/// A desktop notification to be shown by the host system
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notification {
+    /// The pane that requested the notification, if any
+    pub source_pane: Option<u64>,
+    pub title: String,
+    pub body: String,
+}
+
+impl Notification {
+    pub fn new(title: impl Into<String>, body: impl Into<String>) -> Self {
+        Notification {
+            source_pane: None,
+            title: title.into(),
+            body: body.into(),
+        }
+    }
+    
+    pub fn from_pane(mut self, pane_id: u64) -> Self {
+        self.source_pane = Some(pane_id);
+        self
+    }
+}
+
+/// Backend that delivers notifications to the desktop
+pub trait Notifier: Send + Sync {
+    fn notify(&mut self, notification: &Notification) -> Result<(), NotificationError>;
+}
+
+/// Notifier that silently drops everything (used where no backend exists)
+#[derive(Debug, Default)]
+pub struct NullNotifier;
+
+impl Notifier for NullNotifier {
+    fn notify(&mut self, _notification: &Notification) -> Result<(), NotificationError> {
+        Ok(())
+    }
+}
+
+struct NotificationCenterState {
+    notifier: Box<dyn Notifier>,
+    settings: NotificationSettings,
+    last_sent: HashMap<Option<u64>, Instant>,
+    suppressed: usize,
+}
+
+/// Event listener that routes notification events to a `Notifier`
+///
+/// The center is a cheap handle so the same instance can be subscribed to
+/// both notification and configuration events.
+#[derive(Clone)]
+pub struct NotificationCenter {
+    id: ListenerId,
+    state: Arc<Mutex<NotificationCenterState>>,
+}
+
+impl NotificationCenter {
+    pub fn new(id: ListenerId, notifier: Box<dyn Notifier>, settings: NotificationSettings) -> Self {
+        NotificationCenter {
+            id,
+            state: Arc::new(Mutex::new(NotificationCenterState {
+                notifier,
+                settings,
+                last_sent: HashMap::new(),
+                suppressed: 0,
+            })),
+        }
+    }
+    
+    /// Replace the notification settings
+    pub fn update_settings(&self, settings: NotificationSettings) {
+        if let Ok(mut state) = self.state.lock() {
+            state.settings = settings;
+        }
+    }
+    
+    /// Number of notifications dropped by the rate limiter
+    pub fn suppressed_count(&self) -> usize {
+        self.state.lock().map(|state| state.suppressed).unwrap_or(0)
+    }
+    
+    /// Deliver a notification, honouring settings and the per-pane rate limit
+    ///
+    /// Returns whether the notification was passed on to the notifier.
+    pub fn deliver(&self, notification: &Notification) -> Result<bool, NotificationError> {
+        let mut state = self.state.lock()
+            .map_err(|_| NotificationError::DeliveryFailed("Notification state poisoned".to_string()))?;
+        
+        if !state.settings.enabled || !state.settings.show_system_notifications {
+            return Ok(false);
+        }
+        
+        let now = Instant::now();
+        let min_interval = Duration::from_millis(state.settings.rate_limit_ms);
+        if let Some(last) = state.last_sent.get(&notification.source_pane) {
+            if now.duration_since(*last) < min_interval {
+                state.suppressed += 1;
+                return Ok(false);
+            }
+        }
+        
+        state.last_sent.insert(notification.source_pane, now);
+        state.notifier.notify(notification)?;
+        Ok(true)
+    }
+}
+
+impl EventListener for NotificationCenter {
+    fn handle_event(&mut self, event: &GlobalEvent) -> Result<(), EventError> {
+        match event {
+            GlobalEvent::NotificationRequested(notification) => {
+                self.deliver(notification)
+                    .map_err(|e| EventError::ListenerFailed(e.to_string()))?;
+            }
+            GlobalEvent::ConfigurationChanged(ConfigChange::NotificationSettings(settings)) => {
+                self.update_settings(settings.clone());
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+    
+    fn can_handle(&self, event_type: GlobalEventType) -> bool {
+        matches!(event_type, GlobalEventType::Notification | GlobalEventType::Configuration)
+    }
+    
+    fn listener_id(&self) -> ListenerId {
+        self.id
+    }
+}
+
+/// Get the notifier for the current platform
+pub fn default_notifier() -> Box<dyn Notifier> {
+    #[cfg(target_os = "linux")]
+    return Box::new(crate::platform::linux::NotifySendNotifier::new());
+    
+    #[cfg(not(target_os = "linux"))]
+    Box::new(NullNotifier)
+}
@@ -0,0 +1,36 @@
+use std::sync::{Arc, Mutex};
+use crate::pane::{PaneEvent, PaneEventListener, PaneEventType, PaneResult};
+use crate::sash::PaneId;
+
+/// Pane events collected for the frame, tagged with the pane that raised them
+pub type PaneEventQueue = Arc<Mutex<Vec<(PaneId, PaneEvent)>>>;
+
+/// Pane listener that queues the events the frame acts on
+///
+/// Panes dispatch events synchronously while they process output, when the frame cannot be
+/// borrowed; the frame drains the queue in `Frame::process_pane_events`.
+#[derive(Clone)]
+pub struct PaneEventForwarder {
+    pane_id: PaneId,
+    queue: PaneEventQueue,
+}
+
+impl PaneEventForwarder {
+    pub fn new(pane_id: PaneId, queue: PaneEventQueue) -> Self {
+        PaneEventForwarder { pane_id, queue }
+    }
+}
+
+impl PaneEventListener for PaneEventForwarder {
+    fn handle_pane_event(&mut self, event: &PaneEvent) -> PaneResult<()> {
+        if let Ok(mut queue) = self.queue.lock() {
+            queue.push((self.pane_id, event.clone()));
+        }
+        Ok(())
+    }
+
+    fn can_handle(&self, event_type: PaneEventType) -> bool {
+        matches!(event_type,
+            PaneEventType::NotificationRequested | PaneEventType::WindowManipulationRequested)
+    }
+}
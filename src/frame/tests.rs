@@ -504,4 +504,127 @@ mod frame_tests {
         
         assert!(has_destroyed_event);
     }
-}
\ No newline at end of file
+
+    /// Notifier stub that records everything it is asked to show
+    #[derive(Clone, Default)]
+    struct RecordingNotifier {
+        delivered: std::sync::Arc<std::sync::Mutex<Vec<crate::frame::Notification>>>,
+    }
+
+    impl crate::frame::Notifier for RecordingNotifier {
+        fn notify(&mut self, notification: &crate::frame::Notification) -> Result<(), crate::frame::NotificationError> {
+            self.delivered.lock().unwrap().push(notification.clone());
+            Ok(())
+        }
+    }
+
+    fn create_notification_center(
+        settings: crate::frame::config::NotificationSettings,
+    ) -> (crate::frame::NotificationCenter, RecordingNotifier) {
+        let recorder = RecordingNotifier::default();
+        let center = crate::frame::NotificationCenter::new(ListenerId::new(1), Box::new(recorder.clone()), settings);
+        (center, recorder)
+    }
+
+    #[test]
+    fn test_notification_delivered() {
+        let (mut center, recorder) = create_notification_center(Default::default());
+        let event = GlobalEvent::NotificationRequested(crate::frame::Notification::new("Build", "done").from_pane(1));
+        
+        center.handle_event(&event).unwrap();
+        
+        let delivered = recorder.delivered.lock().unwrap();
+        assert_eq!(delivered.len(), 1);
+        assert_eq!(delivered[0].title, "Build");
+        assert_eq!(delivered[0].source_pane, Some(1));
+    }
+
+    #[test]
+    fn test_notification_respects_settings() {
+        let settings = crate::frame::config::NotificationSettings {
+            show_system_notifications: false,
+            ..Default::default()
+        };
+        let (center, recorder) = create_notification_center(settings);
+        
+        assert!(!center.deliver(&crate::frame::Notification::new("a", "b")).unwrap());
+        assert!(recorder.delivered.lock().unwrap().is_empty());
+        
+        center.update_settings(Default::default());
+        assert!(center.deliver(&crate::frame::Notification::new("a", "b")).unwrap());
+    }
+
+    #[test]
+    fn test_notification_rate_limited_per_pane() {
+        let (center, recorder) = create_notification_center(Default::default());
+        
+        assert!(center.deliver(&crate::frame::Notification::new("1", "").from_pane(1)).unwrap());
+        assert!(!center.deliver(&crate::frame::Notification::new("2", "").from_pane(1)).unwrap());
+        assert!(center.deliver(&crate::frame::Notification::new("3", "").from_pane(2)).unwrap());
+        
+        assert_eq!(recorder.delivered.lock().unwrap().len(), 2);
+        assert_eq!(center.suppressed_count(), 1);
+    }
+
+    #[test]
+    fn test_notification_settings_event() {
+        let (mut center, recorder) = create_notification_center(Default::default());
+        let disabled = crate::frame::config::NotificationSettings { enabled: false, ..Default::default() };
+        
+        center.handle_event(&GlobalEvent::ConfigurationChanged(
+            crate::frame::ConfigChange::NotificationSettings(disabled)
+        )).unwrap();
+        center.handle_event(&GlobalEvent::NotificationRequested(crate::frame::Notification::new("x", "y"))).unwrap();
+        
+        assert!(recorder.delivered.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_notification_events_reach_notifier() {
+        let mut frame = Frame::initialize().unwrap();
+        let recorder = RecordingNotifier::default();
+        let delivered = recorder.delivered.clone();
+        frame.set_notifier(Box::new(recorder));
+        
+        let notification = crate::frame::Notification::new("Done", "make finished").from_pane(3);
+        frame.emit_event(GlobalEvent::NotificationRequested(notification.clone())).unwrap();
+        
+        assert_eq!(delivered.lock().unwrap().as_slice(), &[notification]);
+        
+        // Disabling notifications in the config stops delivery
+        let mut config = frame.get_global_config().clone();
+        config.notification_settings.enabled = false;
+        frame.update_global_config(config).unwrap();
+        
+        frame.emit_event(GlobalEvent::NotificationRequested(
+            crate::frame::Notification::new("Again", "").from_pane(4)
+        )).unwrap();
+        assert_eq!(delivered.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_pane_notification_reaches_notifier() {
+        use crate::pane::{Pane, PaneConfig, PaneEventType, PaneInterface};
+        use crate::sash::PaneId;
+        
+        let mut frame = Frame::initialize().unwrap();
+        let recorder = RecordingNotifier::default();
+        let delivered = recorder.delivered.clone();
+        frame.set_notifier(Box::new(recorder));
+        
+        let pane_id = PaneId::new(5);
+        let mut pane = Pane::new(pane_id, PaneConfig::default()).unwrap();
+        pane.register_event_listener(PaneEventType::All, Box::new(frame.pane_event_forwarder(pane_id)));
+        
+        pane.process_output(b"\x1b]777;notify;Build;all green\x07").unwrap();
+        assert!(delivered.lock().unwrap().is_empty());
+        
+        frame.process_pane_events().unwrap();
+        assert_eq!(delivered.lock().unwrap().as_slice(),
+                   &[crate::frame::Notification::new("Build", "all green").from_pane(5)]);
+        
+        // The queue is drained, so nothing is delivered twice
+        frame.process_pane_events().unwrap();
+        assert_eq!(delivered.lock().unwrap().len(), 1);
+    }
+}
@@ -56,6 +56,12 @@ pub struct RendererConfig {
     
     /// Text rendering quality
     pub text_quality: TextQuality,
+    
+    /// Pass underline colors (SGR 58) through to the host terminal; plain underlines otherwise
+    pub underline_color: bool,
+    
+    /// Blink SGR 5/6 text; steady when off
+    pub text_blink: bool,
 }
 
 impl Default for RendererConfig {
@@ -67,10 +73,20 @@ impl Default for RendererConfig {
             font: FontConfig::default(),
             antialiasing: AntiAliasingConfig::default(),
             text_quality: TextQuality::High,
+            underline_color: host_supports_underline_color(),
+            text_blink: true,
         }
     }
 }
 
+/// Whether the host terminal is known to draw colored underlines
+pub fn host_supports_underline_color() -> bool {
+    let term = std::env::var("TERM").unwrap_or_default();
+    let program = std::env::var("TERM_PROGRAM").unwrap_or_default();
+    ["kitty", "wezterm", "foot", "ghostty", "contour", "ratty"].iter().any(|name| term.contains(name))
+        || matches!(program.as_str(), "WezTerm" | "ghostty" | "iTerm.app" | "vscode")
+}
+
 /// Font configuration
 #[derive(Debug, Clone)]
 pub struct FontConfig {
@@ -537,6 +537,8 @@ impl GlazingInterface for MockGlazingInterface {
                     subpixel_order: SubpixelOrder::Rgb,
                 },
                 text_quality: TextQuality::High,
+                underline_color: false,
+                text_blink: true,
             },
             theme: ThemeConfig {
                 default_theme_name: String::new(),
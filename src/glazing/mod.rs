@@ -1,4 +1,5 @@
 pub mod renderer;
+pub mod scheduler;
 pub mod theme;
 pub mod layout;
 pub mod viewport;
@@ -16,10 +17,11 @@ pub use events::*;
 pub use interface::*;
 pub use layout::*;
 pub use renderer::*;
+pub use scheduler::*;
 pub use theme::*;
 pub use viewport::*;
 
-use crate::pane::{CellAttributes, Cursor, Line, ScreenBuffer, ScrollbackBuffer};
+use crate::pane::{CellAttributes, Cursor, ImageStore, Line, PaneInterface, ScreenBuffer, ScrollbackBuffer};
 use crate::sash::{PaneId, Theme};
 use ratatui::{
     backend::Backend,
@@ -30,6 +32,7 @@ use ratatui::{
     widgets::{Block, Borders, Clear, Paragraph},
     Frame as RatatuiFrame,
 };
+use std::collections::HashMap;
 use std::time::{Duration, Instant};
 
 /// The main Glazing engine - coordinates all rendering operations
@@ -51,9 +54,13 @@ pub struct GlazingEngine {
     /// Current render state
     current_frame: Option<RenderFrame>,
     last_render_time: Instant,
+    scheduler: RenderScheduler,
     
     /// Event handling
     event_handler: GlazingEventHandler,
+    
+    /// Image stores shared with panes, keyed by pane
+    image_stores: HashMap<PaneId, ImageStore>,
 }
 
 impl GlazingEngine {
@@ -65,10 +72,12 @@ impl GlazingEngine {
             layout_manager: LayoutManager::new(),
             viewport: Viewport::new(),
             performance: PerformanceTracker::new(),
+            scheduler: RenderScheduler::new(&config),
             config,
             current_frame: None,
             last_render_time: Instant::now(),
             event_handler: GlazingEventHandler::new(),
+            image_stores: HashMap::new(),
         })
     }
     
@@ -88,14 +97,17 @@ impl GlazingEngine {
         // Update viewport based on area
         self.viewport.update_dimensions(area.width, area.height);
         
-        // Create render frame for this pane
-        let render_frame = self.create_render_frame(
-            screen_buffer,
-            scrollback,
-            cursor,
-            pane_id,
-            is_active,
-        )?;
+        // Create render frame for this pane, or keep showing the last one mid-batch
+        let render_frame = match self.scheduler.held_frame(pane_id) {
+            Some(held) => held.clone(),
+            None => self.create_render_frame(
+                screen_buffer,
+                scrollback,
+                cursor,
+                pane_id,
+                is_active,
+            )?,
+        };
         
         // Render the frame
         self.renderer.render_frame::<B>(frame, area, &render_frame, &self.theme_engine)?;
@@ -103,6 +115,7 @@ impl GlazingEngine {
         // Update performance metrics
         self.performance.end_frame();
         self.last_render_time = Instant::now();
+        self.scheduler.record_present(pane_id, render_frame.clone(), self.last_render_time);
         
         // Store frame for next render comparison
         self.current_frame = Some(render_frame);
@@ -110,6 +123,26 @@ impl GlazingEngine {
         Ok(())
     }
     
+    /// Render a pane from its own state, keeping its last frame while a synchronized update is open
+    pub fn render_pane_state<B: Backend>(
+        &mut self,
+        frame: &mut RatatuiFrame,
+        area: Rect,
+        pane: &impl PaneInterface,
+        is_active: bool,
+    ) -> GlazingResult<()> {
+        self.sync_pane(pane);
+        self.render_pane::<B>(
+            frame,
+            area,
+            pane.get_screen_content(),
+            pane.get_scrollback(),
+            pane.get_cursor(),
+            pane.id(),
+            is_active,
+        )
+    }
+    
     /// Render multiple panes in a layout
     pub fn render_layout<B: Backend>(
         &mut self,
@@ -159,6 +192,7 @@ impl GlazingEngine {
     pub fn update_config(&mut self, config: GlazingConfig) -> GlazingResult<()> {
         self.config = config;
         self.renderer.update_config(&self.config)?;
+        self.scheduler.update_config(&self.config);
         self.theme_engine.update_config(&self.config.theme)?;
         Ok(())
     }
@@ -168,6 +202,75 @@ impl GlazingEngine {
         &self.performance
     }
     
+    /// Share a pane's image store so its images are included when rendering
+    pub fn attach_image_store(&mut self, pane_id: PaneId, store: ImageStore) {
+        self.image_stores.insert(pane_id, store);
+    }
+    
+    /// Stop rendering images for a pane
+    pub fn detach_image_store(&mut self, pane_id: PaneId) {
+        self.image_stores.remove(&pane_id);
+    }
+    
+    /// Hold a pane's presentation while its program has a synchronized update open
+    pub fn set_synchronized_update(&mut self, pane_id: PaneId, active: bool) {
+        self.scheduler.set_held(pane_id, active);
+    }
+    
+    /// Pick up a pane's synchronized update (mode 2026) before rendering it
+    pub fn sync_pane(&mut self, pane: &impl PaneInterface) {
+        self.set_synchronized_update(pane.id(), pane.is_synchronized_update());
+    }
+    
+    /// Whether the next frame is due under the target frame rate
+    pub fn frame_due(&self) -> bool {
+        self.scheduler.frame_due(Instant::now())
+    }
+    
+    /// When blinking text in the last frame next changes phase, so the host can wake to redraw
+    pub fn next_blink_toggle(&self) -> Option<Instant> {
+        let frame = self.current_frame.as_ref()?;
+        let now = Instant::now();
+        let text_blink = self.renderer.text_blink();
+        frame
+            .content
+            .iter()
+            .flat_map(|line| &line.cells)
+            .filter_map(|cell| text_blink.next_toggle(cell.style.blink, now))
+            .min()
+    }
+    
+    /// Drop per-pane render state when a pane closes
+    pub fn remove_pane(&mut self, pane_id: PaneId) {
+        self.image_stores.remove(&pane_id);
+        self.scheduler.remove_pane(pane_id);
+    }
+    
+    /// Collect the image placements that overlap the visible lines
+    fn visible_images(&self, pane_id: PaneId, first_row: u64, line_count: usize) -> Vec<RenderedImage> {
+        let Some(store) = self.image_stores.get(&pane_id) else {
+            return Vec::new();
+        };
+        
+        let layer = store.read();
+        let mut images: Vec<RenderedImage> = layer
+            .placements_in_rows(first_row..first_row + line_count as u64)
+            .filter_map(|placement| {
+                Some(RenderedImage {
+                    image: layer.image(placement.image_id)?,
+                    row: placement.row as i64 - first_row as i64,
+                    col: placement.col,
+                    rows: placement.rows,
+                    cols: placement.cols,
+                    z_index: placement.z_index,
+                    pixel_offset: placement.pixel_offset,
+                })
+            })
+            .collect();
+        images.sort_by_key(|image| image.z_index);
+        images
+    }
+    
     /// Create a render frame from pane content
     fn create_render_frame(
         &self,
@@ -226,14 +329,25 @@ impl GlazingEngine {
         
         // Render cursor if active and visible
         let rendered_cursor = if is_active && cursor.should_render() {
-            Some(self.renderer.render_cursor(cursor, &self.theme_engine)?)
+            let mut rendered = self.renderer.render_cursor(cursor, &self.theme_engine)?;
+            // Columns on double-width lines take two cells on screen
+            let on_double_width = screen_buffer
+                .get_line(cursor.position.row as usize)
+                .is_some_and(|line| line.size.is_double_width());
+            if on_double_width {
+                rendered.position.col *= 2;
+            }
+            Some(rendered)
         } else {
             None
         };
         
+        let first_row = scrollback.first_absolute_row() + start_line as u64;
+        
         Ok(RenderFrame {
             content: rendered_lines,
             cursor: rendered_cursor,
+            images: self.visible_images(pane_id, first_row, line_count),
             viewport: self.viewport.clone(),
             dirty_regions: Vec::new(), // Will be populated by dirty tracking
             pane_id,
@@ -247,6 +361,8 @@ impl GlazingEngine {
 pub struct RenderFrame {
     pub content: Vec<RenderedLine>,
     pub cursor: Option<RenderedCursor>,
+    /// Images over the visible lines, lowest z-index first
+    pub images: Vec<RenderedImage>,
     pub viewport: Viewport,
     pub dirty_regions: Vec<DirtyRegion>,
     pub pane_id: PaneId,
@@ -1,5 +1,5 @@
 use super::*;
-use crate::pane::{Cell, Cursor, CursorStyle, CursorVisibility, Line};
+use crate::pane::{BlinkType, Cell, Cursor, CursorStyle, CursorVisibility, FrameType, Line, LineSize, ScriptPosition};
 use ratatui::{
     backend::Backend,
     layout::Rect,
@@ -63,7 +63,17 @@ impl TerminalRenderer {
         theme_engine: &ThemeEngine,
         has_cursor: bool,
     ) -> GlazingResult<RenderedLine> {
-        let rendered_cells = self.text_renderer.render_cells(&line.cells, theme_engine)?;
+        // Double-width lines show each character across two cells. A character grid cannot
+        // split glyphs, so both halves of a double-height line show the doubled text and
+        // carry their size for backends that can clip.
+        let doubled;
+        let cells = if line.size.is_double_width() {
+            doubled = double_cells(&line.cells);
+            &doubled
+        } else {
+            &line.cells
+        };
+        let rendered_cells = self.text_renderer.render_cells(cells, theme_engine)?;
         
         Ok(RenderedLine {
             cells: rendered_cells,
@@ -71,7 +81,8 @@ impl TerminalRenderer {
             is_wrapped: line.wrapped,
             dirty: line.dirty,
             has_cursor,
-            text_spans: self.text_renderer.create_spans(&line.cells, theme_engine)?,
+            size: line.size,
+            text_spans: self.text_renderer.create_spans(cells, theme_engine)?,
         })
     }
     
@@ -86,6 +97,11 @@ impl TerminalRenderer {
         temp_renderer.create_rendered_cursor(cursor, theme_engine)
     }
     
+    /// Phases of blinking text
+    pub fn text_blink(&self) -> &TextBlinkScheduler {
+        self.text_renderer.text_blink()
+    }
+    
     /// Update renderer configuration
     pub fn update_config(&mut self, config: &GlazingConfig) -> GlazingResult<()> {
         self.config = config.renderer.clone();
@@ -124,15 +140,21 @@ impl TerminalRenderer {
 #[derive(Debug)]
 pub struct TextRenderer {
     config: RendererConfig,
+    text_blink: TextBlinkScheduler,
 }
 
 impl TextRenderer {
     pub fn new(config: &RendererConfig) -> GlazingResult<Self> {
         Ok(TextRenderer {
             config: config.clone(),
+            text_blink: TextBlinkScheduler::new(config.text_blink),
         })
     }
     
+    pub fn text_blink(&self) -> &TextBlinkScheduler {
+        &self.text_blink
+    }
+    
     /// Render terminal cells to display cells
     pub fn render_cells(
         &self,
@@ -140,6 +162,7 @@ impl TextRenderer {
         theme_engine: &ThemeEngine,
     ) -> GlazingResult<Vec<RenderedCell>> {
         let mut rendered_cells = Vec::with_capacity(cells.len());
+        let now = Instant::now();
         
         for (col, cell) in cells.iter().enumerate() {
             let style = theme_engine.convert_cell_style(&cell.attributes, cell.foreground, cell.background)?;
@@ -148,7 +171,7 @@ impl TextRenderer {
                 character: cell.character,
                 style: style.clone(),
                 position: CellPosition { col },
-                ratatui_style: self.create_ratatui_style(&style)?,
+                ratatui_style: self.create_ratatui_style(&style, now)?,
             });
         }
         
@@ -160,6 +183,16 @@ impl TextRenderer {
         &self,
         cells: &[Cell],
         theme_engine: &ThemeEngine,
+    ) -> GlazingResult<Vec<Span<'static>>> {
+        self.create_spans_at(cells, theme_engine, Instant::now())
+    }
+    
+    /// Create text spans with blinking text in its phase at `now`
+    pub fn create_spans_at(
+        &self,
+        cells: &[Cell],
+        theme_engine: &ThemeEngine,
+        now: Instant,
     ) -> GlazingResult<Vec<Span<'static>>> {
         let mut spans = Vec::new();
         let mut current_text = String::new();
@@ -171,7 +204,7 @@ impl TextRenderer {
                 cell.foreground,
                 cell.background,
             )?;
-            let ratatui_style = self.create_ratatui_style(&cell_style)?;
+            let ratatui_style = self.create_ratatui_style(&cell_style, now)?;
             
             // If style changed, flush current span and start new one
             if current_style.as_ref() != Some(&ratatui_style) {
@@ -199,7 +232,7 @@ impl TextRenderer {
     }
     
     /// Convert cell style to ratatui style
-    fn create_ratatui_style(&self, style: &CellStyle) -> GlazingResult<RatatuiStyle> {
+    fn create_ratatui_style(&self, style: &CellStyle, now: Instant) -> GlazingResult<RatatuiStyle> {
         let mut ratatui_style = RatatuiStyle::default()
             .fg(self.convert_color(style.foreground)?)
             .bg(self.convert_color(style.background)?);
@@ -212,8 +245,12 @@ impl TextRenderer {
         if style.italic {
             modifiers |= Modifier::ITALIC;
         }
+        // ratatui has no curly, dotted or dashed underlines, so every style draws as a plain one
         if style.underline {
             modifiers |= Modifier::UNDERLINED;
+            if let Some(color) = style.underline_color.filter(|_| self.config.underline_color) {
+                ratatui_style = ratatui_style.underline_color(self.convert_color(color)?);
+            }
         }
         if style.strikethrough {
             modifiers |= Modifier::CROSSED_OUT;
@@ -224,6 +261,10 @@ impl TextRenderer {
         if style.dim {
             modifiers |= Modifier::DIM;
         }
+        // Blinking is timed here rather than left to the host, so both rates look the same everywhere
+        if style.hidden || !self.text_blink.is_visible(style.blink, now) {
+            modifiers |= Modifier::HIDDEN;
+        }
         
         ratatui_style = ratatui_style.add_modifier(modifiers);
         
@@ -238,6 +279,7 @@ impl TextRenderer {
     /// Update text renderer configuration
     pub fn update_config(&mut self, config: &RendererConfig) -> GlazingResult<()> {
         self.config = config.clone();
+        self.text_blink.set_enabled(config.text_blink);
         Ok(())
     }
 }
@@ -265,7 +307,8 @@ impl CursorRenderer {
         cursor: &RenderedCursor,
         theme_engine: &ThemeEngine,
     ) -> GlazingResult<()> {
-        if !cursor.visible || !self.should_show_cursor() {
+        // Steady cursors ignore the blink phase
+        if !cursor.visible || (cursor.blinking && !self.should_show_cursor()) {
             return Ok(());
         }
         
@@ -291,11 +334,17 @@ impl CursorRenderer {
             CursorVisibility::BlinkingBar => self.should_show_cursor(),
         };
         
+        // The program's OSC 12 color wins over the configured override and the theme
+        let color = cursor.color
+            .or(self.config.color_override)
+            .unwrap_or_else(|| theme_engine.get_cursor_color());
+        
         Ok(RenderedCursor {
             position: cursor.position,
             style: cursor.style,
             visible,
-            color: theme_engine.get_cursor_color(),
+            blinking: cursor.is_blinking(),
+            color,
             blink_phase: self.blink_state.current_phase(),
         })
     }
@@ -341,17 +390,29 @@ impl CursorRenderer {
         cursor: &RenderedCursor,
         theme_engine: &ThemeEngine,
     ) -> GlazingResult<impl ratatui::widgets::Widget> {
-        let cursor_char = match cursor.style {
-            CursorStyle::Block => '█',
-            CursorStyle::Underline => '_',
-            CursorStyle::Bar => '│',
-        };
-        
         let style = RatatuiStyle::default()
             .fg(cursor.color.into())
             .bg(theme_engine.get_background_color());
         
-        Ok(Paragraph::new(cursor_char.to_string()).style(style))
+        Ok(Paragraph::new(cursor_glyph(cursor.style).to_string()).style(style))
+    }
+}
+
+/// Spread the first half of a line over its full width, padding each character with a blank
+fn double_cells(cells: &[Cell]) -> Vec<Cell> {
+    cells
+        .iter()
+        .take(cells.len() / 2)
+        .flat_map(|cell| [cell.clone(), Cell { character: ' ', ..cell.clone() }])
+        .collect()
+}
+
+/// Glyph drawn for each cursor shape: a full cell, its bottom edge or its left edge
+pub fn cursor_glyph(style: CursorStyle) -> char {
+    match style {
+        CursorStyle::Block => '█',
+        CursorStyle::Underline => '▁',
+        CursorStyle::Bar => '▏',
     }
 }
 
@@ -390,6 +451,7 @@ pub struct RenderedLine {
     pub is_wrapped: bool,
     pub dirty: bool,
     pub has_cursor: bool,
+    pub size: LineSize,
     pub text_spans: Vec<Span<'static>>,
 }
 
@@ -408,12 +470,27 @@ pub struct CellPosition {
     pub col: usize,
 }
 
+/// Image placement positioned relative to the viewport
+#[derive(Debug, Clone)]
+pub struct RenderedImage {
+    pub image: std::sync::Arc<crate::pane::RgbaImage>,
+    /// Top row relative to the first visible line (negative when partly scrolled off)
+    pub row: i64,
+    pub col: u16,
+    pub rows: u16,
+    pub cols: u16,
+    pub z_index: i32,
+    pub pixel_offset: (u16, u16),
+}
+
 /// Rendered cursor ready for display
 #[derive(Debug, Clone)]
 pub struct RenderedCursor {
     pub position: crate::pane::CursorPosition,
     pub style: CursorStyle,
     pub visible: bool,
+    /// Whether the program asked for a blinking cursor (DECSCUSR)
+    pub blinking: bool,
     pub color: crate::sash::Color,
     pub blink_phase: f32,
 }
@@ -426,9 +503,19 @@ pub struct CellStyle {
     pub bold: bool,
     pub italic: bool,
     pub underline: bool,
+    /// SGR 58 color; `None` underlines in the foreground color
+    pub underline_color: Option<crate::sash::Color>,
     pub strikethrough: bool,
     pub reverse: bool,
     pub dim: bool,
+    /// Concealed (SGR 8): the cell keeps its colors but shows no glyph
+    pub hidden: bool,
+    pub blink: BlinkType,
+    /// ratatui cannot draw overlines, frames, alternate fonts or super/subscript; they are kept for backends that can
+    pub overline: bool,
+    pub font: u8,
+    pub script: ScriptPosition,
+    pub frame: FrameType,
 }
 
 /// Cursor blink state tracking
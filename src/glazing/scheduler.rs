@@ -0,0 +1,134 @@
+use super::*;
+use crate::pane::BlinkType;
+use std::collections::HashSet;
+
+/// Paces presentation and holds panes whose program is mid-way through a synchronized update
+#[derive(Debug)]
+pub struct RenderScheduler {
+    /// Minimum time between frames when render batching is enabled
+    frame_interval: Duration,
+    last_present: Option<Instant>,
+    
+    /// Panes with an open synchronized update (mode 2026)
+    held_panes: HashSet<PaneId>,
+    
+    /// Last frame presented for each pane, shown again while the pane is held
+    presented: HashMap<PaneId, RenderFrame>,
+}
+
+impl RenderScheduler {
+    pub fn new(config: &GlazingConfig) -> Self {
+        RenderScheduler {
+            frame_interval: Self::frame_interval(config),
+            last_present: None,
+            held_panes: HashSet::new(),
+            presented: HashMap::new(),
+        }
+    }
+    
+    /// Hold or release a pane's presentation
+    pub fn set_held(&mut self, pane_id: PaneId, held: bool) {
+        if held {
+            self.held_panes.insert(pane_id);
+        } else {
+            self.held_panes.remove(&pane_id);
+        }
+    }
+    
+    pub fn is_held(&self, pane_id: PaneId) -> bool {
+        self.held_panes.contains(&pane_id)
+    }
+    
+    /// The frame to show again for a held pane, if it has been presented before
+    pub fn held_frame(&self, pane_id: PaneId) -> Option<&RenderFrame> {
+        if self.is_held(pane_id) {
+            self.presented.get(&pane_id)
+        } else {
+            None
+        }
+    }
+    
+    /// Whether enough time has passed since the last frame to present another
+    pub fn frame_due(&self, now: Instant) -> bool {
+        match self.last_present {
+            Some(last) => now.saturating_duration_since(last) >= self.frame_interval,
+            None => true,
+        }
+    }
+    
+    /// Remember a presented frame
+    pub fn record_present(&mut self, pane_id: PaneId, frame: RenderFrame, now: Instant) {
+        self.presented.insert(pane_id, frame);
+        self.last_present = Some(now);
+    }
+    
+    /// Forget everything about a pane (when it closes)
+    pub fn remove_pane(&mut self, pane_id: PaneId) {
+        self.held_panes.remove(&pane_id);
+        self.presented.remove(&pane_id);
+    }
+    
+    pub fn update_config(&mut self, config: &GlazingConfig) {
+        self.frame_interval = Self::frame_interval(config);
+    }
+    
+    fn frame_interval(config: &GlazingConfig) -> Duration {
+        if config.performance.render_batching {
+            Duration::from_secs(1) / config.renderer.target_fps.max(1)
+        } else {
+            Duration::ZERO
+        }
+    }
+}
+
+/// Length of each on and off phase: slow blinking stays under ECMA-48's 150 blinks a minute, rapid reaches it
+const SLOW_BLINK_INTERVAL: Duration = Duration::from_millis(500);
+const FAST_BLINK_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Phases of blinking text (SGR 5 and 6), on one clock so all text of a rate blinks in step
+#[derive(Debug, Clone, Copy)]
+pub struct TextBlinkScheduler {
+    epoch: Instant,
+    enabled: bool,
+}
+
+impl TextBlinkScheduler {
+    pub fn new(enabled: bool) -> Self {
+        TextBlinkScheduler {
+            epoch: Instant::now(),
+            enabled,
+        }
+    }
+    
+    /// Length of each phase, or `None` for steady text
+    pub fn interval(&self, blink: BlinkType) -> Option<Duration> {
+        match blink {
+            _ if !self.enabled => None,
+            BlinkType::None => None,
+            BlinkType::Slow => Some(SLOW_BLINK_INTERVAL),
+            BlinkType::Fast => Some(FAST_BLINK_INTERVAL),
+        }
+    }
+    
+    /// Whether text with this blink is drawn at `now`; every cycle starts visible
+    pub fn is_visible(&self, blink: BlinkType, now: Instant) -> bool {
+        match self.interval(blink) {
+            Some(interval) => self.phase(interval, now).is_multiple_of(2),
+            None => true,
+        }
+    }
+    
+    /// When text with this blink next changes phase after `now`
+    pub fn next_toggle(&self, blink: BlinkType, now: Instant) -> Option<Instant> {
+        let interval = self.interval(blink)?;
+        Some(self.epoch + interval * (self.phase(interval, now) + 1))
+    }
+    
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+    
+    fn phase(&self, interval: Duration, now: Instant) -> u32 {
+        (now.saturating_duration_since(self.epoch).as_millis() / interval.as_millis()) as u32
+    }
+}
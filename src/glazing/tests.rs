@@ -2,15 +2,20 @@
 mod glazing_tests {
     use super::*;
     use crate::sash::{PaneId, Theme};
-    use crate::pane::{ScreenBuffer, ScrollbackBuffer, Cursor, Line, Cell, CellAttributes};
+    use crate::pane::{
+        ScreenBuffer, ScrollbackBuffer, Cursor, Line, Cell, CellAttributes, ImageStore,
+        ImagePlacement, RgbaImage, CursorStyle, LineSize, UnderlineType, SgrColor, BlinkType,
+        ScriptPosition, FrameType
+    };
     use crate::glazing::{
         GlazingEngine, GlazingConfig, PerformanceTracker, Viewport, ScrollDirection,
         LayoutManager, SplitDirection, ThemeEngine, ThemeConfig, RendererConfig,
         TextQuality, FontConfig, FontWeight, FontStyle, CursorConfig,
         PerformanceConfig, FeatureConfig, GlazingEventHandler, GlazingEventType,
-        GlazingEvent, ViewportState, LayoutCacheStats
+        GlazingEvent, ViewportState, LayoutCacheStats, RenderScheduler, CursorRenderer,
+        TextBlinkScheduler, TextRenderer, cursor_glyph
     };
-    use std::time::Duration;
+    use std::time::{Duration, Instant};
 
     // Helper function to create a test glazing engine
     fn create_test_glazing_engine() -> GlazingEngine {
@@ -262,6 +267,14 @@ mod glazing_tests {
             blink: crate::pane::BlinkType::None,
             dim: false,
             invisible: false,
+            overline: false,
+            font: 0,
+            script: crate::pane::ScriptPosition::Normal,
+            frame: crate::pane::FrameType::None,
+            protected: false,
+            foreground: None,
+            background: None,
+            underline_color: None,
         };
         
         let fg = crate::sash::Color::from_rgb(255, 255, 255);
@@ -402,6 +415,78 @@ mod glazing_tests {
         assert!(renderer.is_ok());
     }
 
+    #[test]
+    fn test_underline_styles_and_colors_reach_the_host() {
+        use ratatui::style::{Color as RatatuiColor, Modifier};
+        
+        let theme_engine = ThemeEngine::new(&ThemeConfig::default()).unwrap();
+        let attributes = CellAttributes {
+            underline: UnderlineType::Curly,
+            underline_color: Some(SgrColor::Rgb(255, 0, 0)),
+            ..CellAttributes::default()
+        };
+        let cells = [Cell { character: 'x', attributes, ..Cell::default() }];
+        
+        // Curly underlines draw as plain ones, in the underline color when the host supports it
+        let config = RendererConfig { underline_color: true, ..RendererConfig::default() };
+        let renderer = TextRenderer::new(&config).unwrap();
+        let style = renderer.create_spans(&cells, &theme_engine).unwrap()[0].style;
+        assert!(style.add_modifier.contains(Modifier::UNDERLINED));
+        assert_eq!(style.underline_color, Some(RatatuiColor::Rgb(255, 0, 0)));
+        
+        let config = RendererConfig { underline_color: false, ..RendererConfig::default() };
+        let renderer = TextRenderer::new(&config).unwrap();
+        let style = renderer.create_spans(&cells, &theme_engine).unwrap()[0].style;
+        assert!(style.add_modifier.contains(Modifier::UNDERLINED));
+        assert_eq!(style.underline_color, None);
+    }
+
+    #[test]
+    fn test_text_blink_and_conceal() {
+        use ratatui::style::Modifier;
+        
+        let theme_engine = ThemeEngine::new(&ThemeConfig::default()).unwrap();
+        // Distinct styles keep each cell in its own span
+        let slow = CellAttributes { blink: BlinkType::Slow, bold: true, overline: true, ..CellAttributes::default() };
+        let fast = CellAttributes { blink: BlinkType::Fast, italic: true, ..CellAttributes::default() };
+        let concealed = CellAttributes { invisible: true, ..CellAttributes::default() };
+        let cells = [slow, fast, concealed].map(|attributes| Cell { character: 'x', attributes, ..Cell::default() });
+        let renderer = TextRenderer::new(&RendererConfig::default()).unwrap();
+        let blink = *renderer.text_blink();
+        let hidden_at = |now| -> Vec<bool> {
+            renderer
+                .create_spans_at(&cells, &theme_engine, now)
+                .unwrap()
+                .iter()
+                .map(|span| span.style.add_modifier.contains(Modifier::HIDDEN))
+                .collect()
+        };
+        
+        // Blinking cells hide in their off phase, and concealed text never shows
+        let expected = |now| [!blink.is_visible(BlinkType::Slow, now), !blink.is_visible(BlinkType::Fast, now), true];
+        let now = Instant::now();
+        assert_eq!(hidden_at(now), expected(now));
+        let toggle = blink.next_toggle(BlinkType::Fast, now).unwrap();
+        assert_ne!(blink.is_visible(BlinkType::Fast, toggle), blink.is_visible(BlinkType::Fast, now));
+        assert_eq!(hidden_at(toggle), expected(toggle));
+        
+        // Rapid blink toggles every 200ms and slow every 500ms
+        assert_eq!(blink.next_toggle(BlinkType::Fast, toggle), Some(toggle + Duration::from_millis(200)));
+        let slow_toggle = blink.next_toggle(BlinkType::Slow, now).unwrap();
+        assert_eq!(blink.next_toggle(BlinkType::Slow, slow_toggle), Some(slow_toggle + Duration::from_millis(500)));
+        assert_eq!(blink.next_toggle(BlinkType::None, now), None);
+        
+        // With text blinking off, blinking cells stay drawn
+        let mut steady = TextBlinkScheduler::new(false);
+        assert!(steady.is_visible(BlinkType::Fast, toggle));
+        steady.set_enabled(true);
+        assert!(steady.interval(BlinkType::Fast).is_some());
+        
+        let style = theme_engine.convert_cell_style(&cells[0].attributes, cells[0].foreground, cells[0].background).unwrap();
+        assert_eq!(style.blink, BlinkType::Slow);
+        assert!(style.overline);
+    }
+
     #[test]
     fn test_cursor_renderer_creation() {
         let config = CursorConfig::default();
@@ -409,6 +494,34 @@ mod glazing_tests {
         assert!(renderer.is_ok());
     }
 
+    #[test]
+    fn test_cursor_renderer_uses_program_shape_and_color() {
+        let theme_engine = ThemeEngine::new(&ThemeConfig::default()).unwrap();
+        let mut renderer = CursorRenderer::new(&CursorConfig::default()).unwrap();
+        let mut cursor = create_test_cursor();
+        
+        // A steady bar stays visible regardless of the blink phase
+        cursor.set_shape(CursorStyle::Bar, false);
+        let rendered = renderer.create_rendered_cursor(&cursor, &theme_engine).unwrap();
+        assert_eq!(rendered.style, CursorStyle::Bar);
+        assert!(rendered.visible);
+        assert!(!rendered.blinking);
+        assert_eq!(rendered.color, theme_engine.get_cursor_color());
+        
+        // OSC 12 overrides the theme color
+        let orange = crate::sash::Color::from_rgb(255, 128, 0);
+        cursor.color = Some(orange);
+        cursor.set_shape(CursorStyle::Underline, true);
+        let rendered = renderer.create_rendered_cursor(&cursor, &theme_engine).unwrap();
+        assert_eq!(rendered.style, CursorStyle::Underline);
+        assert!(rendered.blinking);
+        assert_eq!(rendered.color, orange);
+        
+        assert_eq!(cursor_glyph(CursorStyle::Block), '█');
+        assert_eq!(cursor_glyph(CursorStyle::Underline), '▁');
+        assert_eq!(cursor_glyph(CursorStyle::Bar), '▏');
+    }
+
     #[test]
     fn test_blink_state() {
         let rate = Duration::from_millis(100);
@@ -431,9 +544,16 @@ mod glazing_tests {
             bold: true,
             italic: false,
             underline: false,
+            underline_color: None,
             strikethrough: false,
             reverse: false,
             dim: false,
+            hidden: false,
+            blink: BlinkType::None,
+            overline: false,
+            font: 0,
+            script: ScriptPosition::Normal,
+            frame: FrameType::None,
         };
         
         let style2 = style1.clone();
@@ -577,6 +697,160 @@ mod glazing_tests {
         assert_eq!(render_frame.pane_id, PaneId::new(1));
     }
 
+    #[test]
+    fn test_render_frame_doubles_wide_lines() {
+        let engine = create_test_glazing_engine();
+        let mut screen_buffer = create_test_screen_buffer();
+        let attrs = CellAttributes::default();
+        screen_buffer.write_char_at(0, 0, 'A', &attrs).unwrap();
+        screen_buffer.write_char_at(0, 1, 'B', &attrs).unwrap();
+        screen_buffer.set_line_size(0, LineSize::DoubleWidth);
+        screen_buffer.write_char_at(1, 1, 'C', &attrs).unwrap();
+        let mut cursor = create_test_cursor();
+        cursor.set_position(1, 2, &screen_buffer).unwrap();
+        
+        let frame = engine.create_render_frame(
+            &screen_buffer,
+            &create_test_scrollback_buffer(),
+            &cursor,
+            PaneId::new(1),
+            true,
+        ).unwrap();
+        
+        let wide = &frame.content[0];
+        assert_eq!(wide.size, LineSize::DoubleWidth);
+        assert_eq!(wide.cells.len(), 80);
+        let text: String = wide.cells.iter().take(4).map(|cell| cell.character).collect();
+        assert_eq!(text, "A B ");
+        assert_eq!(frame.content[1].cells[1].character, 'C');
+        
+        // The cursor sits on the doubled cell
+        assert_eq!(frame.cursor.unwrap().position.col, 2);
+    }
+
+    #[test]
+    fn test_render_frame_includes_shared_images() {
+        let mut engine = create_test_glazing_engine();
+        let store = ImageStore::new();
+        {
+            let mut layer = store.write();
+            let visible = layer.add_image(RgbaImage::new(8, 16), None).unwrap();
+            let below = layer.add_image(RgbaImage::new(8, 16), None).unwrap();
+            layer.place(ImagePlacement::new(visible, 2, 4, 1, 1).with_z_index(1));
+            layer.place(ImagePlacement::new(below, 3, 0, 2, 2).with_z_index(-1));
+            layer.place(ImagePlacement::new(visible, 40, 0, 1, 1));
+        }
+        engine.attach_image_store(PaneId::new(1), store.clone());
+        
+        let frame = engine.create_render_frame(
+            &create_test_screen_buffer(),
+            &create_test_scrollback_buffer(),
+            &create_test_cursor(),
+            PaneId::new(1),
+            true,
+        ).unwrap();
+        
+        // Off-screen placements are skipped and the rest sorted by z-index
+        assert_eq!(frame.images.len(), 2);
+        assert_eq!((frame.images[0].row, frame.images[0].z_index), (3, -1));
+        assert_eq!((frame.images[1].row, frame.images[1].col), (2, 4));
+        
+        engine.detach_image_store(PaneId::new(1));
+        let frame = engine.create_render_frame(
+            &create_test_screen_buffer(),
+            &create_test_scrollback_buffer(),
+            &create_test_cursor(),
+            PaneId::new(1),
+            true,
+        ).unwrap();
+        assert!(frame.images.is_empty());
+    }
+
+    #[test]
+    fn test_synchronized_pane_keeps_last_frame() {
+        let mut engine = create_test_glazing_engine();
+        let pane = PaneId::new(1);
+        let frame = engine.create_render_frame(
+            &create_test_screen_buffer(),
+            &create_test_scrollback_buffer(),
+            &create_test_cursor(),
+            pane,
+            true,
+        ).unwrap();
+        
+        // Nothing to hold until the pane has been presented once
+        engine.set_synchronized_update(pane, true);
+        assert!(engine.scheduler.held_frame(pane).is_none());
+        
+        engine.scheduler.record_present(pane, frame, Instant::now());
+        assert!(engine.scheduler.held_frame(pane).is_some());
+        assert!(engine.scheduler.held_frame(PaneId::new(2)).is_none());
+        
+        engine.set_synchronized_update(pane, false);
+        assert!(engine.scheduler.held_frame(pane).is_none());
+        
+        engine.set_synchronized_update(pane, true);
+        engine.remove_pane(pane);
+        assert!(!engine.scheduler.is_held(pane));
+    }
+
+    #[test]
+    fn test_pane_synchronized_update_holds_rendering() {
+        use crate::pane::{Pane, PaneConfig};
+        use ratatui::{backend::TestBackend, Terminal};
+        
+        let mut engine = create_test_glazing_engine();
+        let pane_id = PaneId::new(1);
+        let mut pane = Pane::new(pane_id, PaneConfig::default().with_size(20, 4)).unwrap();
+        let mut terminal = Terminal::new(TestBackend::new(20, 4)).unwrap();
+        let mut render = |engine: &mut GlazingEngine, pane: &Pane| {
+            terminal.draw(|frame| {
+                engine.render_pane_state::<TestBackend>(frame, frame.area(), pane, true).unwrap();
+            }).unwrap();
+        };
+        
+        pane.process_output(b"before").unwrap();
+        render(&mut engine, &pane);
+        assert!(!engine.scheduler.is_held(pane_id));
+        
+        // Output inside the batch is not shown until the program closes it
+        pane.process_output(b"\x1b[?2026h\rduring").unwrap();
+        render(&mut engine, &pane);
+        assert!(engine.scheduler.is_held(pane_id));
+        let held = engine.scheduler.held_frame(pane_id).unwrap();
+        assert_eq!(held.content[0].cells[0].character, 'b');
+        
+        pane.process_output(b"\x1b[?2026l").unwrap();
+        render(&mut engine, &pane);
+        assert!(!engine.scheduler.is_held(pane_id));
+        assert_eq!(engine.current_frame.as_ref().unwrap().content[0].cells[0].character, 'd');
+    }
+
+    #[test]
+    fn test_render_scheduler_frame_pacing() {
+        let mut config = GlazingConfig::default();
+        config.renderer.target_fps = 50;
+        let mut scheduler = RenderScheduler::new(&config);
+        let frame = create_test_glazing_engine().create_render_frame(
+            &create_test_screen_buffer(),
+            &create_test_scrollback_buffer(),
+            &create_test_cursor(),
+            PaneId::new(1),
+            true,
+        ).unwrap();
+        
+        let now = Instant::now();
+        assert!(scheduler.frame_due(now));
+        scheduler.record_present(PaneId::new(1), frame, now);
+        assert!(!scheduler.frame_due(now + Duration::from_millis(10)));
+        assert!(scheduler.frame_due(now + Duration::from_millis(20)));
+        
+        // Without batching every frame is due
+        config.performance.render_batching = false;
+        scheduler.update_config(&config);
+        assert!(scheduler.frame_due(now));
+    }
+
     #[test]
     fn test_scrollbar_info() {
         let mut viewport = Viewport::new();
@@ -78,9 +78,16 @@ impl ThemeEngine {
             bold: attributes.bold,
             italic: attributes.italic,
             underline: attributes.underline != crate::pane::UnderlineType::None,
+            underline_color: attributes.underline_color.map(crate::pane::SgrColor::to_color),
             strikethrough: attributes.strikethrough,
             reverse: false, // Already applied above
             dim: attributes.dim,
+            hidden: attributes.invisible,
+            blink: attributes.blink,
+            overline: attributes.overline,
+            font: attributes.font,
+            script: attributes.script,
+            frame: attributes.frame,
         })
     }
     
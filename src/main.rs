@@ -11,6 +11,12 @@ use anyhow::Result;
 use frame::{Frame, FrameInterface};
 
 fn main() -> Result<()> {
+    // Print the terminfo entries for `tic` instead of starting the emulator
+    if std::env::args().nth(1).as_deref() == Some("--print-terminfo") {
+        print!("{}", pane::terminfo_source());
+        return Ok(());
+    }
+    
     println!("Starting Ratty - A Rust Terminal Emulator");
     
     // Initialize the Frame (top-level application coordinator)
@@ -1,6 +1,6 @@
 use super::*;
 use std::collections::VecDeque;
-use crate::sash::{Color, Theme};
+use crate::sash::{Color, ColorScheme, Theme};
 
 /// Screen buffer containing the currently visible terminal content
 #[derive(Debug, Clone)]
@@ -9,6 +9,10 @@ pub struct ScreenBuffer {
     pub width: u16,
     pub height: u16,
     pub dirty_regions: DirtyTracker,
+    /// Scrolling region (DECSTBM/DECSLRM)
+    pub margins: ScrollMargins,
+    /// Row moves not yet applied to the image layer
+    pub(crate) row_shifts: Vec<RowShift>,
 }
 
 impl ScreenBuffer {
@@ -24,6 +28,8 @@ impl ScreenBuffer {
             width,
             height,
             dirty_regions: DirtyTracker::new(width, height),
+            margins: ScrollMargins::full(width, height),
+            row_shifts: Vec::new(),
         }
     }
     
@@ -50,11 +56,44 @@ impl ScreenBuffer {
         self.width = new_width;
         self.height = new_height;
         self.dirty_regions = DirtyTracker::new(new_width, new_height);
+        self.reset_margins();
         self.mark_all_dirty();
         
         Ok(())
     }
     
+    /// Columns usable on a row: double-width lines hold half as many characters
+    pub fn line_width(&self, row: u16) -> u16 {
+        match self.lines.get(row as usize) {
+            Some(line) if line.size.is_double_width() => (self.width / 2).max(1),
+            _ => self.width,
+        }
+    }
+    
+    /// Change a line's size (DECSWL/DECDWL/DECDHL); text past the new right edge is lost
+    pub fn set_line_size(&mut self, row: u16, size: LineSize) {
+        let half = (self.width / 2).max(1);
+        if let Some(line) = self.lines.get_mut(row as usize) {
+            if size.is_double_width() && !line.size.is_double_width() {
+                line.clear_from(half);
+            }
+            line.size = size;
+            line.dirty = true;
+            self.dirty_regions.mark_line_dirty(row);
+        }
+    }
+    
+    /// Fill the screen with `E` on single-size lines (DECALN)
+    pub fn fill_alignment_pattern(&mut self) {
+        let filled = Cell { character: 'E', ..Cell::default() };
+        for line in &mut self.lines {
+            line.cells.fill(filled.clone());
+            line.size = LineSize::Single;
+            line.dirty = true;
+        }
+        self.mark_all_dirty();
+    }
+    
     /// Write a character at the cursor position
     pub fn write_char_at_cursor(&mut self, ch: char, cursor: &Cursor, modes: &TerminalModes) -> PaneResult<()> {
         let pos = cursor.position;
@@ -68,8 +107,9 @@ impl ScreenBuffer {
             return Err(PaneError::InvalidCursorPosition(row, col));
         }
         
+        let line_width = self.line_width(row);
         let line = &mut self.lines[row as usize];
-        if col < self.width {
+        if col < line_width {
             line.write_char(col, ch, attrs.clone());
             self.dirty_regions.mark_cell_dirty(row, col);
         }
@@ -78,13 +118,15 @@ impl ScreenBuffer {
     }
     
     /// Clear the entire screen
-    pub fn clear_screen(&mut self, clear_type: ClearType, cursor: &mut Cursor) -> PaneResult<()> {
+    pub fn clear_screen(&mut self, clear_type: ClearType, cursor: &Cursor) -> PaneResult<()> {
         match clear_type {
             ClearType::All => {
                 for line in &mut self.lines {
                     line.clear();
+                    line.clear_zone_markers();
+                    line.marked = false;
+                    line.size = LineSize::Single;
                 }
-                cursor.set_position(0, 0, self)?;
             }
             ClearType::ToEnd => {
                 // Clear from cursor to end of screen
@@ -97,7 +139,7 @@ impl ScreenBuffer {
                 }
                 
                 // Clear all lines below
-                for line in &mut self.lines[(cursor_row + 1)..] {
+                for line in self.lines.iter_mut().skip(cursor_row + 1) {
                     line.clear();
                 }
             }
@@ -107,7 +149,7 @@ impl ScreenBuffer {
                 let cursor_col = cursor.position.col;
                 
                 // Clear all lines above
-                for line in &mut self.lines[..cursor_row] {
+                for line in self.lines.iter_mut().take(cursor_row) {
                     line.clear();
                 }
                 
@@ -140,18 +182,21 @@ impl ScreenBuffer {
         Ok(())
     }
     
-    /// Insert blank lines at the cursor position
-    pub fn insert_lines(&mut self, count: u16, cursor: &Cursor) -> PaneResult<()> {
-        let start_row = cursor.position.row as usize;
-        if start_row >= self.lines.len() {
-            return Ok(());
-        }
-        
-        // Remove lines from the bottom and insert blank lines at cursor
-        for _ in 0..count.min(self.height - cursor.position.row) {
-            if self.lines.len() > start_row {
-                self.lines.remove(self.lines.len() - 1);
-                self.lines.insert(start_row, Line::new(self.width));
+    /// Erase unprotected characters in the display, keeping their attributes (DECSED)
+    pub fn selective_clear_screen(&mut self, clear_type: ClearType, cursor: &Cursor) -> PaneResult<()> {
+        let CursorPosition { row, col, .. } = cursor.position;
+        let last_col = self.width.saturating_sub(1);
+        for (index, line) in self.lines.iter_mut().enumerate() {
+            let index = index as u16;
+            let span = match clear_type {
+                ClearType::All => Some((0, last_col)),
+                ClearType::ToEnd if index == row => Some((col, last_col)),
+                ClearType::ToEnd => (index > row).then_some((0, last_col)),
+                ClearType::ToBeginning if index == row => Some((0, col)),
+                ClearType::ToBeginning => (index < row).then_some((0, last_col)),
+            };
+            if let Some((from_col, to_col)) = span {
+                line.selective_clear(from_col, to_col);
             }
         }
         
@@ -159,22 +204,40 @@ impl ScreenBuffer {
         Ok(())
     }
     
-    /// Delete lines at the cursor position
-    pub fn delete_lines(&mut self, count: u16, cursor: &Cursor) -> PaneResult<()> {
-        let start_row = cursor.position.row as usize;
-        if start_row >= self.lines.len() {
+    /// Erase unprotected characters in the cursor line, keeping their attributes (DECSEL)
+    pub fn selective_clear_line(&mut self, clear_type: ClearType, cursor: &Cursor) -> PaneResult<()> {
+        let CursorPosition { row, col, .. } = cursor.position;
+        let last_col = self.width.saturating_sub(1);
+        let Some(line) = self.lines.get_mut(row as usize) else {
             return Ok(());
-        }
+        };
         
-        // Remove lines at cursor position and add blank lines at bottom
-        for _ in 0..count.min(self.height - cursor.position.row) {
-            if start_row < self.lines.len() {
-                self.lines.remove(start_row);
-                self.lines.push(Line::new(self.width));
-            }
+        match clear_type {
+            ClearType::All => line.selective_clear(0, last_col),
+            ClearType::ToEnd => line.selective_clear(col, last_col),
+            ClearType::ToBeginning => line.selective_clear(0, col),
         }
         
-        self.mark_all_dirty();
+        self.dirty_regions.mark_line_dirty(row);
+        Ok(())
+    }
+    
+    /// Insert blank lines at the cursor position, pushing lines down to the bottom margin (IL)
+    pub fn insert_lines(&mut self, count: u16, cursor: &Cursor) -> PaneResult<()> {
+        let CursorPosition { row, col, .. } = cursor.position;
+        // Outside the margins there is nothing to scroll
+        if self.margins.contains(row, col) {
+            self.insert_rows(row, count);
+        }
+        Ok(())
+    }
+    
+    /// Delete lines at the cursor position, pulling blank lines in at the bottom margin (DL)
+    pub fn delete_lines(&mut self, count: u16, cursor: &Cursor) -> PaneResult<()> {
+        let CursorPosition { row, col, .. } = cursor.position;
+        if self.margins.contains(row, col) {
+            self.remove_rows(row, count);
+        }
         Ok(())
     }
     
@@ -211,6 +274,30 @@ pub struct Line {
     pub wrapped: bool,
     pub dirty: bool,
     pub timestamp: Option<std::time::Instant>,
+    pub zone_markers: Vec<ZoneMarker>,
+    /// Set by iTerm2's `SetMark` for mark-to-mark navigation
+    pub marked: bool,
+    /// Double-width or double-height rendering (ESC # 3/4/5/6)
+    pub size: LineSize,
+}
+
+/// Line size attribute set by DECSWL, DECDWL and DECDHL
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LineSize {
+    #[default]
+    Single,
+    DoubleWidth,
+    /// Top half of a double-height line
+    DoubleHeightTop,
+    /// Bottom half of a double-height line
+    DoubleHeightBottom,
+}
+
+impl LineSize {
+    /// Every size but single is also double-width
+    pub fn is_double_width(self) -> bool {
+        self != LineSize::Single
+    }
 }
 
 impl Line {
@@ -221,6 +308,9 @@ impl Line {
             wrapped: false,
             dirty: true,
             timestamp: Some(std::time::Instant::now()),
+            zone_markers: Vec::new(),
+            marked: false,
+            size: LineSize::Single,
         }
     }
     
@@ -240,8 +330,10 @@ impl Line {
     pub fn write_char(&mut self, col: u16, ch: char, attrs: CellAttributes) {
         if let Some(cell) = self.cells.get_mut(col as usize) {
             cell.character = ch;
+            let blank = Cell::default();
+            cell.foreground = attrs.foreground.map_or(blank.foreground, SgrColor::to_color);
+            cell.background = attrs.background.map_or(blank.background, SgrColor::to_color);
             cell.attributes = attrs;
-            // TODO: Set colors from current attributes/theme
             self.dirty = true;
             self.timestamp = Some(std::time::Instant::now());
         }
@@ -252,6 +344,7 @@ impl Line {
         for cell in &mut self.cells {
             *cell = Cell::default();
         }
+        self.wrapped = false;
         self.dirty = true;
         self.timestamp = Some(std::time::Instant::now());
     }
@@ -262,6 +355,20 @@ impl Line {
             for cell in cells_to_clear {
                 *cell = Cell::default();
             }
+            // Nothing is left to continue onto the next line
+            self.wrapped = false;
+            self.dirty = true;
+            self.timestamp = Some(std::time::Instant::now());
+        }
+    }
+    
+    /// Blank unprotected characters between two columns (inclusive), keeping their attributes
+    pub fn selective_clear(&mut self, from_col: u16, to_col: u16) {
+        let end_idx = (to_col as usize + 1).min(self.cells.len());
+        if let Some(cells_to_clear) = self.cells.get_mut(from_col as usize..end_idx) {
+            for cell in cells_to_clear.iter_mut().filter(|cell| !cell.attributes.protected) {
+                cell.character = ' ';
+            }
             self.dirty = true;
             self.timestamp = Some(std::time::Instant::now());
         }
@@ -288,6 +395,21 @@ impl Line {
     pub fn is_empty(&self) -> bool {
         self.cells.iter().all(|cell| cell.is_default())
     }
+    
+    /// Attach a semantic zone marker to this line
+    pub fn add_zone_marker(&mut self, marker: ZoneMarker) {
+        self.zone_markers.push(marker);
+    }
+    
+    /// Check if the line carries a marker matching the predicate
+    pub fn has_zone_marker(&self, matches: impl Fn(SemanticMark) -> bool) -> bool {
+        self.zone_markers.iter().any(|marker| matches(marker.mark))
+    }
+    
+    /// Remove all semantic zone markers from this line
+    pub fn clear_zone_markers(&mut self) {
+        self.zone_markers.clear();
+    }
 }
 
 /// A single character cell in the terminal
@@ -341,6 +463,20 @@ pub struct CellAttributes {
     pub reverse: bool,
     pub blink: BlinkType,
     pub invisible: bool,
+    pub overline: bool,
+    /// SGR 10-20: 0 is the primary font, 1-9 the alternates and 10 Fraktur
+    pub font: u8,
+    /// SGR 73-75
+    pub script: ScriptPosition,
+    /// SGR 51/52/54
+    pub frame: FrameType,
+    /// Set by DECSCA; selective erases skip protected cells
+    pub protected: bool,
+    /// SGR colors; `None` is the default color
+    pub foreground: Option<SgrColor>,
+    pub background: Option<SgrColor>,
+    /// SGR 58; `None` draws underlines in the foreground color
+    pub underline_color: Option<SgrColor>,
 }
 
 impl Default for CellAttributes {
@@ -354,6 +490,57 @@ impl Default for CellAttributes {
             reverse: false,
             blink: BlinkType::None,
             invisible: false,
+            overline: false,
+            font: 0,
+            script: ScriptPosition::Normal,
+            frame: FrameType::None,
+            protected: false,
+            foreground: None,
+            background: None,
+            underline_color: None,
+        }
+    }
+}
+
+/// A color selected by SGR, resolved to RGB when a cell is written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SgrColor {
+    /// 0-15 are the ANSI colors, 16-231 the 6x6x6 cube and 232-255 the gray ramp
+    Indexed(u8),
+    Rgb(u8, u8, u8),
+}
+
+impl SgrColor {
+    pub fn to_color(self) -> Color {
+        match self {
+            SgrColor::Indexed(index @ 0..=15) => ColorScheme::default().ansi_colors[index as usize],
+            SgrColor::Indexed(index @ 16..=231) => {
+                let level = |n: u8| if n == 0 { 0 } else { 55 + n * 40 };
+                let cube = index - 16;
+                Color::from_rgb(level(cube / 36), level(cube / 6 % 6), level(cube % 6))
+            }
+            SgrColor::Indexed(index) => {
+                let gray = 8 + (index - 232) * 10;
+                Color::from_rgb(gray, gray, gray)
+            }
+            SgrColor::Rgb(r, g, b) => Color::from_rgb(r, g, b),
+        }
+    }
+    
+    /// SGR parameters selecting this color, with `base` 30 for foreground or 40 for background
+    pub fn sgr_params(self, base: u8) -> String {
+        match self {
+            SgrColor::Indexed(index @ 0..=7) => (base + index).to_string(),
+            SgrColor::Indexed(index @ 8..=15) => (base + 60 + index - 8).to_string(),
+            _ => self.extended_sgr_params(base + 8),
+        }
+    }
+    
+    /// The `5;n` or `2;r;g;b` form after `selector` (38, 48 or 58)
+    pub fn extended_sgr_params(self, selector: u8) -> String {
+        match self {
+            SgrColor::Indexed(index) => format!("{};5;{}", selector, index),
+            SgrColor::Rgb(r, g, b) => format!("{};2;{};{};{}", selector, r, g, b),
         }
     }
 }
@@ -375,12 +562,28 @@ pub enum BlinkType {
     Fast,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScriptPosition {
+    Normal,
+    Superscript,
+    Subscript,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FrameType {
+    None,
+    Framed,
+    Encircled,
+}
+
 /// Scrollback buffer for storing terminal history
 #[derive(Debug, Clone)]
 pub struct ScrollbackBuffer {
     pub lines: VecDeque<Line>,
     pub max_lines: usize,
     pub current_size: usize,
+    /// Total lines ever pushed; never reset, so it doubles as an absolute row origin
+    pub lines_pushed: u64,
 }
 
 impl ScrollbackBuffer {
@@ -390,6 +593,7 @@ impl ScrollbackBuffer {
             lines: VecDeque::with_capacity(max_lines),
             max_lines,
             current_size: 0,
+            lines_pushed: 0,
         }
     }
     
@@ -401,6 +605,7 @@ impl ScrollbackBuffer {
             self.current_size += 1;
         }
         self.lines.push_back(line);
+        self.lines_pushed += 1;
     }
     
     /// Get a line from the scrollback buffer
@@ -418,6 +623,11 @@ impl ScrollbackBuffer {
         self.lines.is_empty()
     }
     
+    /// Absolute row of the oldest line still held in scrollback
+    pub fn first_absolute_row(&self) -> u64 {
+        self.lines_pushed - self.lines.len() as u64
+    }
+    
     /// Clear the scrollback buffer
     pub fn clear(&mut self) {
         self.lines.clear();
@@ -459,6 +669,17 @@ pub enum ClearType {
     ToBeginning,
 }
 
+impl ClearType {
+    /// The erase extent selected by an ED/EL parameter
+    pub fn from_param(param: i32) -> Self {
+        match param {
+            0 => ClearType::ToEnd,
+            1 => ClearType::ToBeginning,
+            _ => ClearType::All,
+        }
+    }
+}
+
 /// Track dirty regions for efficient rendering
 #[derive(Debug, Clone)]
 pub struct DirtyTracker {
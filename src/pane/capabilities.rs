@@ -0,0 +1,379 @@
+use super::*;
+
+/// TERM for programs started in a pane, once the entry from `ratty --print-terminfo` is installed
+pub const TERM_NAME: &str = "ratty";
+
+/// TERM when the `ratty` entry is not installed; xterm's entry describes a subset of what ratty handles
+pub const FALLBACK_TERM_NAME: &str = "xterm-256color";
+
+/// Value of a terminfo capability
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CapabilityValue {
+    Flag,
+    Number(u32),
+    /// Terminfo source notation (`\E`, `^X`, `%p1%d`...)
+    String(&'static str),
+}
+
+/// A terminfo capability the emulator implements
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Capability {
+    pub name: &'static str,
+    /// Two-letter termcap name, for capabilities that have one
+    pub termcap: Option<&'static str>,
+    pub value: CapabilityValue,
+}
+
+impl Capability {
+    const fn flag(name: &'static str, termcap: &'static str) -> Self {
+        Capability { name, termcap: Some(termcap), value: CapabilityValue::Flag }
+    }
+    
+    const fn number(name: &'static str, termcap: &'static str, value: u32) -> Self {
+        Capability { name, termcap: Some(termcap), value: CapabilityValue::Number(value) }
+    }
+    
+    const fn string(name: &'static str, termcap: &'static str, value: &'static str) -> Self {
+        Capability { name, termcap: Some(termcap), value: CapabilityValue::String(value) }
+    }
+    
+    /// A user-defined (extended) flag with no termcap name
+    const fn extended_flag(name: &'static str) -> Self {
+        Capability { name, termcap: None, value: CapabilityValue::Flag }
+    }
+    
+    /// A user-defined (extended) string capability with no termcap name
+    const fn extended(name: &'static str, value: &'static str) -> Self {
+        Capability { name, termcap: None, value: CapabilityValue::String(value) }
+    }
+    
+    /// The value as programs receive it, or `None` for flags
+    pub fn wire_value(&self) -> Option<Vec<u8>> {
+        match self.value {
+            CapabilityValue::Flag => None,
+            CapabilityValue::Number(value) => Some(value.to_string().into_bytes()),
+            CapabilityValue::String(source) => Some(decode_terminfo_string(source)),
+        }
+    }
+}
+
+/// Everything the parser and key encoder implement, described as terminfo
+///
+/// XTGETTCAP answers from this table, so add an entry only together with the sequence it describes.
+pub const CAPABILITIES: &[Capability] = &[
+    Capability::flag("am", "am"),
+    Capability::flag("xenl", "xn"),
+    
+    // DA1 request, and the shape of the answer `primary_device_attributes` builds
+    Capability::string("u8", "u8", "\\E[?%[;0123456789]c"),
+    Capability::string("u9", "u9", "\\E[c"),
+    Capability::number("it", "it", 8),
+    
+    // Cursor movement
+    Capability::string("cup", "cm", "\\E[%i%p1%d;%p2%dH"),
+    Capability::string("home", "ho", "\\E[H"),
+    Capability::string("cuu1", "up", "\\E[A"),
+    Capability::string("cud1", "do", "^J"),
+    Capability::string("cuf1", "nd", "\\E[C"),
+    Capability::string("cub1", "le", "^H"),
+    Capability::string("cuu", "UP", "\\E[%p1%dA"),
+    Capability::string("cud", "DO", "\\E[%p1%dB"),
+    Capability::string("cuf", "RI", "\\E[%p1%dC"),
+    Capability::string("cub", "LE", "\\E[%p1%dD"),
+    Capability::string("cr", "cr", "^M"),
+    Capability::string("ht", "ta", "^I"),
+    Capability::string("ind", "sf", "^J"),
+    Capability::string("ri", "sr", "\\EM"),
+    Capability::string("sc", "sc", "\\E7"),
+    Capability::string("rc", "rc", "\\E8"),
+    
+    // Editing and scrolling
+    Capability::string("clear", "cl", "\\E[H\\E[2J"),
+    Capability::string("ed", "cd", "\\E[J"),
+    Capability::string("el", "ce", "\\E[K"),
+    Capability::string("el1", "cb", "\\E[1K"),
+    Capability::string("il1", "al", "\\E[L"),
+    Capability::string("il", "AL", "\\E[%p1%dL"),
+    Capability::string("dl1", "dl", "\\E[M"),
+    Capability::string("dl", "DL", "\\E[%p1%dM"),
+    Capability::string("ich", "IC", "\\E[%p1%d@"),
+    Capability::string("dch1", "dc", "\\E[P"),
+    Capability::string("dch", "DC", "\\E[%p1%dP"),
+    Capability::string("csr", "cs", "\\E[%i%p1%d;%p2%dr"),
+    Capability::string("indn", "SF", "\\E[%p1%dS"),
+    Capability::string("rin", "SR", "\\E[%p1%dT"),
+    Capability::string("smcup", "ti", "\\E[?1049h"),
+    Capability::string("rmcup", "te", "\\E[?1049l"),
+    
+    // Attributes
+    Capability::string("sgr0", "me", "\\E[m"),
+    Capability::string("bold", "md", "\\E[1m"),
+    Capability::string("dim", "mh", "\\E[2m"),
+    Capability::string("sitm", "ZH", "\\E[3m"),
+    Capability::string("ritm", "ZR", "\\E[23m"),
+    Capability::string("smul", "us", "\\E[4m"),
+    Capability::string("rmul", "ue", "\\E[24m"),
+    Capability::extended("Smulx", "\\E[4:%p1%dm"),
+    Capability::extended("Setulc", "\\E[58:2::%p1%{65536}%/%d:%p1%{256}%/%{255}%&%d:%p1%{255}%&%dm"),
+    Capability::string("blink", "mb", "\\E[5m"),
+    Capability::string("rev", "mr", "\\E[7m"),
+    Capability::string("smso", "so", "\\E[7m"),
+    Capability::string("rmso", "se", "\\E[27m"),
+    Capability::string("invis", "mk", "\\E[8m"),
+    Capability::extended("smxx", "\\E[9m"),
+    Capability::extended("rmxx", "\\E[29m"),
+    
+    // Colors: 8 ANSI, 8 bright, then the 256-color palette
+    Capability::number("colors", "Co", 256),
+    Capability::number("pairs", "pa", 0x10000),
+    Capability::string("op", "op", "\\E[39;49m"),
+    Capability::string("setaf", "AF", "\\E[%?%p1%{8}%<%t3%p1%d%e%p1%{16}%<%t9%p1%{8}%-%d%e38;5;%p1%d%;m"),
+    Capability::string("setab", "AB", "\\E[%?%p1%{8}%<%t4%p1%d%e%p1%{16}%<%t10%p1%{8}%-%d%e48;5;%p1%d%;m"),
+    
+    // Cursor shape and color, synchronized output
+    Capability::extended("Ss", "\\E[%p1%d\\sq"),
+    Capability::extended("Se", "\\E[2\\sq"),
+    Capability::extended("Cs", "\\E]12;%p1%s\\E\\\\"),
+    Capability::extended("Cr", "\\E]112\\E\\\\"),
+    Capability::extended("Sync", "\\E[?2026%?%p1%{1}%-%tl%eh%;"),
+    
+    // Bracketed paste: enable, disable, and the markers sent around pasted text
+    Capability::extended("BE", "\\E[?2004h"),
+    Capability::extended("BD", "\\E[?2004l"),
+    Capability::extended("PS", "\\E[200~"),
+    Capability::extended("PE", "\\E[201~"),
+    
+    // Keys, in application mode as set by smkx
+    Capability::string("smkx", "ks", "\\E[?1h\\E="),
+    Capability::string("rmkx", "ke", "\\E[?1l\\E>"),
+    Capability::string("kcuu1", "ku", "\\EOA"),
+    Capability::string("kcud1", "kd", "\\EOB"),
+    Capability::string("kcuf1", "kr", "\\EOC"),
+    Capability::string("kcub1", "kl", "\\EOD"),
+    Capability::string("khome", "kh", "\\EOH"),
+    Capability::string("kend", "@7", "\\EOF"),
+    Capability::string("kich1", "kI", "\\E[2~"),
+    Capability::string("kdch1", "kD", "\\E[3~"),
+    Capability::string("kpp", "kP", "\\E[5~"),
+    Capability::string("knp", "kN", "\\E[6~"),
+    Capability::string("kbs", "kb", "^?"),
+    Capability::string("kcbt", "kB", "\\E[Z"),
+    Capability::string("kf1", "k1", "\\EOP"),
+    Capability::string("kf2", "k2", "\\EOQ"),
+    Capability::string("kf3", "k3", "\\EOR"),
+    Capability::string("kf4", "k4", "\\EOS"),
+    Capability::string("kf5", "k5", "\\E[15~"),
+    Capability::string("kf6", "k6", "\\E[17~"),
+    Capability::string("kf7", "k7", "\\E[18~"),
+    Capability::string("kf8", "k8", "\\E[19~"),
+    Capability::string("kf9", "k9", "\\E[20~"),
+    Capability::string("kf10", "k;", "\\E[21~"),
+    Capability::string("kf11", "F1", "\\E[23~"),
+    Capability::string("kf12", "F2", "\\E[24~"),
+    Capability::string("kmous", "Km", "\\E[M"),
+];
+
+/// Overrides for the `ratty-direct` terminfo entry, where color numbers are packed 24-bit RGB
+pub const DIRECT_COLOR_CAPABILITIES: &[Capability] = &[
+    Capability::extended_flag("RGB"),
+    Capability::number("colors", "Co", 0x1000000),
+    Capability::string(
+        "setaf",
+        "AF",
+        "\\E[%?%p1%{8}%<%t3%p1%d%e38;2;%p1%{65536}%/%d;%p1%{256}%/%{255}%&%d;%p1%{255}%&%d%;m",
+    ),
+    Capability::string(
+        "setab",
+        "AB",
+        "\\E[%?%p1%{8}%<%t4%p1%d%e48;2;%p1%{65536}%/%d;%p1%{256}%/%{255}%&%d;%p1%{255}%&%d%;m",
+    ),
+];
+
+/// Look up a capability by terminfo or termcap name
+pub fn find_capability(name: &str) -> Option<&'static Capability> {
+    CAPABILITIES.iter().find(|cap| cap.name == name || cap.termcap == Some(name))
+}
+
+/// Decode terminfo string escapes (`\E`, `\n`, `^X`...) into bytes; `%` parameters are kept
+pub fn decode_terminfo_string(source: &str) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(source.len());
+    let mut chars = source.bytes();
+    while let Some(byte) = chars.next() {
+        match byte {
+            b'\\' => match chars.next() {
+                Some(b'E' | b'e') => bytes.push(0x1b),
+                Some(b'n') => bytes.push(b'\n'),
+                Some(b'r') => bytes.push(b'\r'),
+                Some(b't') => bytes.push(b'\t'),
+                Some(b's') => bytes.push(b' '),
+                Some(other) => bytes.push(other),
+                None => bytes.push(b'\\'),
+            },
+            b'^' => match chars.next() {
+                Some(b'?') => bytes.push(0x7f),
+                Some(other) => bytes.push(other.to_ascii_uppercase() & 0x1f),
+                None => bytes.push(b'^'),
+            },
+            _ => bytes.push(byte),
+        }
+    }
+    bytes
+}
+
+/// Settings DECRQSS can report
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusSetting {
+    /// SGR - current graphic rendition
+    GraphicsRendition,
+    /// DECSTBM - top and bottom margins
+    ScrollMargins,
+    /// DECSLRM - left and right margins
+    LeftRightMargins,
+    /// DECSCUSR - cursor shape
+    CursorStyle,
+    /// DECSCA - character protection
+    CharacterProtection,
+}
+
+impl StatusSetting {
+    pub const ALL: [StatusSetting; 5] = [
+        StatusSetting::GraphicsRendition,
+        StatusSetting::ScrollMargins,
+        StatusSetting::LeftRightMargins,
+        StatusSetting::CursorStyle,
+        StatusSetting::CharacterProtection,
+    ];
+    
+    /// The intermediate and final bytes that name this setting in a request and its reply
+    pub fn selector(self) -> &'static str {
+        match self {
+            StatusSetting::GraphicsRendition => "m",
+            StatusSetting::ScrollMargins => "r",
+            StatusSetting::LeftRightMargins => "s",
+            StatusSetting::CursorStyle => " q",
+            StatusSetting::CharacterProtection => "\"q",
+        }
+    }
+    
+    pub fn from_request(request: &[u8]) -> Option<Self> {
+        Self::ALL.into_iter().find(|setting| setting.selector().as_bytes() == request)
+    }
+}
+
+/// SGR parameters that reproduce a set of attributes from a reset
+pub fn graphics_rendition_params(attrs: &CellAttributes) -> String {
+    let flags = [
+        (attrs.bold, "1"),
+        (attrs.dim, "2"),
+        (attrs.italic, "3"),
+        (attrs.underline == UnderlineType::Single, "4"),
+        // Not 21, which some readers take as bold-off
+        (attrs.underline == UnderlineType::Double, "4:2"),
+        (attrs.underline == UnderlineType::Curly, "4:3"),
+        (attrs.underline == UnderlineType::Dotted, "4:4"),
+        (attrs.underline == UnderlineType::Dashed, "4:5"),
+        (attrs.blink == BlinkType::Slow, "5"),
+        (attrs.blink == BlinkType::Fast, "6"),
+        (attrs.reverse, "7"),
+        (attrs.invisible, "8"),
+        (attrs.strikethrough, "9"),
+        (attrs.frame == FrameType::Framed, "51"),
+        (attrs.frame == FrameType::Encircled, "52"),
+        (attrs.overline, "53"),
+        (attrs.script == ScriptPosition::Superscript, "73"),
+        (attrs.script == ScriptPosition::Subscript, "74"),
+    ];
+    let mut params = vec!["0".to_string()];
+    params.extend(flags.iter().filter(|(set, _)| *set).map(|(_, param)| param.to_string()));
+    if attrs.font != 0 {
+        params.push((10 + attrs.font).to_string());
+    }
+    params.extend(attrs.foreground.map(|color| color.sgr_params(30)));
+    params.extend(attrs.background.map(|color| color.sgr_params(40)));
+    params.extend(attrs.underline_color.map(|color| color.extended_sgr_params(58)));
+    params.join(";")
+}
+
+/// DA1 features the parser implements that terminfo has no capability for
+const DEVICE_FEATURES: &[(u16, &str)] = &[
+    (4, "sixel graphics"),
+    (6, "selective erase (DECSCA, DECSED, DECSEL, DECSERA)"),
+    (28, "rectangular editing (DECCRA, DECFRA, DECERA, DECCARA)"),
+];
+
+/// The DA1 answer: VT420 conformance (for DECSLRM and rectangular editing), ANSI color when
+/// the capability table has `setaf`, and the features above
+pub fn primary_device_attributes() -> String {
+    let mut features: Vec<u16> = DEVICE_FEATURES.iter().map(|(code, _)| *code).collect();
+    if find_capability("setaf").is_some() {
+        features.push(22);
+    }
+    features.sort_unstable();
+    let features: Vec<String> = features.iter().map(u16::to_string).collect();
+    format!("\x1b[?64;{}c", features.join(";"))
+}
+
+fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02X}", byte)).collect()
+}
+
+fn hex_decode(hex: &[u8]) -> Option<String> {
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+    let bytes = hex
+        .chunks(2)
+        .map(|pair| u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+    String::from_utf8(bytes).ok()
+}
+
+impl Pane {
+    /// Answer DECRQSS (`DCS $ q Pt ST`) with `DCS 1 $ r value Pt ST`, or `DCS 0 $ r ST` if unsupported
+    pub(crate) fn report_status_string(&mut self, request: &[u8]) {
+        let reply = match StatusSetting::from_request(request) {
+            Some(setting) => format!("\x1bP1$r{}{}\x1b\\", self.status_value(setting), setting.selector()),
+            None => "\x1bP0$r\x1b\\".to_string(),
+        };
+        self.respond(reply.as_bytes());
+    }
+    
+    /// Answer XTGETTCAP (`DCS + q name ; name ... ST`, names hex-encoded) with one reply per name:
+    /// `DCS 1 + r name = value ST` (just the name for flags), or `DCS 0 + r name ST` if unknown
+    pub(crate) fn report_termcap(&mut self, request: &[u8]) {
+        for hex_name in request.split(|&byte| byte == b';') {
+            let value = match hex_decode(hex_name).as_deref() {
+                Some("TN") => Some(Some(self.term_name().into_bytes())),
+                Some(name) => find_capability(name).map(Capability::wire_value),
+                None => None,
+            };
+            let name = String::from_utf8_lossy(hex_name).to_ascii_uppercase();
+            let reply = match value {
+                Some(Some(value)) => format!("\x1bP1+r{}={}\x1b\\", name, hex_encode(&value)),
+                Some(None) => format!("\x1bP1+r{}\x1b\\", name),
+                None => format!("\x1bP0+r{}\x1b\\", name),
+            };
+            self.respond(reply.as_bytes());
+        }
+    }
+    
+    /// TERM as the pane's programs see it
+    fn term_name(&self) -> String {
+        self.config
+            .environment_variables
+            .get("TERM")
+            .cloned()
+            .unwrap_or_else(|| term_name().to_string())
+    }
+    
+    fn status_value(&self, setting: StatusSetting) -> String {
+        let margins = self.screen_buffer.margins;
+        match setting {
+            StatusSetting::GraphicsRendition => graphics_rendition_params(&self.modes.current_attributes),
+            StatusSetting::ScrollMargins => format!("{};{}", margins.top + 1, margins.bottom + 1),
+            StatusSetting::LeftRightMargins => format!("{};{}", margins.left + 1, margins.right + 1),
+            StatusSetting::CursorStyle => self.cursor.shape_param().to_string(),
+            StatusSetting::CharacterProtection => (self.modes.current_attributes.protected as u8).to_string(),
+        }
+    }
+}
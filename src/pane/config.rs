@@ -20,12 +20,14 @@ pub struct PaneConfig {
     pub auto_wrap: bool,
     pub cursor_blink: bool,
     pub cursor_style: CursorStyle,
+    pub sgr21_double_underline: bool, // ECMA-48 reading of SGR 21; false makes it bold-off, as on the Linux console
     
     // Display settings
     pub default_title: String,
     pub title_format: String, // Template for dynamic titles
     pub show_cursor: bool,
     pub bell_action: BellAction,
+    pub cell_size: (u16, u16), // Cell size in pixels (width, height) as in sill's MouseConfig, used to place images and answer XTWINOPS
     
     // Input handling
     pub alt_sends_escape: bool,
@@ -41,9 +43,12 @@ pub struct PaneConfig {
     pub render_throttle_ms: u64,
     pub max_fps: f32,
     pub lazy_rendering: bool,
+    pub synchronized_update_timeout_ms: u64, // Longest a mode 2026 batch may hold rendering
     
     // Logging and debugging
     pub log_terminal_sequences: bool,
+    pub sequence_log_dir: Option<String>, // Directory for per-pane sequence logs; None keeps the trace in memory only
+    pub sequence_log_capacity: usize, // Trace entries kept for the inspector
     pub debug_mode: bool,
 }
 
@@ -66,12 +71,14 @@ impl Default for PaneConfig {
             auto_wrap: true,
             cursor_blink: true,
             cursor_style: CursorStyle::Block,
+            sgr21_double_underline: true,
             
             // Display
             default_title: "Terminal".to_string(),
             title_format: "{command}".to_string(),
             show_cursor: true,
             bell_action: BellAction::None,
+            cell_size: (8, 16),
             
             // Input
             alt_sends_escape: true,
@@ -87,9 +94,12 @@ impl Default for PaneConfig {
             render_throttle_ms: 16, // ~60 FPS
             max_fps: 60.0,
             lazy_rendering: true,
+            synchronized_update_timeout_ms: 150,
             
             // Debug
             log_terminal_sequences: false,
+            sequence_log_dir: None,
+            sequence_log_capacity: 10_000,
             debug_mode: false,
         }
     }
@@ -143,6 +153,18 @@ impl PaneConfig {
         self
     }
     
+    /// Set the cell size in pixels
+    pub fn with_cell_size(mut self, width: u16, height: u16) -> Self {
+        self.cell_size = (width, height);
+        self
+    }
+    
+    /// Set the memory limit in bytes
+    pub fn with_memory_limit(mut self, bytes: usize) -> Self {
+        self.max_memory_usage = Some(bytes);
+        self
+    }
+    
     /// Enable debug mode
     pub fn with_debug(mut self, enabled: bool) -> Self {
         self.debug_mode = enabled;
@@ -253,6 +275,7 @@ impl PaneConfig {
         // Always take explicit boolean settings
         self.auto_wrap = other.auto_wrap;
         self.cursor_blink = other.cursor_blink;
+        self.sgr21_double_underline = other.sgr21_double_underline;
         self.show_cursor = other.show_cursor;
         self.alt_sends_escape = other.alt_sends_escape;
         self.application_cursor_keys = other.application_cursor_keys;
@@ -265,6 +288,8 @@ impl PaneConfig {
         // Performance settings
         self.render_throttle_ms = other.render_throttle_ms;
         self.max_fps = other.max_fps;
+        self.synchronized_update_timeout_ms = other.synchronized_update_timeout_ms;
+        self.sequence_log_capacity = other.sequence_log_capacity;
         
         // Optional settings
         if other.max_size.is_some() {
@@ -279,7 +304,12 @@ impl PaneConfig {
             self.max_memory_usage = other.max_memory_usage;
         }
         
+        if other.sequence_log_dir.is_some() {
+            self.sequence_log_dir = other.sequence_log_dir;
+        }
+        
         // Style settings
+        self.cell_size = other.cell_size;
         self.cursor_style = other.cursor_style;
         self.bell_action = other.bell_action;
     }
@@ -312,8 +342,7 @@ fn default_shell() -> String {
 fn default_environment() -> HashMap<String, String> {
     let mut env = HashMap::new();
     
-    // Set TERM for terminal identification
-    env.insert("TERM".to_string(), "xterm-256color".to_string());
+    // TERM is resolved when a process is spawned, since it depends on the installed terminfo
     
     // Set COLORTERM for color support indication
     env.insert("COLORTERM".to_string(), "truecolor".to_string());
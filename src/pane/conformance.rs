@@ -0,0 +1,303 @@
+//! Golden screen snapshots for emulation conformance
+//!
+//! Each fixture in `src/pane/conformance/` is a readable text file: a header of
+//! `size:` and `input:` lines, a `---` separator, then the expected snapshot.
+//! Run with `RATTY_UPDATE_SNAPSHOTS=1` to rewrite the snapshots from the current output.
+
+use super::*;
+use crate::sash::{Color, PaneId};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const FIXTURE_DIR: &str = "src/pane/conformance";
+const UPDATE_VAR: &str = "RATTY_UPDATE_SNAPSHOTS";
+const SEPARATOR: &str = "---\n";
+
+/// A scripted byte stream and the screen it should leave behind
+struct Fixture {
+    header: String,
+    size: (u16, u16),
+    input: Vec<u8>,
+    expected: String,
+}
+
+impl Fixture {
+    fn parse(text: &str) -> Result<Self, String> {
+        let (header, expected) = text
+            .split_once(SEPARATOR)
+            .ok_or_else(|| format!("missing `{}` separator", SEPARATOR.trim_end()))?;
+        
+        let mut size = None;
+        let mut input = Vec::new();
+        for line in header.lines() {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match line.split_once(':') {
+                Some(("size", value)) => {
+                    let (cols, rows) = value
+                        .trim()
+                        .split_once('x')
+                        .ok_or_else(|| format!("bad size `{}`", value.trim()))?;
+                    let parse = |n: &str| n.parse::<u16>().map_err(|err| format!("bad size `{}`: {}", n, err));
+                    size = Some((parse(cols)?, parse(rows)?));
+                }
+                Some(("input", value)) => input.extend(unescape(value.strip_prefix(' ').unwrap_or(value))?),
+                _ => return Err(format!("unrecognized header line `{}`", line)),
+            }
+        }
+        
+        Ok(Fixture {
+            header: header.to_string(),
+            size: size.ok_or("missing `size:` line")?,
+            input,
+            expected: expected.to_string(),
+        })
+    }
+    
+    /// Feed the input to a fresh pane and snapshot the result
+    fn run(&self) -> Result<String, String> {
+        let config = PaneConfig { initial_size: self.size, ..PaneConfig::default() };
+        let mut pane = Pane::new(PaneId::new(1), config).map_err(|err| err.to_string())?;
+        pane.process_output(&self.input).map_err(|err| err.to_string())?;
+        Ok(snapshot(&pane))
+    }
+}
+
+/// Decode the escapes used in `input:` lines: `\e`, `\r`, `\n`, `\t`, `\b`, `\xHH` and `\\`
+fn unescape(text: &str) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    let mut chars = text.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            let mut buf = [0; 4];
+            bytes.extend_from_slice(ch.encode_utf8(&mut buf).as_bytes());
+            continue;
+        }
+        match chars.next() {
+            Some('e') => bytes.push(0x1b),
+            Some('r') => bytes.push(b'\r'),
+            Some('n') => bytes.push(b'\n'),
+            Some('t') => bytes.push(b'\t'),
+            Some('b') => bytes.push(0x08),
+            Some('\\') => bytes.push(b'\\'),
+            Some('x') => {
+                let hex: String = chars.by_ref().take(2).collect();
+                let byte = u8::from_str_radix(&hex, 16).map_err(|_| format!("bad escape `\\x{}`", hex))?;
+                bytes.push(byte);
+            }
+            other => return Err(format!("bad escape `\\{}`", other.map(String::from).unwrap_or_default())),
+        }
+    }
+    Ok(bytes)
+}
+
+/// Render characters, attributes, colors, cursor, modes and wrapped flags as text
+fn snapshot(pane: &Pane) -> String {
+    let screen = &pane.screen_buffer;
+    let modes = &pane.modes;
+    let mut out = String::new();
+    
+    let position = pane.cursor.position;
+    let visibility = if modes.cursor_visible { "" } else { " hidden" };
+    let _ = writeln!(out, "cursor: row {} col {}{}", position.row + 1, position.col + 1, visibility);
+    
+    let flags = [
+        ("insert", modes.insert_mode),
+        ("autowrap", modes.auto_wrap),
+        ("origin", modes.origin_mode),
+        ("app-cursor", modes.application_cursor),
+        ("app-keypad", modes.application_keypad),
+        ("alt-screen", modes.alternate_screen),
+        ("lr-margins", modes.left_right_margins),
+        ("vt52", modes.vt52),
+    ];
+    let enabled: Vec<&str> = flags.iter().filter(|(_, on)| *on).map(|(name, _)| *name).collect();
+    let enabled = if enabled.is_empty() { "none".to_string() } else { enabled.join(" ") };
+    let _ = writeln!(out, "modes: {}", enabled);
+    
+    let margins = screen.margins;
+    let _ = writeln!(
+        out,
+        "margins: rows {}-{} cols {}-{}",
+        margins.top + 1,
+        margins.bottom + 1,
+        margins.left + 1,
+        margins.right + 1
+    );
+    
+    let sets = &pane.character_sets;
+    let _ = writeln!(
+        out,
+        "charsets: g0={} g1={} active={}",
+        charset_name(sets.g0),
+        charset_name(sets.g1),
+        if sets.active == CharacterSetSlot::G0 { "g0" } else { "g1" }
+    );
+    
+    out.push_str("screen:\n");
+    for (row, line) in screen.lines.iter().enumerate() {
+        let _ = write!(out, "{:>3} |{}|", row + 1, line.text());
+        match line.size {
+            LineSize::Single => {}
+            LineSize::DoubleWidth => out.push_str(" double-width"),
+            LineSize::DoubleHeightTop => out.push_str(" double-height-top"),
+            LineSize::DoubleHeightBottom => out.push_str(" double-height-bottom"),
+        }
+        if line.wrapped {
+            out.push_str(" wrapped");
+        }
+        out.push('\n');
+    }
+    
+    out.push_str("attributes:\n");
+    for (row, line) in screen.lines.iter().enumerate() {
+        let mut col = 0;
+        while col < line.cells.len() {
+            let style = cell_style(&line.cells[col]);
+            let start = col;
+            while col < line.cells.len() && cell_style(&line.cells[col]) == style {
+                col += 1;
+            }
+            if style.is_empty() {
+                continue;
+            }
+            let span = if col - start == 1 {
+                format!("{}", start + 1)
+            } else {
+                format!("{}-{}", start + 1, col)
+            };
+            let _ = writeln!(out, "{:>3}:{} {}", row + 1, span, style);
+        }
+    }
+    
+    out
+}
+
+fn charset_name(set: CharacterSet) -> &'static str {
+    match set {
+        CharacterSet::Ascii => "ascii",
+        CharacterSet::DecSpecialCharacter => "dec-special",
+        CharacterSet::DecAlternateCharacter => "dec-alternate",
+        CharacterSet::DecAlternateRom => "dec-alternate-rom",
+    }
+}
+
+/// Space-separated attributes and colors that differ from a blank cell
+fn cell_style(cell: &Cell) -> String {
+    let attrs = &cell.attributes;
+    let mut parts: Vec<String> = Vec::new();
+    let flags = [
+        ("bold", attrs.bold),
+        ("dim", attrs.dim),
+        ("italic", attrs.italic),
+        ("strikethrough", attrs.strikethrough),
+        ("reverse", attrs.reverse),
+        ("invisible", attrs.invisible),
+        ("overline", attrs.overline),
+        ("protected", attrs.protected),
+    ];
+    parts.extend(flags.iter().filter(|(_, on)| *on).map(|(name, _)| name.to_string()));
+    match attrs.underline {
+        UnderlineType::None => {}
+        UnderlineType::Single => parts.push("underline".to_string()),
+        other => parts.push(format!("underline={:?}", other).to_lowercase()),
+    }
+    match attrs.blink {
+        BlinkType::None => {}
+        BlinkType::Slow => parts.push("blink".to_string()),
+        BlinkType::Fast => parts.push("blink=fast".to_string()),
+    }
+    match attrs.script {
+        ScriptPosition::Normal => {}
+        other => parts.push(format!("{:?}", other).to_lowercase()),
+    }
+    match attrs.frame {
+        FrameType::None => {}
+        other => parts.push(format!("{:?}", other).to_lowercase()),
+    }
+    if attrs.font != 0 {
+        parts.push(format!("font={}", attrs.font));
+    }
+    
+    let blank = Cell::default();
+    if cell.foreground != blank.foreground {
+        parts.push(format!("fg={}", hex(cell.foreground)));
+    }
+    if cell.background != blank.background {
+        parts.push(format!("bg={}", hex(cell.background)));
+    }
+    if let Some(color) = attrs.underline_color {
+        parts.push(format!("ul={}", hex(color.to_color())));
+    }
+    parts.join(" ")
+}
+
+fn hex(color: Color) -> String {
+    format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b)
+}
+
+fn fixture_paths() -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join(FIXTURE_DIR);
+    let mut paths: Vec<PathBuf> = fs::read_dir(&dir)
+        .unwrap_or_else(|err| panic!("cannot read {}: {}", dir.display(), err))
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "txt"))
+        .collect();
+    paths.sort();
+    paths
+}
+
+#[test]
+fn test_conformance_snapshots() {
+    let update = std::env::var_os(UPDATE_VAR).is_some();
+    let paths = fixture_paths();
+    assert!(!paths.is_empty(), "no conformance fixtures found");
+    
+    let mut failures = Vec::new();
+    for path in &paths {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let text = fs::read_to_string(path).unwrap_or_else(|err| panic!("cannot read {}: {}", name, err));
+        let fixture = match Fixture::parse(&text) {
+            Ok(fixture) => fixture,
+            Err(err) => {
+                failures.push(format!("{}: {}", name, err));
+                continue;
+            }
+        };
+        let actual = match fixture.run() {
+            Ok(actual) => actual,
+            Err(err) => {
+                failures.push(format!("{}: {}", name, err));
+                continue;
+            }
+        };
+        
+        if actual == fixture.expected {
+            continue;
+        }
+        if update {
+            fs::write(path, format!("{}{}{}", fixture.header, SEPARATOR, actual))
+                .unwrap_or_else(|err| panic!("cannot write {}: {}", name, err));
+        } else {
+            failures.push(format!("{}\n--- expected\n{}--- actual\n{}", name, fixture.expected, actual));
+        }
+    }
+    
+    assert!(
+        failures.is_empty(),
+        "{} of {} snapshots differ (rerun with {}=1 to accept):\n\n{}",
+        failures.len(),
+        paths.len(),
+        UPDATE_VAR,
+        failures.join("\n")
+    );
+}
+
+#[test]
+fn test_fixture_input_escapes() {
+    assert_eq!(unescape(r"\e[1mA\r\n\t\b\\\x7f").unwrap(), b"\x1b[1mA\r\n\t\x08\\\x7f");
+    assert!(unescape(r"\q").is_err());
+    assert!(unescape(r"\xZZ").is_err());
+}
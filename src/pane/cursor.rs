@@ -8,6 +8,10 @@ pub struct Cursor {
     pub visibility: CursorVisibility,
     pub blink_state: BlinkState,
     pub saved_positions: Vec<CursorPosition>,
+    /// Color set by the program with OSC 12 (None follows the theme)
+    pub color: Option<Color>,
+    /// Set after printing in the last column; the next printed character wraps first
+    pub pending_wrap: bool,
 }
 
 impl Cursor {
@@ -19,6 +23,8 @@ impl Cursor {
             visibility: CursorVisibility::Visible,
             blink_state: BlinkState::default(),
             saved_positions: Vec::new(),
+            color: None,
+            pending_wrap: false,
         }
     }
     
@@ -28,130 +34,155 @@ impl Cursor {
         let zero_row = row.saturating_sub(1);
         let zero_col = col.saturating_sub(1);
         
-        // Apply origin mode if enabled
-        let final_row = if self.position.origin_mode {
-            // TODO: In origin mode, row is relative to scroll region
-            zero_row
+        // In origin mode, positions are relative to the margins and cannot leave them
+        if self.position.origin_mode {
+            let margins = screen.margins;
+            self.move_to(
+                zero_row.saturating_add(margins.top).min(margins.bottom),
+                zero_col.saturating_add(margins.left).min(margins.right),
+                screen,
+            );
         } else {
-            zero_row
-        };
-        
-        // Clamp to screen boundaries
-        self.position.row = final_row.min(screen.height.saturating_sub(1));
-        self.position.col = zero_col.min(screen.width.saturating_sub(1));
+            self.move_to(zero_row, zero_col, screen);
+        }
         
         Ok(())
     }
     
-    /// Move cursor up by n rows
+    /// Move cursor up by n rows, stopping at the top margin if the cursor is below it
     pub fn move_up(&mut self, n: u16, screen: &ScreenBuffer) -> PaneResult<()> {
-        let new_row = self.position.row.saturating_sub(n);
-        self.set_position(new_row + 1, self.position.col + 1, screen)
+        let limit = if self.position.row >= screen.margins.top { screen.margins.top } else { 0 };
+        let new_row = self.position.row.saturating_sub(n).max(limit);
+        self.move_to(new_row, self.position.col, screen);
+        Ok(())
     }
     
-    /// Move cursor down by n rows
+    /// Move cursor down by n rows, stopping at the bottom margin if the cursor is above it
     pub fn move_down(&mut self, n: u16, screen: &ScreenBuffer) -> PaneResult<()> {
-        let new_row = (self.position.row + n).min(screen.height.saturating_sub(1));
-        self.set_position(new_row + 1, self.position.col + 1, screen)
+        let limit = if self.position.row <= screen.margins.bottom {
+            screen.margins.bottom
+        } else {
+            screen.height.saturating_sub(1)
+        };
+        let new_row = self.position.row.saturating_add(n).min(limit);
+        self.move_to(new_row, self.position.col, screen);
+        Ok(())
     }
     
-    /// Move cursor forward by n columns
+    /// Move cursor forward by n columns, stopping at the right margin if the cursor is left of it
     pub fn move_forward(&mut self, n: u16, screen: &ScreenBuffer) -> PaneResult<()> {
-        let new_col = (self.position.col + n).min(screen.width.saturating_sub(1));
-        self.set_position(self.position.row + 1, new_col + 1, screen)
+        let new_col = self.position.col.saturating_add(n).min(self.right_limit(screen));
+        self.move_to(self.position.row, new_col, screen);
+        Ok(())
     }
     
-    /// Move cursor back by n columns
-    pub fn move_back(&mut self, n: u16) -> PaneResult<()> {
-        self.position.col = self.position.col.saturating_sub(n);
+    /// Move cursor back by n columns, stopping at the left margin if the cursor is right of it
+    pub fn move_back(&mut self, n: u16, screen: &ScreenBuffer) -> PaneResult<()> {
+        self.position.col = self.position.col.saturating_sub(n).max(self.left_limit(screen));
         Ok(())
     }
     
-    /// Advance cursor after character input
+    /// Advance cursor after character input; at the right margin the wrap waits for the
+    /// next character, as on a VT100
     pub fn advance(&mut self, screen: &ScreenBuffer, modes: &TerminalModes) -> PaneResult<()> {
-        self.position.col += 1;
-        
-        // Handle line wrapping
-        if self.position.col >= screen.width {
-            if modes.auto_wrap {
-                self.position.col = 0;
-                self.position.row += 1;
-                
-                // Handle scrolling if we've gone past the bottom
-                if self.position.row >= screen.height {
-                    self.position.row = screen.height.saturating_sub(1);
-                    // TODO: Scroll screen up
-                }
-            } else {
-                // Stay at the last column
-                self.position.col = screen.width.saturating_sub(1);
-            }
+        if self.position.col < self.right_limit(screen) {
+            self.position.col += 1;
+        } else if modes.auto_wrap {
+            self.pending_wrap = true;
         }
-        
         Ok(())
     }
     
-    /// Handle line feed (move down, potentially scroll)
+    /// Carry out a pending wrap to the next line, returning true when the cursor is at the
+    /// bottom margin and the caller must line feed to scroll
+    pub fn wrap(&mut self, screen: &ScreenBuffer) -> bool {
+        self.pending_wrap = false;
+        self.position.col = self.left_limit(screen);
+        if self.position.row == screen.margins.bottom {
+            return true;
+        }
+        self.position.row = (self.position.row + 1).min(screen.height.saturating_sub(1));
+        self.position.col = self.position.col.min(screen.line_width(self.position.row).saturating_sub(1));
+        false
+    }
+    
+    /// Handle line feed (move down, scrolling the margins at the bottom margin)
     pub fn line_feed(
         &mut self, 
         screen: &mut ScreenBuffer, 
         scrollback: &mut ScrollbackBuffer, 
-        _modes: &TerminalModes
+        modes: &TerminalModes
     ) -> PaneResult<()> {
-        self.position.row += 1;
-        
-        // Check if we need to scroll
-        if self.position.row >= screen.height {
-            self.position.row = screen.height.saturating_sub(1);
-            
-            // Move the top line to scrollback and add a new line at bottom
-            if let Some(top_line) = screen.lines.first().cloned() {
-                scrollback.push_line(top_line);
-            }
-            
-            // Shift all lines up
-            for i in 1..screen.lines.len() {
-                if let (Some(src), Some(dst)) = (screen.lines.get(i).cloned(), screen.lines.get_mut(i - 1)) {
-                    *dst = src;
-                }
-            }
-            
-            // Add new blank line at bottom
-            if let Some(last_line) = screen.lines.last_mut() {
-                *last_line = Line::new(screen.width);
-            }
-            
-            screen.mark_all_dirty();
+        if self.position.row == screen.margins.bottom {
+            // The alternate screen keeps no scrollback
+            let scrollback = (!modes.alternate_screen).then_some(scrollback);
+            screen.scroll_up(1, scrollback);
+        } else if self.position.row + 1 < screen.height {
+            self.position.row += 1;
         }
-        
+        // The line now under the cursor may be double-width
+        self.clamp_to_screen(screen);
         Ok(())
     }
     
-    /// Handle carriage return (move to beginning of line)
-    pub fn carriage_return(&mut self) -> PaneResult<()> {
-        self.position.col = 0;
+    /// Move up a line, scrolling the margins down at the top margin (RI)
+    pub fn reverse_index(&mut self, screen: &mut ScreenBuffer) -> PaneResult<()> {
+        if self.position.row == screen.margins.top {
+            screen.scroll_down(1);
+        } else {
+            self.position.row = self.position.row.saturating_sub(1);
+        }
+        self.clamp_to_screen(screen);
+        Ok(())
+    }
+    
+    /// Handle carriage return (move to the left margin, or the line start left of it)
+    pub fn carriage_return(&mut self, screen: &ScreenBuffer) -> PaneResult<()> {
+        self.position.col = self.left_limit(screen);
         Ok(())
     }
     
     /// Handle tab forward
     pub fn tab_forward(&mut self, tabs: &TabStops, screen: &ScreenBuffer) -> PaneResult<()> {
         let next_tab = tabs.next_tab_stop(self.position.col);
-        self.position.col = next_tab.min(screen.width.saturating_sub(1));
+        self.position.col = next_tab.min(self.right_limit(screen));
         Ok(())
     }
     
     /// Handle backspace
     pub fn backspace(&mut self, screen: &ScreenBuffer) -> PaneResult<()> {
-        if self.position.col > 0 {
+        if self.position.col > self.left_limit(screen) {
             self.position.col -= 1;
-        } else if self.position.row > 0 {
+        } else if self.position.col == 0 && self.position.row > 0 {
             // Move to end of previous line
             self.position.row -= 1;
-            self.position.col = screen.width.saturating_sub(1);
+            self.position.col = screen.line_width(self.position.row).saturating_sub(1);
         }
         Ok(())
     }
     
+    /// Pull the cursor back onto the screen after it has shrunk
+    pub fn clamp_to_screen(&mut self, screen: &ScreenBuffer) {
+        self.move_to(self.position.row, self.position.col, screen);
+    }
+    
+    /// Place the cursor at a 0-based screen position, clamped to the screen
+    fn move_to(&mut self, row: u16, col: u16, screen: &ScreenBuffer) {
+        self.position.row = row.min(screen.height.saturating_sub(1));
+        self.position.col = col.min(screen.line_width(self.position.row).saturating_sub(1));
+    }
+    
+    /// Leftmost column the cursor can reach by moving left: the left margin unless already past it
+    fn left_limit(&self, screen: &ScreenBuffer) -> u16 {
+        if self.position.col >= screen.margins.left { screen.margins.left } else { 0 }
+    }
+    
+    /// Rightmost column the cursor can reach by moving right: the right margin unless already past it
+    fn right_limit(&self, screen: &ScreenBuffer) -> u16 {
+        let last_col = screen.line_width(self.position.row).saturating_sub(1);
+        if self.position.col <= screen.margins.right { screen.margins.right.min(last_col) } else { last_col }
+    }
+    
     /// Save current cursor position
     pub fn save_position(&mut self) {
         self.saved_positions.push(self.position);
@@ -162,8 +193,7 @@ impl Cursor {
         if let Some(saved_pos) = self.saved_positions.pop() {
             self.position = saved_pos;
             // Ensure position is still valid for current screen size
-            self.position.row = self.position.row.min(screen.height.saturating_sub(1));
-            self.position.col = self.position.col.min(screen.width.saturating_sub(1));
+            self.clamp_to_screen(screen);
         }
         Ok(())
     }
@@ -178,6 +208,36 @@ impl Cursor {
         self.style = style;
     }
     
+    /// Set the shape and blinking together (DECSCUSR); a hidden cursor stays hidden
+    pub fn set_shape(&mut self, style: CursorStyle, blinking: bool) {
+        self.style = style;
+        if self.visibility != CursorVisibility::Hidden {
+            self.visibility = if blinking {
+                CursorVisibility::blinking(style)
+            } else {
+                CursorVisibility::Visible
+            };
+        }
+    }
+    
+    /// Whether the cursor blinks
+    pub fn is_blinking(&self) -> bool {
+        matches!(
+            self.visibility,
+            CursorVisibility::BlinkingBlock | CursorVisibility::BlinkingUnderline | CursorVisibility::BlinkingBar
+        )
+    }
+    
+    /// The DECSCUSR parameter describing the current shape (1-6)
+    pub fn shape_param(&self) -> u16 {
+        let steady = !self.is_blinking() as u16;
+        match self.style {
+            CursorStyle::Block => 1 + steady,
+            CursorStyle::Underline => 3 + steady,
+            CursorStyle::Bar => 5 + steady,
+        }
+    }
+    
     /// Update blink state (called periodically for blinking cursors)
     pub fn update_blink(&mut self, elapsed: std::time::Duration) {
         self.blink_state.update(elapsed);
@@ -231,6 +291,17 @@ pub enum CursorVisibility {
     BlinkingBar,
 }
 
+impl CursorVisibility {
+    /// The blinking visibility for a style
+    pub fn blinking(style: CursorStyle) -> Self {
+        match style {
+            CursorStyle::Block => CursorVisibility::BlinkingBlock,
+            CursorStyle::Underline => CursorVisibility::BlinkingUnderline,
+            CursorStyle::Bar => CursorVisibility::BlinkingBar,
+        }
+    }
+}
+
 /// Cursor blink state management
 #[derive(Debug, Clone)]
 pub struct BlinkState {
@@ -273,4 +344,37 @@ impl BlinkState {
     pub fn set_interval(&mut self, interval: std::time::Duration) {
         self.blink_interval = interval;
     }
+}
+
+impl Pane {
+    /// Apply a cursor shape or color request from the program
+    pub(crate) fn handle_cursor_command(&mut self, command: VtCommand) {
+        match command {
+            // DECSCUSR: 0 restores the configured shape, then blinking/steady pairs
+            VtCommand::SetCursorStyle(0) => {
+                self.cursor.set_shape(self.config.cursor_style, self.config.cursor_blink);
+            }
+            VtCommand::SetCursorStyle(param @ 1..=6) => {
+                let style = match param {
+                    1 | 2 => CursorStyle::Block,
+                    3 | 4 => CursorStyle::Underline,
+                    _ => CursorStyle::Bar,
+                };
+                self.cursor.set_shape(style, param % 2 == 1);
+            }
+            VtCommand::SetCursorStyle(_) => self.stats.unhandled_sequences += 1,
+            
+            // OSC 12 / OSC 112
+            VtCommand::SetCursorColor(color) => self.cursor.color = Some(color),
+            VtCommand::ResetCursorColor => self.cursor.color = None,
+            VtCommand::QueryCursorColor => {
+                let color = self.cursor.color.unwrap_or_else(|| {
+                    self.local_theme_override.as_ref().map_or_else(|| Theme::default().colors.cursor, |theme| theme.colors.cursor)
+                });
+                let reply = format!("\x1b]12;{}\x1b\\", xterm_color_spec(color));
+                self.respond(reply.as_bytes());
+            }
+            _ => {}
+        }
+    }
 }
\ No newline at end of file
@@ -18,6 +18,8 @@ pub enum PaneEvent {
     Resized(u16, u16),
     ModeChanged(TerminalMode),
     BellRung,
+    NotificationRequested(DesktopNotification),
+    WindowManipulationRequested(WindowManipulation),
     
     // User interaction events
     TextSelected(Selection),
@@ -90,6 +92,8 @@ pub enum PaneEventType {
     Resized,
     ModeChanged,
     BellRung,
+    NotificationRequested,
+    WindowManipulationRequested,
     TextSelected,
     SearchResultsChanged,
     PtyError,
@@ -112,6 +116,8 @@ impl From<&PaneEvent> for PaneEventType {
             PaneEvent::Resized(_, _) => PaneEventType::Resized,
             PaneEvent::ModeChanged(_) => PaneEventType::ModeChanged,
             PaneEvent::BellRung => PaneEventType::BellRung,
+            PaneEvent::NotificationRequested(_) => PaneEventType::NotificationRequested,
+            PaneEvent::WindowManipulationRequested(_) => PaneEventType::WindowManipulationRequested,
             PaneEvent::TextSelected(_) => PaneEventType::TextSelected,
             PaneEvent::SearchResultsChanged(_) => PaneEventType::SearchResultsChanged,
             PaneEvent::PtyError(_) => PaneEventType::PtyError,
@@ -293,6 +299,8 @@ pub struct PaneStatistics {
     pub lines_processed: usize,
     pub commands_executed: usize,
     pub unhandled_sequences: usize,
+    /// Sequence handlers that returned an error or panicked
+    pub handler_errors: usize,
     pub render_count: usize,
     pub last_activity: Option<std::time::Instant>,
     pub creation_time: std::time::Instant,
@@ -308,6 +316,7 @@ impl Default for PaneStatistics {
             lines_processed: 0,
             commands_executed: 0,
             unhandled_sequences: 0,
+            handler_errors: 0,
             render_count: 0,
             last_activity: None,
             creation_time: std::time::Instant::now(),
@@ -0,0 +1,276 @@
+//! Property tests that feed hostile byte streams and resizes into a pane
+//!
+//! Every step must leave the pane in a valid state without panicking, so that
+//! `cat`ing a binary file can never take the terminal down.
+
+use super::*;
+use crate::sash::PaneId;
+use base64::Engine;
+use proptest::prelude::*;
+
+/// One thing that can happen to a pane between renders
+#[derive(Debug, Clone)]
+enum Step {
+    Output(Vec<u8>),
+    Resize { cols: u16, rows: u16 },
+    ClearScreen,
+}
+
+/// Bytes weighted towards escape sequence syntax, so parameters, intermediates
+/// and string terminators are reached far more often than with uniform bytes
+fn sequence_byte() -> impl Strategy<Value = u8> {
+    prop_oneof![
+        4 => any::<u8>(),
+        2 => Just(0x1b),
+        3 => prop::sample::select(b"[]P_\\#()0123456789;:?>=<!$\"*' ".to_vec()),
+        2 => prop::sample::select(b"@ABCDEFGHIJKLMPSTXZ`abcdghlmnpqrstuvxyz{|}~".to_vec()),
+        1 => prop::sample::select(vec![0x07, 0x08, 0x09, 0x0a, 0x0d, 0x0e, 0x0f, 0x9b, 0x9c]),
+    ]
+}
+
+/// Numbers at the edges of the u16 and u32 ranges, or small enough to be plausible
+fn extreme_number() -> impl Strategy<Value = u32> {
+    prop_oneof![Just(0u32), Just(1), Just(255), Just(65535), Just(65536), Just(u32::MAX), 0..300u32]
+}
+
+/// Well-formed CSI sequences with extreme parameters
+fn csi_sequence() -> impl Strategy<Value = Vec<u8>> {
+    (
+        prop::option::of(prop::sample::select(b"?>=<".to_vec())),
+        prop::collection::vec(extreme_number(), 0..6),
+        prop::option::of(prop::sample::select(b" $\"*'".to_vec())),
+        prop::sample::select(b"@ABCDEFGHIJKLMPSTXZ`abcdghlmnpqrstuvxyz{|}~".to_vec()),
+    )
+        .prop_map(|(marker, params, intermediate, final_byte)| {
+            let mut bytes = b"\x1b[".to_vec();
+            bytes.extend(marker);
+            let params: Vec<String> = params.iter().map(u32::to_string).collect();
+            bytes.extend(params.join(";").into_bytes());
+            bytes.extend(intermediate);
+            bytes.push(final_byte);
+            bytes
+        })
+}
+
+/// A tiny PNG, so image payloads get past decoding to sizing and placement
+fn png_payload() -> Vec<u8> {
+    let image: ::image::DynamicImage = ::image::RgbaImage::from_pixel(3, 2, ::image::Rgba([255, 0, 0, 255])).into();
+    let mut data = Vec::new();
+    image.write_to(&mut std::io::Cursor::new(&mut data), ::image::ImageFormat::Png).expect("Encoding should succeed");
+    data
+}
+
+/// Image data: garbage, a valid PNG, or raw pixels that may or may not match the stated size
+fn image_payload() -> impl Strategy<Value = Vec<u8>> {
+    prop_oneof![
+        prop::collection::vec(any::<u8>(), 0..64),
+        Just(png_payload()),
+        prop::sample::select(vec![3usize, 4, 6, 8, 24, 32]).prop_map(|len| vec![0x80; len]),
+    ]
+}
+
+/// Well-formed kitty graphics commands (`APC G key=value,...;base64 ST`) with extreme numbers
+fn kitty_graphics_sequence() -> impl Strategy<Value = Vec<u8>> {
+    // Keys taking a number; `t` is left at its default so nothing reads from the filesystem
+    let numeric_key = prop::sample::select(vec!["i", "I", "p", "s", "v", "x", "y", "w", "h", "X", "Y", "c", "r", "C", "z", "m", "q", "S", "O"]);
+    (
+        prop::sample::select(vec!["t", "T", "p", "d", "q"]),
+        prop::sample::select(vec!["24", "32", "100"]),
+        prop::option::of(prop::sample::select(b"aAiIcCpPxXyYzZ".to_vec())),
+        prop::collection::vec((numeric_key, extreme_number()), 0..8),
+        image_payload(),
+    )
+        .prop_map(|(action, format, delete, numbers, payload)| {
+            let mut keys = vec![format!("a={}", action), format!("f={}", format)];
+            keys.extend(delete.map(|target| format!("d={}", target as char)));
+            keys.extend(numbers.iter().map(|(key, value)| format!("{}={}", key, value)));
+            let payload = base64::engine::general_purpose::STANDARD.encode(payload);
+            format!("\x1b_G{};{}\x1b\\", keys.join(","), payload).into_bytes()
+        })
+}
+
+/// Well-formed sixel images with extreme raster sizes, colors and repeat counts
+fn sixel_sequence() -> impl Strategy<Value = Vec<u8>> {
+    let item = prop_oneof![
+        (extreme_number(), extreme_number(), extreme_number(), extreme_number())
+            .prop_map(|(pan, pad, width, height)| format!("\"{};{};{};{}", pan, pad, width, height)),
+        (extreme_number(), extreme_number(), extreme_number(), extreme_number(), extreme_number())
+            .prop_map(|(index, space, x, y, z)| format!("#{};{};{};{};{}", index, space, x, y, z)),
+        extreme_number().prop_map(|index| format!("#{}", index)),
+        (extreme_number(), 0x3fu8..=0x7e).prop_map(|(count, sixel)| format!("!{}{}", count, sixel as char)),
+        prop::collection::vec(0x3fu8..=0x7e, 1..16).prop_map(|data| String::from_utf8(data).unwrap()),
+        Just("-".to_string()),
+        Just("$".to_string()),
+    ];
+    (
+        prop::collection::vec(extreme_number(), 0..4),
+        prop::collection::vec(item, 0..12),
+    )
+        .prop_map(|(params, items)| {
+            let params: Vec<String> = params.iter().map(u32::to_string).collect();
+            format!("\x1bP{}q{}\x1b\\", params.join(";"), items.concat()).into_bytes()
+        })
+}
+
+/// Well-formed iTerm2 inline images (`OSC 1337 ; File=... : base64 BEL`) with extreme sizes
+fn iterm2_image_sequence() -> impl Strategy<Value = Vec<u8>> {
+    let dimension = prop_oneof![
+        Just("auto".to_string()),
+        (extreme_number(), prop::sample::select(vec!["", "px", "%"]))
+            .prop_map(|(value, unit)| format!("{}{}", value, unit)),
+    ];
+    (
+        dimension.clone(),
+        dimension,
+        prop::option::of(extreme_number()),
+        any::<bool>(),
+        any::<bool>(),
+        image_payload(),
+    )
+        .prop_map(|(width, height, size, inline, preserve_aspect, payload)| {
+            let mut args = vec![
+                format!("inline={}", inline as u8),
+                format!("width={}", width),
+                format!("height={}", height),
+                format!("preserveAspectRatio={}", preserve_aspect as u8),
+            ];
+            args.extend(size.map(|size| format!("size={}", size)));
+            let payload = base64::engine::general_purpose::STANDARD.encode(payload);
+            format!("\x1b]1337;File={}:{}\x07", args.join(";"), payload).into_bytes()
+        })
+}
+
+fn graphics_sequence() -> impl Strategy<Value = Vec<u8>> {
+    prop_oneof![kitty_graphics_sequence(), sixel_sequence(), iterm2_image_sequence()]
+}
+
+/// Well-formed escape sequences: line sizes and DECALN (`ESC #`), character set designations, and
+/// single-byte finals such as DECSC, IND and RI
+fn escape_sequence() -> impl Strategy<Value = Vec<u8>> {
+    prop_oneof![
+        prop::sample::select(b"34568".to_vec()).prop_map(|final_byte| vec![0x1b, b'#', final_byte]),
+        (prop::sample::select(b"()".to_vec()), prop::sample::select(b"B0A<>%5".to_vec()))
+            .prop_map(|(slot, set)| vec![0x1b, slot, set]),
+        prop::sample::select(b"78=>DEHMNOZc\\".to_vec()).prop_map(|final_byte| vec![0x1b, final_byte]),
+    ]
+}
+
+/// Escape and CSI sequences mixed with printable text, so edits meet line sizes, margins and wraps
+fn sequence_mix() -> impl Strategy<Value = Vec<u8>> {
+    let piece = prop_oneof![
+        3 => csi_sequence(),
+        2 => escape_sequence(),
+        1 => prop::collection::vec(0x20u8..0x7f, 1..12),
+        1 => prop::sample::select(vec![0x08, 0x09, 0x0a, 0x0d, 0x0e, 0x0f]).prop_map(|byte| vec![byte]),
+    ];
+    prop::collection::vec(piece, 1..24).prop_map(|pieces| pieces.concat())
+}
+
+fn output() -> impl Strategy<Value = Vec<u8>> {
+    prop_oneof![
+        prop::collection::vec(any::<u8>(), 0..256),
+        prop::collection::vec(sequence_byte(), 0..256),
+        sequence_mix(),
+        prop::collection::vec(graphics_sequence(), 1..4).prop_map(|sequences| sequences.concat()),
+    ]
+}
+
+fn step() -> impl Strategy<Value = Step> {
+    prop_oneof![
+        8 => output().prop_map(Step::Output),
+        2 => (1..120u16, 1..50u16).prop_map(|(cols, rows)| Step::Resize { cols, rows }),
+        1 => Just(Step::ClearScreen),
+    ]
+}
+
+fn test_pane() -> Pane {
+    let config = PaneConfig { initial_size: (20, 8), ..PaneConfig::default() };
+    Pane::new(PaneId::new(1), config).expect("Failed to create test pane")
+}
+
+/// Apply each step, checking the pane is still consistent after it
+fn run_steps(steps: &[Step]) -> Result<(), TestCaseError> {
+    let mut pane = test_pane();
+    for step in steps {
+        match step {
+            Step::Output(bytes) => {
+                prop_assert!(pane.process_output(bytes).is_ok(), "output {:?} failed", bytes);
+            }
+            Step::Resize { cols, rows } => {
+                prop_assert!(pane.resize(*rows, *cols).is_ok());
+            }
+            Step::ClearScreen => {
+                prop_assert!(pane.clear_screen().is_ok());
+            }
+        }
+        if let Err(err) = pane.validate_state() {
+            return Err(TestCaseError::fail(format!("{} after {:?}", err, step)));
+        }
+    }
+    Ok(())
+}
+
+proptest! {
+    // Four times proptest's default: sequence interactions are rare per case, and the suite still
+    // finishes in under a minute
+    #![proptest_config(ProptestConfig::with_cases(1024))]
+    
+    #[test]
+    fn test_arbitrary_output_keeps_pane_valid(bytes in prop::collection::vec(any::<u8>(), 0..4096)) {
+        run_steps(&[Step::Output(bytes)])?;
+    }
+    
+    #[test]
+    fn test_interleaved_output_and_resizes_keep_pane_valid(steps in prop::collection::vec(step(), 1..24)) {
+        run_steps(&steps)?;
+    }
+    
+    #[test]
+    fn test_graphics_sequences_keep_pane_valid(sequences in prop::collection::vec(graphics_sequence(), 1..8)) {
+        let steps: Vec<Step> = sequences.into_iter().map(Step::Output).collect();
+        run_steps(&steps)?;
+    }
+    
+    #[test]
+    fn test_output_split_anywhere_keeps_pane_valid(bytes in output(), split in any::<prop::sample::Index>()) {
+        // A sequence cut across two reads must behave like one read
+        let (head, tail) = bytes.split_at(split.index(bytes.len() + 1));
+        run_steps(&[Step::Output(head.to_vec()), Step::Output(tail.to_vec())])?;
+    }
+}
+
+#[test]
+fn test_binary_file_cat_keeps_pane_valid() {
+    // A deterministic stand-in for `cat /bin/ls`: every byte value, repeated in shifting strides
+    let bytes: Vec<u8> = (0..64 * 1024u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8).collect();
+    let mut pane = test_pane();
+    for chunk in bytes.chunks(1000) {
+        pane.process_output(chunk).expect("Processing should succeed");
+        pane.validate_state().expect("Pane state should stay valid");
+    }
+}
+
+#[test]
+fn test_shrinking_under_the_cursor_keeps_erases_in_bounds() {
+    let mut pane = test_pane();
+    pane.process_output(b"\x1b[8;20H").unwrap();
+    pane.resize(1, 1).unwrap();
+    pane.validate_state().expect("Cursor should be pulled onto the screen");
+    
+    // Each of these once indexed past the screen from a stale cursor row
+    for sequence in [&b"\x1b[J"[..], b"\x1b[1J", b"\x1b[L", b"\x1b[M", b"\x1b[65535C", b"\x1b[65535@", b"X"] {
+        pane.process_output(sequence).expect("Processing should succeed");
+        pane.validate_state().expect("Pane state should stay valid");
+    }
+}
+
+#[test]
+fn test_extreme_graphics_numbers_keep_pane_valid() {
+    let mut pane = test_pane();
+    
+    // Each of these once overflowed: an HLS hue near i32::MAX, and a kitty delete at the last column
+    for sequence in [&b"\x1bPq#1;1;4294967295;50;50!3~\x1b\\"[..], b"\x1b_Ga=d,d=p,x=65536,y=1\x1b\\"] {
+        pane.process_output(sequence).expect("Processing should succeed");
+        pane.validate_state().expect("Pane state should stay valid");
+    }
+}
@@ -0,0 +1,72 @@
+use super::*;
+use std::panic::{self, AssertUnwindSafe};
+
+/// The part of a pane that sequence handlers may read and change
+pub struct PaneHandle<'a> {
+    pane: &'a mut Pane,
+}
+
+impl PaneHandle<'_> {
+    pub fn id(&self) -> PaneId {
+        self.pane.id
+    }
+    
+    /// Screen size in cells (columns, rows)
+    pub fn size(&self) -> (u16, u16) {
+        self.pane.size()
+    }
+    
+    /// Cursor position (column, row), 0-based
+    pub fn cursor_position(&self) -> (u16, u16) {
+        (self.pane.cursor.position.col, self.pane.cursor.position.row)
+    }
+    
+    /// Print text at the cursor in the current attributes; control characters are skipped
+    pub fn print(&mut self, text: &str) -> PaneResult<()> {
+        for ch in text.chars().filter(|ch| !ch.is_control()) {
+            self.pane.execute_terminal_command(VtCommand::PrintChar(ch))?;
+        }
+        Ok(())
+    }
+    
+    /// Queue a reply to the program running in the pane
+    pub fn respond(&mut self, data: &[u8]) {
+        self.pane.respond(data);
+    }
+    
+    pub fn set_title(&mut self, title: String) {
+        self.pane.set_title(title);
+    }
+    
+    pub fn user_var(&self, name: &str) -> Option<&str> {
+        self.pane.user_vars.get(name).map(String::as_str)
+    }
+    
+    /// Set a user variable, as iTerm2's `SetUserVar` does
+    pub fn set_user_var(&mut self, name: String, value: String) {
+        self.pane.user_vars.insert(name, value);
+    }
+    
+    /// Ask the frame to show a desktop notification
+    pub fn notify(&mut self, notification: DesktopNotification) -> PaneResult<()> {
+        self.pane.request_notification(notification)
+    }
+}
+
+impl Pane {
+    /// Run every handler that claims an unrecognized sequence
+    pub(crate) fn dispatch_custom_sequence(&mut self, sequence: &CustomSequence) {
+        let mut handlers = self.terminal.interpreter_mut().take_handlers();
+        
+        for handler in handlers.values_mut().filter(|handler| handler.can_handle(sequence)) {
+            let mut handle = PaneHandle { pane: self };
+            // A failing or panicking extension must not stall the output stream
+            let result = panic::catch_unwind(AssertUnwindSafe(|| handler.handle(sequence, &mut handle)));
+            if !matches!(result, Ok(Ok(()))) {
+                self.stats.handler_errors += 1;
+            }
+        }
+        
+        self.terminal.interpreter_mut().restore_handlers(handlers);
+    }
+}
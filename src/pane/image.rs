@@ -0,0 +1,537 @@
+use super::*;
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// Largest image accepted from any graphics protocol, in pixels per side
+pub const MAX_IMAGE_DIMENSION: u32 = 8192;
+
+/// A decoded RGBA image (8 bits per channel, row-major)
+#[derive(Debug, Clone, PartialEq)]
+pub struct RgbaImage {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl RgbaImage {
+    pub fn new(width: u32, height: u32) -> Self {
+        RgbaImage {
+            width,
+            height,
+            pixels: vec![0; width as usize * height as usize * 4],
+        }
+    }
+    
+    /// Build an image from packed RGBA data
+    pub fn from_rgba(width: u32, height: u32, data: &[u8]) -> PaneResult<Self> {
+        Self::check_dimensions(width, height)?;
+        let len = width as usize * height as usize * 4;
+        if data.len() < len {
+            return Err(PaneError::parse(format!("Expected {} bytes of RGBA data, got {}", len, data.len())));
+        }
+        Ok(RgbaImage { width, height, pixels: data[..len].to_vec() })
+    }
+    
+    /// Build an image from packed RGB data
+    pub fn from_rgb(width: u32, height: u32, data: &[u8]) -> PaneResult<Self> {
+        Self::check_dimensions(width, height)?;
+        let len = width as usize * height as usize * 3;
+        if data.len() < len {
+            return Err(PaneError::parse(format!("Expected {} bytes of RGB data, got {}", len, data.len())));
+        }
+        let pixels = data[..len]
+            .chunks_exact(3)
+            .flat_map(|rgb| [rgb[0], rgb[1], rgb[2], 255])
+            .collect();
+        Ok(RgbaImage { width, height, pixels })
+    }
+    
+    /// Decode a PNG file
+    pub fn decode_png(data: &[u8]) -> PaneResult<Self> {
+        let decoded = ::image::load_from_memory_with_format(data, ::image::ImageFormat::Png)
+            .map_err(|e| PaneError::parse(format!("Invalid PNG data: {}", e)))?;
+        Self::from_decoded(decoded)
+    }
+    
+    /// Decode a PNG, JPEG or GIF file, sniffing the format (GIFs yield their first frame)
+    pub fn decode(data: &[u8]) -> PaneResult<Self> {
+        let decoded = ::image::load_from_memory(data)
+            .map_err(|e| PaneError::parse(format!("Invalid image data: {}", e)))?;
+        Self::from_decoded(decoded)
+    }
+    
+    fn from_decoded(decoded: ::image::DynamicImage) -> PaneResult<Self> {
+        let decoded = decoded.to_rgba8();
+        Self::check_dimensions(decoded.width(), decoded.height())?;
+        Ok(RgbaImage {
+            width: decoded.width(),
+            height: decoded.height(),
+            pixels: decoded.into_raw(),
+        })
+    }
+    
+    /// Get the RGBA value of a pixel
+    pub fn pixel(&self, x: u32, y: u32) -> Option<[u8; 4]> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        let idx = (y as usize * self.width as usize + x as usize) * 4;
+        Some([self.pixels[idx], self.pixels[idx + 1], self.pixels[idx + 2], self.pixels[idx + 3]])
+    }
+    
+    /// Size of the pixel data in bytes
+    pub fn byte_size(&self) -> usize {
+        self.pixels.len()
+    }
+    
+    fn check_dimensions(width: u32, height: u32) -> PaneResult<()> {
+        if width == 0 || height == 0 || width > MAX_IMAGE_DIMENSION || height > MAX_IMAGE_DIMENSION {
+            return Err(PaneError::parse(format!("Unsupported image size {}x{}", width, height)));
+        }
+        Ok(())
+    }
+}
+
+/// Identifier for an image held in a pane's image layer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ImageId(pub u64);
+
+/// An image anchored to a range of cells
+///
+/// Rows are absolute: row 0 is the first line the pane ever produced, so a
+/// placement keeps pointing at the same text as that text scrolls into
+/// scrollback.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImagePlacement {
+    pub image_id: ImageId,
+    pub row: u64,
+    pub col: u16,
+    pub rows: u16,
+    pub cols: u16,
+    /// Stacking order relative to text (negative values draw below it)
+    pub z_index: i32,
+    /// Offset of the image within the top-left cell, in pixels
+    pub pixel_offset: (u16, u16),
+    /// Client-chosen placement id (kitty `p=`), 0 if none
+    pub placement_id: u32,
+}
+
+impl ImagePlacement {
+    pub fn new(image_id: ImageId, row: u64, col: u16, rows: u16, cols: u16) -> Self {
+        ImagePlacement {
+            image_id,
+            row,
+            col,
+            rows,
+            cols,
+            z_index: 0,
+            pixel_offset: (0, 0),
+            placement_id: 0,
+        }
+    }
+    
+    pub fn with_z_index(mut self, z_index: i32) -> Self {
+        self.z_index = z_index;
+        self
+    }
+    
+    pub fn with_pixel_offset(mut self, x: u16, y: u16) -> Self {
+        self.pixel_offset = (x, y);
+        self
+    }
+    
+    pub fn with_placement_id(mut self, placement_id: u32) -> Self {
+        self.placement_id = placement_id;
+        self
+    }
+    
+    /// Absolute row just past the bottom edge
+    pub fn end_row(&self) -> u64 {
+        self.row + self.rows as u64
+    }
+    
+    /// Column just past the right edge
+    pub fn end_col(&self) -> u16 {
+        self.col.saturating_add(self.cols)
+    }
+    
+    /// Check whether the placement covers any cell in the given region
+    pub fn intersects(&self, rows: &Range<u64>, cols: &Range<u16>) -> bool {
+        self.row < rows.end && rows.start < self.end_row()
+            && self.col < cols.end && cols.start < self.end_col()
+    }
+}
+
+#[derive(Debug, Clone)]
+struct StoredImage {
+    image: Arc<RgbaImage>,
+    /// Keep the image after its last placement goes away (kitty transmit)
+    retained: bool,
+}
+
+/// Images and their cell placements for a single pane
+#[derive(Debug, Clone, Default)]
+pub struct ImageLayer {
+    images: BTreeMap<ImageId, StoredImage>,
+    placements: Vec<ImagePlacement>,
+    /// Main screen placements, set aside while the alternate screen is shown
+    stashed_placements: Option<Vec<ImagePlacement>>,
+    next_id: u64,
+    memory_used: usize,
+}
+
+impl ImageLayer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Store an image that lives only as long as its placements (sixel)
+    pub fn add_image(&mut self, image: RgbaImage, budget: Option<usize>) -> PaneResult<ImageId> {
+        self.insert(image, budget, false)
+    }
+    
+    /// Store an image that is kept until explicitly removed (kitty graphics)
+    pub fn add_retained_image(&mut self, image: RgbaImage, budget: Option<usize>) -> PaneResult<ImageId> {
+        self.insert(image, budget, true)
+    }
+    
+    /// Anchor a stored image to a cell range
+    ///
+    /// A placement with the same non-zero placement id replaces the old one.
+    pub fn place(&mut self, placement: ImagePlacement) {
+        if !self.images.contains_key(&placement.image_id) {
+            return;
+        }
+        if placement.placement_id != 0 {
+            self.placements.retain(|p| {
+                p.image_id != placement.image_id || p.placement_id != placement.placement_id
+            });
+        }
+        self.placements.push(placement);
+    }
+    
+    /// Get a stored image
+    pub fn image(&self, id: ImageId) -> Option<Arc<RgbaImage>> {
+        self.images.get(&id).map(|stored| stored.image.clone())
+    }
+    
+    /// All placements, oldest first
+    pub fn placements(&self) -> &[ImagePlacement] {
+        &self.placements
+    }
+    
+    /// Placements that overlap the given absolute rows
+    pub fn placements_in_rows(&self, rows: Range<u64>) -> impl Iterator<Item = &ImagePlacement> {
+        self.placements.iter().filter(move |p| p.row < rows.end && rows.start < p.end_row())
+    }
+    
+    /// Remove an image and every placement that shows it
+    pub fn remove_image(&mut self, id: ImageId) {
+        if let Some(stored) = self.images.remove(&id) {
+            self.memory_used -= stored.image.byte_size();
+        }
+        self.placements.retain(|p| p.image_id != id);
+        if let Some(stashed) = self.stashed_placements.as_mut() {
+            stashed.retain(|p| p.image_id != id);
+        }
+    }
+    
+    /// Remove the placements matching a predicate, returning the affected images
+    pub fn remove_placements(&mut self, predicate: impl Fn(&ImagePlacement) -> bool) -> Vec<ImageId> {
+        let mut affected = Vec::new();
+        self.placements.retain(|p| {
+            let remove = predicate(p);
+            if remove && !affected.contains(&p.image_id) {
+                affected.push(p.image_id);
+            }
+            !remove
+        });
+        self.drop_unplaced_images();
+        affected
+    }
+    
+    /// Remove images (retained or not) that no longer have any placement
+    pub fn remove_unplaced(&mut self, ids: &[ImageId]) {
+        for id in ids {
+            if !self.is_placed(*id) {
+                self.remove_image(*id);
+            }
+        }
+    }
+    
+    /// Remove placements that overlap an erased region
+    pub fn erase(&mut self, rows: Range<u64>, cols: Range<u16>) {
+        self.remove_placements(|p| p.intersects(&rows, &cols));
+    }
+    
+    /// Move placements along with rows scrolled inside the margins, given the screen's top absolute row
+    ///
+    /// Placements only partly inside the moved rows, or pushed out of them, are removed like the
+    /// text under them.
+    pub(crate) fn shift_rows(&mut self, origin: u64, shift: &RowShift) {
+        let top = origin + shift.top as u64;
+        let bottom = origin + shift.bottom as u64 + 1;
+        let count = shift.count as u64;
+        let before = self.placements.len();
+        
+        if shift.into_scrollback {
+            // Rows down to the bottom margin keep their absolute rows as the origin moves down;
+            // rows below it stay on screen, so their absolute rows grow
+            self.placements.retain(|p| p.row >= bottom || p.end_row() <= bottom);
+            for placement in self.placements.iter_mut().filter(|p| p.row >= bottom) {
+                placement.row += count;
+            }
+        } else {
+            let rows = top..bottom;
+            let cols = shift.left..shift.right.saturating_add(1);
+            self.placements.retain_mut(|p| {
+                if !p.intersects(&rows, &cols) {
+                    return true;
+                }
+                let inside = rows.start <= p.row && p.end_row() <= rows.end
+                    && cols.start <= p.col && p.end_col() <= cols.end;
+                let moved = if shift.up { p.row.checked_sub(count) } else { Some(p.row + count) };
+                match moved {
+                    Some(row) if inside && row >= rows.start && row + p.rows as u64 <= rows.end => {
+                        p.row = row;
+                        true
+                    }
+                    _ => false,
+                }
+            });
+        }
+        
+        if self.placements.len() != before {
+            self.drop_unplaced_images();
+        }
+    }
+    
+    /// Remove placements that have scrolled out above `first_row`
+    pub fn prune_before(&mut self, first_row: u64) {
+        let before = self.placements.len();
+        self.placements.retain(|p| p.end_row() > first_row);
+        if self.placements.len() != before {
+            self.drop_unplaced_images();
+        }
+    }
+    
+    /// Set the main screen's placements aside when the alternate screen is shown
+    pub fn stash_placements(&mut self) {
+        if self.stashed_placements.is_none() {
+            self.stashed_placements = Some(std::mem::take(&mut self.placements));
+        }
+    }
+    
+    /// Bring back the main screen's placements, dropping those made on the alternate screen
+    pub fn restore_placements(&mut self) {
+        if let Some(stashed) = self.stashed_placements.take() {
+            self.placements = stashed;
+            self.drop_unplaced_images();
+        }
+    }
+    
+    /// Remove all images and placements
+    pub fn clear(&mut self) {
+        self.images.clear();
+        self.placements.clear();
+        self.stashed_placements = None;
+        self.memory_used = 0;
+    }
+    
+    /// Bytes of pixel data currently held
+    pub fn memory_usage(&self) -> usize {
+        self.memory_used
+    }
+    
+    /// Number of stored images
+    pub fn image_count(&self) -> usize {
+        self.images.len()
+    }
+    
+    /// Check if the layer holds no images
+    pub fn is_empty(&self) -> bool {
+        self.images.is_empty()
+    }
+    
+    fn insert(&mut self, image: RgbaImage, budget: Option<usize>, retained: bool) -> PaneResult<ImageId> {
+        let size = image.byte_size();
+        if let Some(budget) = budget {
+            if size > budget {
+                return Err(PaneError::BufferOverflow(size, budget));
+            }
+            while self.memory_used + size > budget {
+                let Some((&oldest, _)) = self.images.iter().next() else { break };
+                self.remove_image(oldest);
+            }
+        }
+        
+        self.next_id += 1;
+        let id = ImageId(self.next_id);
+        self.memory_used += size;
+        self.images.insert(id, StoredImage { image: Arc::new(image), retained });
+        Ok(id)
+    }
+    
+    /// Whether an image has a placement on either screen
+    fn is_placed(&self, id: ImageId) -> bool {
+        self.placements.iter().chain(self.stashed_placements.iter().flatten()).any(|p| p.image_id == id)
+    }
+    
+    fn drop_unplaced_images(&mut self) {
+        let placements = &self.placements;
+        let stashed = self.stashed_placements.as_deref().unwrap_or_default();
+        let mut freed = 0;
+        self.images.retain(|id, stored| {
+            let keep = stored.retained || placements.iter().chain(stashed).any(|p| p.image_id == *id);
+            if !keep {
+                freed += stored.image.byte_size();
+            }
+            keep
+        });
+        self.memory_used -= freed;
+    }
+}
+
+/// Shared handle to a pane's image layer
+///
+/// The pane writes through the store while glazing holds a clone to read
+/// placements when rendering, so image data is never copied between layers.
+#[derive(Debug, Clone, Default)]
+pub struct ImageStore {
+    layer: Arc<RwLock<ImageLayer>>,
+}
+
+impl ImageStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Lock the layer for reading
+    pub fn read(&self) -> RwLockReadGuard<'_, ImageLayer> {
+        self.layer.read().unwrap_or_else(PoisonError::into_inner)
+    }
+    
+    /// Lock the layer for writing
+    pub fn write(&self) -> RwLockWriteGuard<'_, ImageLayer> {
+        self.layer.write().unwrap_or_else(PoisonError::into_inner)
+    }
+    
+    /// Check whether two handles share the same layer
+    pub fn ptr_eq(&self, other: &ImageStore) -> bool {
+        Arc::ptr_eq(&self.layer, &other.layer)
+    }
+}
+
+impl Pane {
+    /// Absolute row of the top screen line
+    pub(crate) fn screen_origin_row(&self) -> u64 {
+        self.scrollback.lines_pushed
+    }
+    
+    /// Absolute row of the cursor
+    pub(crate) fn cursor_absolute_row(&self) -> u64 {
+        self.screen_origin_row() + self.cursor.position.row as u64
+    }
+    
+    /// Number of cells covered by an image of the given pixel size
+    pub(crate) fn image_cell_extent(&self, width: u32, height: u32) -> (u16, u16) {
+        let (cell_width, cell_height) = self.config.cell_size;
+        let cols = (width as usize).div_ceil(cell_width.max(1) as usize);
+        let rows = (height as usize).div_ceil(cell_height.max(1) as usize);
+        (cols.min(u16::MAX as usize) as u16, rows.min(u16::MAX as usize) as u16)
+    }
+    
+    /// Decode a sixel stream and place it at the cursor
+    pub(crate) fn display_sixel(&mut self, params: &[i32], data: &[u8]) -> PaneResult<()> {
+        let image = SixelDecoder::decode(params, data)?;
+        if image.width == 0 || image.height == 0 {
+            return Ok(());
+        }
+        
+        let (cols, rows) = self.image_cell_extent(image.width, image.height);
+        let budget = self.image_memory_budget();
+        let row = self.cursor_absolute_row();
+        let col = self.cursor.position.col;
+        {
+            let mut layer = self.images.write();
+            let image_id = layer.add_image(image, budget)?;
+            layer.place(ImagePlacement::new(image_id, row, col, rows, cols));
+        }
+        
+        // Sixel scrolling: the cursor ends up on the line below the image
+        for _ in 0..rows {
+            self.cursor.line_feed(&mut self.screen_buffer, &mut self.scrollback, &self.modes)?;
+        }
+        self.cursor.position.col = col;
+        self.cursor.clamp_to_screen(&self.screen_buffer);
+        self.prune_scrolled_images();
+        Ok(())
+    }
+    
+    /// Drop image placements for text that has left the scrollback
+    pub(crate) fn prune_scrolled_images(&mut self) {
+        let first_row = self.scrollback.first_absolute_row();
+        let mut layer = self.images.write();
+        if !layer.placements().is_empty() {
+            layer.prune_before(first_row);
+        }
+    }
+    
+    /// Apply the row moves recorded by the screen since the last call to the image placements
+    pub(crate) fn shift_scrolled_images(&mut self) {
+        let shifts = std::mem::take(&mut self.screen_buffer.row_shifts);
+        let mut layer = self.images.write();
+        if shifts.is_empty() || layer.placements().is_empty() {
+            return;
+        }
+        
+        // Replay from the screen origin the first shift saw
+        let pushed: u64 = shifts.iter().filter(|shift| shift.into_scrollback).map(|shift| shift.count as u64).sum();
+        let mut origin = self.scrollback.lines_pushed.saturating_sub(pushed);
+        for shift in &shifts {
+            layer.shift_rows(origin, shift);
+            if shift.into_scrollback {
+                origin += shift.count as u64;
+            }
+        }
+    }
+    
+    /// Remove images under an erase operation on the screen
+    pub(crate) fn erase_images(&mut self, clear_type: ClearType, whole_screen: bool) {
+        let origin = self.screen_origin_row();
+        let row = self.cursor_absolute_row();
+        let col = self.cursor.position.col;
+        let (width, height) = self.size();
+        let bottom = origin + height as u64;
+        
+        let mut layer = self.images.write();
+        if layer.placements().is_empty() {
+            return;
+        }
+        
+        match (clear_type, whole_screen) {
+            (ClearType::All, true) => layer.erase(origin..bottom, 0..width),
+            (ClearType::All, false) => layer.erase(row..row + 1, 0..width),
+            (ClearType::ToEnd, whole_screen) => {
+                layer.erase(row..row + 1, col..width);
+                if whole_screen {
+                    layer.erase(row + 1..bottom, 0..width);
+                }
+            }
+            (ClearType::ToBeginning, whole_screen) => {
+                layer.erase(row..row + 1, 0..col.saturating_add(1));
+                if whole_screen {
+                    layer.erase(origin..row, 0..width);
+                }
+            }
+        }
+    }
+    
+    /// Bytes available for images under `PaneConfig::max_memory_usage`
+    pub(crate) fn image_memory_budget(&self) -> Option<usize> {
+        let limit = self.config.max_memory_usage?;
+        let text_usage = self.estimate_memory_usage() - self.images.read().memory_usage();
+        Some(limit.saturating_sub(text_usage))
+    }
+}
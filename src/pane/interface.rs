@@ -1,5 +1,6 @@
 use super::*;
 use crate::sash::{PaneId, Theme};
+use std::sync::RwLockReadGuard;
 
 /// Primary interface for Pane operations
 pub trait PaneInterface: Send + Sync {
@@ -14,6 +15,9 @@ pub trait PaneInterface: Send + Sync {
     /// Set the active state
     fn set_active(&mut self, active: bool);
     
+    /// Gain or lose keyboard focus, reporting it to the program if it enabled mode 1004
+    fn set_focused(&mut self, focused: bool) -> PaneResult<()>;
+    
     /// Get the pane title
     fn get_title(&self) -> &str;
     
@@ -45,12 +49,18 @@ pub trait PaneInterface: Send + Sync {
     /// Write input data to the PTY
     fn write_input(&mut self, data: &[u8]) -> PaneResult<usize>;
     
+    /// Send pasted text, bracketed if the program asked for it
+    fn paste(&mut self, text: &str) -> PaneResult<()>;
+    
     /// Read output data from the PTY
     fn read_output(&mut self) -> PaneResult<Vec<u8>>;
     
     /// Process output data through the terminal emulator
     fn process_output(&mut self, data: &[u8]) -> PaneResult<()>;
     
+    /// Take replies generated by the emulator that have not been written to a PTY
+    fn take_responses(&mut self) -> Vec<u8>;
+    
     // ========== Terminal State ==========
     
     /// Resize the terminal
@@ -65,6 +75,15 @@ pub trait PaneInterface: Send + Sync {
     /// Get the current terminal mode
     fn get_terminal_mode(&self) -> TerminalMode;
     
+    /// Get the kitty keyboard protocol flags requested by the program
+    fn keyboard_flags(&self) -> KeyboardFlags;
+    
+    /// Get the mode flags that affect rendering and key encoding
+    fn get_terminal_modes(&self) -> &TerminalModes;
+    
+    /// Whether the program is in the middle of a synchronized update (mode 2026)
+    fn is_synchronized_update(&self) -> bool;
+    
     // ========== Content Access ==========
     
     /// Get a specific line from the screen buffer
@@ -93,6 +112,37 @@ pub trait PaneInterface: Send + Sync {
     /// Clear the scrollback buffer
     fn clear_scrollback(&mut self) -> PaneResult<()>;
     
+    // ========== Shell Integration ==========
+    
+    /// Find the closest prompt above the given absolute line (scrollback first, then screen)
+    fn previous_prompt(&self, from_line: usize) -> Option<usize>;
+    
+    /// Find the closest prompt below the given absolute line
+    fn next_prompt(&self, from_line: usize) -> Option<usize>;
+    
+    /// Select the output of the most recent command
+    fn select_last_command_output(&self) -> Option<Selection>;
+    
+    /// Get the recorded command blocks, oldest first
+    fn command_blocks(&self) -> Vec<CommandBlock>;
+    
+    /// Find the closest line marked with iTerm2's SetMark above the given absolute line
+    fn previous_mark(&self, from_line: usize) -> Option<usize>;
+    
+    /// Find the closest marked line below the given absolute line
+    fn next_mark(&self, from_line: usize) -> Option<usize>;
+    
+    /// Get a user variable set with iTerm2's SetUserVar
+    fn user_var(&self, name: &str) -> Option<&str>;
+    
+    // ========== Graphics ==========
+    
+    /// Get the images displayed in this pane and their cell placements
+    fn image_layer(&self) -> RwLockReadGuard<'_, ImageLayer>;
+    
+    /// Get a shared handle to the image store, for the renderer
+    fn image_store(&self) -> ImageStore;
+    
     // ========== Configuration ==========
     
     /// Update the pane configuration
@@ -112,11 +162,20 @@ pub trait PaneInterface: Send + Sync {
     /// Emit an event
     fn emit_event(&mut self, event: PaneEvent) -> PaneResult<()>;
     
+    /// Register a handler for sequences ratty does not recognize, returning any it replaces
+    fn register_sequence_handler(&mut self, name: String, handler: Box<dyn VtCommandHandler>) -> Option<Box<dyn VtCommandHandler>>;
+    
+    /// Remove a sequence handler by name
+    fn unregister_sequence_handler(&mut self, name: &str) -> Option<Box<dyn VtCommandHandler>>;
+    
     // ========== Statistics and Diagnostics ==========
     
     /// Get pane statistics
     fn get_statistics(&self) -> PaneStatistics;
     
+    /// Recently received sequences, while `log_terminal_sequences` is on
+    fn sequence_trace(&self) -> Option<&SequenceTrace>;
+    
     /// Validate the internal state
     fn validate_state(&self) -> PaneResult<()>;
     
@@ -151,6 +210,19 @@ impl PaneInterface for Pane {
         }
     }
     
+    fn set_focused(&mut self, focused: bool) -> PaneResult<()> {
+        if self.focused == focused {
+            return Ok(());
+        }
+        self.focused = focused;
+        
+        if self.modes.focus_reporting {
+            self.respond(if focused { b"\x1b[I" } else { b"\x1b[O" });
+            self.flush_responses()?;
+        }
+        Ok(())
+    }
+    
     fn get_title(&self) -> &str {
         &self.title
     }
@@ -181,8 +253,18 @@ impl PaneInterface for Pane {
             self.pty = Some(PtyFactory::create());
         }
         
+        // Resolve TERM once per spawn and keep it in the config, so XTGETTCAP TN reports the same name
+        let term = env.iter()
+            .find(|(key, _)| key == "TERM")
+            .map(|(_, value)| value.clone())
+            .or_else(|| self.config.environment_variables.get("TERM").cloned())
+            .unwrap_or_else(|| term_name().to_string());
+        self.config.environment_variables.insert("TERM".to_string(), term.clone());
+        let mut env: Vec<(String, String)> = env.iter().filter(|(key, _)| key != "TERM").cloned().collect();
+        env.push(("TERM".to_string(), term));
+        
         if let Some(ref mut pty) = self.pty {
-            pty.spawn(command, args, env)?;
+            pty.spawn(command, args, &env)?;
             
             if let Some(pid) = pty.pid() {
                 self.stats.record_spawn();
@@ -231,6 +313,19 @@ impl PaneInterface for Pane {
         }
     }
     
+    fn paste(&mut self, text: &str) -> PaneResult<()> {
+        if self.modes.bracketed_paste {
+            // Escapes are dropped so the text cannot end the bracket early
+            let text: String = text.chars().filter(|&ch| ch != '\x1b').collect();
+            self.respond(b"\x1b[200~");
+            self.respond(text.as_bytes());
+            self.respond(b"\x1b[201~");
+        } else {
+            self.respond(text.as_bytes());
+        }
+        self.flush_responses()
+    }
+    
     fn read_output(&mut self) -> PaneResult<Vec<u8>> {
         if let Some(ref mut pty) = self.pty {
             let data = pty.read()?;
@@ -248,6 +343,10 @@ impl PaneInterface for Pane {
         Ok(())
     }
     
+    fn take_responses(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.pending_responses)
+    }
+    
     // ========== Terminal State ==========
     
     fn resize(&mut self, rows: u16, cols: u16) -> PaneResult<()> {
@@ -262,11 +361,14 @@ impl PaneInterface for Pane {
             pty.resize(rows, cols)?;
         }
         
-        // Resize screen buffer
-        self.screen_buffer.resize(cols, rows)?;
+        // Resize screen buffers
+        self.resize_screens(cols, rows)?;
         
         // Update tab stops for new width
         self.tabs = TabStops::new(cols);
+        // The last column has moved, so a wrap pending there no longer applies
+        self.cursor.pending_wrap = false;
+        self.cursor.clamp_to_screen(&self.screen_buffer);
         
         // Emit resize event
         let _ = self.emit_event(PaneEvent::Resized(cols, rows));
@@ -286,6 +388,18 @@ impl PaneInterface for Pane {
         self.terminal.current_mode()
     }
     
+    fn keyboard_flags(&self) -> KeyboardFlags {
+        self.keyboard_modes.current()
+    }
+    
+    fn get_terminal_modes(&self) -> &TerminalModes {
+        &self.modes
+    }
+    
+    fn is_synchronized_update(&self) -> bool {
+        self.synchronized_update_active()
+    }
+    
     // ========== Content Access ==========
     
     fn get_line(&self, index: usize) -> Option<&Line> {
@@ -366,15 +480,59 @@ impl PaneInterface for Pane {
     }
     
     fn clear_screen(&mut self) -> PaneResult<()> {
-        self.screen_buffer.clear_screen(ClearType::All, &mut self.cursor)?;
+        self.erase_images(ClearType::All, true);
+        self.screen_buffer.clear_screen(ClearType::All, &self.cursor)?;
+        // ED 2 leaves the cursor alone, but a user-requested clear starts over at the top
+        self.cursor.set_position(1, 1, &self.screen_buffer)?;
         Ok(())
     }
     
     fn clear_scrollback(&mut self) -> PaneResult<()> {
         self.scrollback.clear();
+        self.prune_scrolled_images();
         Ok(())
     }
     
+    // ========== Shell Integration ==========
+    
+    fn previous_prompt(&self, from_line: usize) -> Option<usize> {
+        self.find_previous_prompt(from_line)
+    }
+    
+    fn next_prompt(&self, from_line: usize) -> Option<usize> {
+        self.find_next_prompt(from_line)
+    }
+    
+    fn select_last_command_output(&self) -> Option<Selection> {
+        self.last_command_output_selection()
+    }
+    
+    fn command_blocks(&self) -> Vec<CommandBlock> {
+        self.command_history.blocks().cloned().collect()
+    }
+    
+    fn previous_mark(&self, from_line: usize) -> Option<usize> {
+        self.find_previous_mark(from_line)
+    }
+    
+    fn next_mark(&self, from_line: usize) -> Option<usize> {
+        self.find_next_mark(from_line)
+    }
+    
+    fn user_var(&self, name: &str) -> Option<&str> {
+        self.user_vars.get(name).map(String::as_str)
+    }
+    
+    // ========== Graphics ==========
+    
+    fn image_layer(&self) -> RwLockReadGuard<'_, ImageLayer> {
+        self.images.read()
+    }
+    
+    fn image_store(&self) -> ImageStore {
+        self.images.clone()
+    }
+    
     // ========== Configuration ==========
     
     fn update_config(&mut self, config: PaneConfig) -> PaneResult<()> {
@@ -399,11 +557,7 @@ impl PaneInterface for Pane {
         if config.show_cursor != self.config.show_cursor {
             let visibility = if config.show_cursor {
                 if config.cursor_blink {
-                    match config.cursor_style {
-                        CursorStyle::Block => CursorVisibility::BlinkingBlock,
-                        CursorStyle::Underline => CursorVisibility::BlinkingUnderline,
-                        CursorStyle::Bar => CursorVisibility::BlinkingBar,
-                    }
+                    CursorVisibility::blinking(config.cursor_style)
                 } else {
                     CursorVisibility::Visible
                 }
@@ -416,6 +570,10 @@ impl PaneInterface for Pane {
         // Apply terminal mode changes
         self.modes.auto_wrap = config.auto_wrap;
         self.modes.cursor_visible = config.show_cursor;
+        self.modes.sgr21_double_underline = config.sgr21_double_underline;
+        
+        // Start, stop or reopen the sequence trace
+        self.configure_trace(&config)?;
         
         self.config = config;
         Ok(())
@@ -457,6 +615,14 @@ impl PaneInterface for Pane {
         Ok(())
     }
     
+    fn register_sequence_handler(&mut self, name: String, handler: Box<dyn VtCommandHandler>) -> Option<Box<dyn VtCommandHandler>> {
+        self.terminal.register_handler(name, handler)
+    }
+    
+    fn unregister_sequence_handler(&mut self, name: &str) -> Option<Box<dyn VtCommandHandler>> {
+        self.terminal.unregister_handler(name)
+    }
+    
     // ========== Statistics and Diagnostics ==========
     
     fn get_statistics(&self) -> PaneStatistics {
@@ -468,6 +634,10 @@ impl PaneInterface for Pane {
         stats
     }
     
+    fn sequence_trace(&self) -> Option<&SequenceTrace> {
+        self.trace.as_ref()
+    }
+    
     fn validate_state(&self) -> PaneResult<()> {
         // Check cursor position is valid
         let (width, height) = self.get_size();
@@ -478,6 +648,15 @@ impl PaneInterface for Pane {
             ));
         }
         
+        // A double-width line holds half as many columns
+        let line_width = self.screen_buffer.line_width(self.cursor.position.row);
+        if self.cursor.position.col >= line_width {
+            return Err(PaneError::state(
+                format!("Cursor column {} is past the end of row {}, which is {} columns wide",
+                       self.cursor.position.col, self.cursor.position.row, line_width)
+            ));
+        }
+        
         // Check screen buffer integrity
         if self.screen_buffer.lines.len() != height as usize {
             return Err(PaneError::state(
@@ -495,6 +674,16 @@ impl PaneInterface for Pane {
             }
         }
         
+        // Check the scrolling region lies on the screen
+        let margins = self.screen_buffer.margins;
+        if margins.top > margins.bottom || margins.bottom >= height
+            || margins.left > margins.right || margins.right >= width
+        {
+            return Err(PaneError::state(
+                format!("Margins {:?} are invalid for size {}x{}", margins, width, height)
+            ));
+        }
+        
         Ok(())
     }
     
@@ -504,6 +693,10 @@ impl PaneInterface for Pane {
     }
     
     fn needs_render(&self) -> bool {
+        // Hold the frame until the program finishes its batch (or the batch times out)
+        if self.synchronized_update_active() {
+            return false;
+        }
         !self.screen_buffer.dirty_regions.dirty_lines.iter().all(|&dirty| !dirty) ||
         self.screen_buffer.dirty_regions.all_dirty
     }
@@ -511,7 +704,7 @@ impl PaneInterface for Pane {
 
 impl Pane {
     /// Estimate memory usage of this pane
-    fn estimate_memory_usage(&self) -> usize {
+    pub(crate) fn estimate_memory_usage(&self) -> usize {
         let mut size = std::mem::size_of::<Pane>();
         
         // Screen buffer
@@ -526,6 +719,9 @@ impl Pane {
             size += line.cells.len() * std::mem::size_of::<Cell>();
         }
         
+        // Image layer
+        size += self.images.read().memory_usage();
+        
         // Other allocations
         size += self.title.len();
         size += self.config.environment_variables.len() * 64; // Estimate for env vars
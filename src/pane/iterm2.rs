@@ -0,0 +1,237 @@
+use super::*;
+use base64::Engine;
+
+/// An iTerm2 proprietary escape (`OSC 1337 ; <command> ST`)
+#[derive(Debug, Clone, PartialEq)]
+pub enum Iterm2Command {
+    /// `File=<args>:<base64>` - transfer a file, displayed when `inline=1`
+    File(InlineImage),
+    /// `SetUserVar=<name>=<base64 value>`
+    SetUserVar { name: String, value: String },
+    /// `CurrentDir=<path>`
+    CurrentDir(String),
+    /// `SetMark` - mark the cursor line for navigation
+    SetMark,
+    /// `ClearScrollback`
+    ClearScrollback,
+}
+
+impl Iterm2Command {
+    /// Parse the payload of an OSC 1337 sequence (everything after "1337;")
+    pub fn parse(payload: &str) -> Option<Self> {
+        let (key, value) = payload.split_once('=').unwrap_or((payload, ""));
+        match key {
+            "File" => InlineImage::parse(value).map(Iterm2Command::File),
+            "SetUserVar" => {
+                let (name, encoded) = value.split_once('=')?;
+                let decoded = base64::engine::general_purpose::STANDARD.decode(encoded).ok()?;
+                Some(Iterm2Command::SetUserVar {
+                    name: name.to_string(),
+                    value: String::from_utf8_lossy(&decoded).into_owned(),
+                })
+            }
+            "CurrentDir" if !value.is_empty() => Some(Iterm2Command::CurrentDir(value.to_string())),
+            "SetMark" => Some(Iterm2Command::SetMark),
+            "ClearScrollback" => Some(Iterm2Command::ClearScrollback),
+            _ => None,
+        }
+    }
+}
+
+/// A requested image width or height
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageDimension {
+    /// Use the image's own size
+    Auto,
+    Cells(u32),
+    Pixels(u32),
+    /// Percentage of the pane's width or height
+    Percent(u32),
+}
+
+impl ImageDimension {
+    /// Parse `N`, `Npx`, `N%` or `auto`
+    pub fn parse(value: &str) -> Option<Self> {
+        if value == "auto" {
+            return Some(ImageDimension::Auto);
+        }
+        if let Some(pixels) = value.strip_suffix("px") {
+            return pixels.parse().ok().map(ImageDimension::Pixels);
+        }
+        if let Some(percent) = value.strip_suffix('%') {
+            return percent.parse().ok().map(ImageDimension::Percent);
+        }
+        value.parse().ok().map(ImageDimension::Cells)
+    }
+    
+    /// Resolve to pixels given the cell size and pane extent along this axis
+    fn to_pixels(self, cell_pixels: u16, pane_cells: u16) -> Option<u64> {
+        match self {
+            ImageDimension::Auto => None,
+            ImageDimension::Cells(cells) => Some(cells as u64 * cell_pixels as u64),
+            ImageDimension::Pixels(pixels) => Some(pixels as u64),
+            ImageDimension::Percent(percent) => Some(pane_cells as u64 * cell_pixels as u64 * percent.min(100) as u64 / 100),
+        }
+    }
+}
+
+/// Arguments and payload of an OSC 1337 `File=` transfer
+#[derive(Debug, Clone, PartialEq)]
+pub struct InlineImage {
+    pub name: Option<String>,
+    /// Declared file size in bytes
+    pub size: Option<usize>,
+    pub width: ImageDimension,
+    pub height: ImageDimension,
+    pub preserve_aspect_ratio: bool,
+    /// Display the file instead of downloading it
+    pub inline: bool,
+    pub do_not_move_cursor: bool,
+    /// Base64-encoded file contents
+    pub payload: String,
+}
+
+impl Default for InlineImage {
+    fn default() -> Self {
+        InlineImage {
+            name: None,
+            size: None,
+            width: ImageDimension::Auto,
+            height: ImageDimension::Auto,
+            preserve_aspect_ratio: true,
+            inline: false,
+            do_not_move_cursor: false,
+            payload: String::new(),
+        }
+    }
+}
+
+impl InlineImage {
+    /// Parse `key=value;key=value:<base64>` (everything after "File=")
+    pub fn parse(value: &str) -> Option<Self> {
+        let (args, payload) = value.split_once(':')?;
+        let mut image = InlineImage {
+            payload: payload.to_string(),
+            ..Default::default()
+        };
+        
+        for arg in args.split(';').filter(|arg| !arg.is_empty()) {
+            let (key, value) = arg.split_once('=')?;
+            match key {
+                "name" => {
+                    let decoded = base64::engine::general_purpose::STANDARD.decode(value).ok()?;
+                    image.name = Some(String::from_utf8_lossy(&decoded).into_owned());
+                }
+                "size" => image.size = value.parse().ok(),
+                "width" => image.width = ImageDimension::parse(value)?,
+                "height" => image.height = ImageDimension::parse(value)?,
+                "preserveAspectRatio" => image.preserve_aspect_ratio = value != "0",
+                "inline" => image.inline = value == "1",
+                "doNotMoveCursor" => image.do_not_move_cursor = value == "1",
+                _ => {} // Ignore type hints and future keys
+            }
+        }
+        Some(image)
+    }
+    
+    /// Decode the payload into an RGBA image (PNG, JPEG or the first GIF frame)
+    pub fn decode(&self) -> PaneResult<RgbaImage> {
+        let data = base64::engine::general_purpose::STANDARD.decode(self.payload.trim())
+            .map_err(|e| PaneError::parse(format!("Invalid inline image payload: {}", e)))?;
+        RgbaImage::decode(&data)
+    }
+}
+
+impl Pane {
+    /// Apply an OSC 1337 command
+    pub(crate) fn handle_iterm2_command(&mut self, command: Iterm2Command) -> PaneResult<()> {
+        match command {
+            // Downloads (inline=0) have nowhere to go
+            Iterm2Command::File(image) if image.inline => self.display_inline_image(&image)?,
+            Iterm2Command::File(_) => self.stats.unhandled_sequences += 1,
+            Iterm2Command::SetUserVar { name, value } => {
+                self.user_vars.insert(name, value);
+            }
+            Iterm2Command::CurrentDir(path) => self.config.working_directory = Some(path),
+            Iterm2Command::SetMark => {
+                let row = self.cursor.position.row as usize;
+                if let Some(line) = self.screen_buffer.get_line_mut(row) {
+                    line.marked = true;
+                }
+            }
+            Iterm2Command::ClearScrollback => self.clear_scrollback()?,
+        }
+        Ok(())
+    }
+    
+    /// Decode an inline image and place it at the cursor
+    fn display_inline_image(&mut self, request: &InlineImage) -> PaneResult<()> {
+        let image = request.decode()?;
+        let (cols, rows) = self.inline_image_extent(request, image.width, image.height);
+        let budget = self.image_memory_budget();
+        let row = self.cursor_absolute_row();
+        let col = self.cursor.position.col;
+        {
+            let mut layer = self.images.write();
+            let image_id = layer.add_image(image, budget)?;
+            layer.place(ImagePlacement::new(image_id, row, col, rows, cols));
+        }
+        
+        if !request.do_not_move_cursor {
+            // Like kitty, leave the cursor just right of the image on its last row
+            for _ in 1..rows {
+                self.cursor.line_feed(&mut self.screen_buffer, &mut self.scrollback, &self.modes)?;
+            }
+            let last_col = self.screen_buffer.line_width(self.cursor.position.row).saturating_sub(1);
+            self.cursor.position.col = col.saturating_add(cols).min(last_col);
+            self.prune_scrolled_images();
+        }
+        Ok(())
+    }
+    
+    /// Number of cells an inline image covers after applying width, height and aspect ratio
+    pub(crate) fn inline_image_extent(&self, request: &InlineImage, width: u32, height: u32) -> (u16, u16) {
+        let (cell_width, cell_height) = self.config.cell_size;
+        let (pane_cols, pane_rows) = self.size();
+        let natural = (width.max(1) as u64, height.max(1) as u64);
+        let requested_width = request.width.to_pixels(cell_width, pane_cols);
+        let requested_height = request.height.to_pixels(cell_height, pane_rows);
+        
+        let (target_width, target_height) = match (requested_width, requested_height) {
+            (None, None) => natural,
+            (Some(w), None) if request.preserve_aspect_ratio => (w, natural.1 * w / natural.0),
+            (None, Some(h)) if request.preserve_aspect_ratio => (natural.0 * h / natural.1, h),
+            (Some(w), None) => (w, natural.1),
+            (None, Some(h)) => (natural.0, h),
+            (Some(w), Some(h)) if request.preserve_aspect_ratio => {
+                // Fit inside the requested box
+                if w * natural.1 <= h * natural.0 {
+                    (w, natural.1 * w / natural.0)
+                } else {
+                    (natural.0 * h / natural.1, h)
+                }
+            }
+            (Some(w), Some(h)) => (w, h),
+        };
+        
+        let clamp = |pixels: u64| pixels.clamp(1, u32::MAX as u64) as u32;
+        let (cols, rows) = self.image_cell_extent(clamp(target_width), clamp(target_height));
+        (cols.max(1), rows.max(1))
+    }
+    
+    /// Absolute line indices of all lines marked with SetMark, in order
+    fn marked_lines(&self) -> Vec<usize> {
+        self.all_lines().enumerate()
+            .filter(|(_, line)| line.marked)
+            .map(|(idx, _)| idx)
+            .collect()
+    }
+    
+    pub(crate) fn find_previous_mark(&self, from_line: usize) -> Option<usize> {
+        self.marked_lines().into_iter().rev().find(|&line| line < from_line)
+    }
+    
+    pub(crate) fn find_next_mark(&self, from_line: usize) -> Option<usize> {
+        self.marked_lines().into_iter().find(|&line| line > from_line)
+    }
+}
@@ -0,0 +1,145 @@
+use super::*;
+
+/// Most entries kept on a kitty keyboard flag stack before the oldest is evicted
+pub const KEYBOARD_STACK_LIMIT: usize = 16;
+
+/// Kitty keyboard protocol progressive enhancement flags
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct KeyboardFlags(u8);
+
+impl KeyboardFlags {
+    pub const NONE: KeyboardFlags = KeyboardFlags(0);
+    pub const DISAMBIGUATE: KeyboardFlags = KeyboardFlags(0b1);
+    pub const REPORT_EVENT_TYPES: KeyboardFlags = KeyboardFlags(0b10);
+    pub const REPORT_ALTERNATE_KEYS: KeyboardFlags = KeyboardFlags(0b100);
+    pub const REPORT_ALL_KEYS: KeyboardFlags = KeyboardFlags(0b1000);
+    pub const REPORT_ASSOCIATED_TEXT: KeyboardFlags = KeyboardFlags(0b10000);
+    
+    /// Build flags from a CSI parameter, dropping unknown bits
+    pub fn from_bits(bits: i32) -> Self {
+        KeyboardFlags((bits & 0b11111) as u8)
+    }
+    
+    pub fn bits(self) -> u8 {
+        self.0
+    }
+    
+    pub fn contains(self, other: KeyboardFlags) -> bool {
+        self.0 & other.0 == other.0
+    }
+    
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl std::ops::BitOr for KeyboardFlags {
+    type Output = KeyboardFlags;
+    
+    fn bitor(self, rhs: KeyboardFlags) -> KeyboardFlags {
+        KeyboardFlags(self.0 | rhs.0)
+    }
+}
+
+/// How `CSI = flags ; mode u` combines flags with the current ones
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyboardFlagsMode {
+    Replace,
+    Set,
+    Clear,
+}
+
+impl KeyboardFlagsMode {
+    pub fn from_param(param: i32) -> Self {
+        match param {
+            2 => KeyboardFlagsMode::Set,
+            3 => KeyboardFlagsMode::Clear,
+            _ => KeyboardFlagsMode::Replace,
+        }
+    }
+}
+
+/// Kitty keyboard flag stacks, one each for the main and alternate screens
+#[derive(Debug, Clone, Default)]
+pub struct KeyboardModeStack {
+    main: Vec<KeyboardFlags>,
+    alternate: Vec<KeyboardFlags>,
+    alternate_active: bool,
+}
+
+impl KeyboardModeStack {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Flags in effect for the active screen
+    pub fn current(&self) -> KeyboardFlags {
+        self.active().last().copied().unwrap_or_default()
+    }
+    
+    /// Push flags (`CSI > flags u`), evicting the oldest entry when full
+    pub fn push(&mut self, flags: KeyboardFlags) {
+        let stack = self.active_mut();
+        if stack.len() >= KEYBOARD_STACK_LIMIT {
+            stack.remove(0);
+        }
+        stack.push(flags);
+    }
+    
+    /// Pop entries (`CSI < count u`); popping everything restores legacy encoding
+    pub fn pop(&mut self, count: usize) {
+        let stack = self.active_mut();
+        let keep = stack.len().saturating_sub(count);
+        stack.truncate(keep);
+    }
+    
+    /// Modify the flags at the top of the stack (`CSI = flags ; mode u`)
+    pub fn set(&mut self, flags: KeyboardFlags, mode: KeyboardFlagsMode) {
+        let current = self.current();
+        let updated = match mode {
+            KeyboardFlagsMode::Replace => flags,
+            KeyboardFlagsMode::Set => current | flags,
+            KeyboardFlagsMode::Clear => KeyboardFlags(current.0 & !flags.0),
+        };
+        
+        let stack = self.active_mut();
+        match stack.last_mut() {
+            Some(top) => *top = updated,
+            None => stack.push(updated),
+        }
+    }
+    
+    /// Switch to the stack belonging to the main or alternate screen
+    pub fn set_alternate_screen(&mut self, active: bool) {
+        self.alternate_active = active;
+    }
+    
+    /// Reset both stacks (RIS)
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+    
+    fn active(&self) -> &Vec<KeyboardFlags> {
+        if self.alternate_active { &self.alternate } else { &self.main }
+    }
+    
+    fn active_mut(&mut self) -> &mut Vec<KeyboardFlags> {
+        if self.alternate_active { &mut self.alternate } else { &mut self.main }
+    }
+}
+
+impl Pane {
+    /// Apply a kitty keyboard protocol request from the program
+    pub(crate) fn handle_keyboard_command(&mut self, command: VtCommand) {
+        match command {
+            VtCommand::QueryKeyboardFlags => {
+                let reply = format!("\x1b[?{}u", self.keyboard_modes.current().bits());
+                self.respond(reply.as_bytes());
+            }
+            VtCommand::PushKeyboardFlags(flags) => self.keyboard_modes.push(flags),
+            VtCommand::PopKeyboardFlags(count) => self.keyboard_modes.pop(count as usize),
+            VtCommand::SetKeyboardFlags(flags, mode) => self.keyboard_modes.set(flags, mode),
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,486 @@
+use super::*;
+use base64::engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig};
+use base64::Engine;
+use std::collections::HashMap;
+
+/// Kitty clients are inconsistent about base64 padding, so accept both forms
+const PAYLOAD_ENGINE: GeneralPurpose = GeneralPurpose::new(
+    &base64::alphabet::STANDARD,
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
+/// First id handed out for images transmitted with only an image number
+const AUTO_IMAGE_ID_START: u32 = 1 << 31;
+
+/// Action requested by a kitty graphics command (`a=`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KittyGraphicsAction {
+    Transmit,
+    TransmitAndPut,
+    Put,
+    Delete,
+    Query,
+    /// Animation and composition actions
+    Unsupported(u8),
+}
+
+/// Pixel format of transmitted data (`f=`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KittyImageFormat {
+    Rgb,
+    Rgba,
+    Png,
+    Unsupported(u32),
+}
+
+/// A parsed `APC G <control data> ; <payload> ST` command
+#[derive(Debug, Clone, PartialEq)]
+pub struct KittyGraphicsCommand {
+    pub action: KittyGraphicsAction,
+    pub format: KittyImageFormat,
+    /// Transmission medium (`t=`): direct, file, temp file or shared memory
+    pub medium: u8,
+    /// Payload compression (`o=`)
+    pub compression: Option<u8>,
+    /// Pixel size of raw data (`s=`, `v=`)
+    pub data_width: u32,
+    pub data_height: u32,
+    pub image_id: u32,
+    pub image_number: u32,
+    pub placement_id: u32,
+    /// More chunks follow (`m=1`)
+    pub more_chunks: bool,
+    /// Response suppression (`q=`): 1 hides OK, 2 hides errors too
+    pub quiet: u8,
+    /// Display size in cells (`c=`, `r=`)
+    pub columns: u32,
+    pub rows: u32,
+    /// Pixel offset within the first cell (`X=`, `Y=`)
+    pub cell_offset: (u32, u32),
+    pub z_index: i32,
+    /// Leave the cursor where it is after placing (`C=1`)
+    pub keep_cursor: bool,
+    /// What to delete (`d=`)
+    pub delete: u8,
+    /// Cell coordinates used by the delete variants (`x=`, `y=`)
+    pub x: u32,
+    pub y: u32,
+    /// Base64 payload, still encoded
+    pub payload: Vec<u8>,
+}
+
+impl Default for KittyGraphicsCommand {
+    fn default() -> Self {
+        KittyGraphicsCommand {
+            action: KittyGraphicsAction::Transmit,
+            format: KittyImageFormat::Rgba,
+            medium: b'd',
+            compression: None,
+            data_width: 0,
+            data_height: 0,
+            image_id: 0,
+            image_number: 0,
+            placement_id: 0,
+            more_chunks: false,
+            quiet: 0,
+            columns: 0,
+            rows: 0,
+            cell_offset: (0, 0),
+            z_index: 0,
+            keep_cursor: false,
+            delete: b'a',
+            x: 0,
+            y: 0,
+            payload: Vec::new(),
+        }
+    }
+}
+
+impl KittyGraphicsCommand {
+    /// Parse the body of an APC sequence following the `G`
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        let (control, payload) = match data.iter().position(|&b| b == b';') {
+            Some(idx) => (&data[..idx], &data[idx + 1..]),
+            None => (data, &[][..]),
+        };
+        
+        let mut command = KittyGraphicsCommand {
+            payload: payload.to_vec(),
+            ..Default::default()
+        };
+        
+        let control = std::str::from_utf8(control).ok()?;
+        for entry in control.split(',').filter(|entry| !entry.is_empty()) {
+            let (key, value) = entry.split_once('=')?;
+            let byte = value.bytes().next().unwrap_or(0);
+            let number = || value.parse::<u32>().ok();
+            
+            match key {
+                "a" => {
+                    command.action = match byte {
+                        b't' => KittyGraphicsAction::Transmit,
+                        b'T' => KittyGraphicsAction::TransmitAndPut,
+                        b'p' => KittyGraphicsAction::Put,
+                        b'd' => KittyGraphicsAction::Delete,
+                        b'q' => KittyGraphicsAction::Query,
+                        other => KittyGraphicsAction::Unsupported(other),
+                    }
+                }
+                "f" => {
+                    command.format = match number()? {
+                        24 => KittyImageFormat::Rgb,
+                        32 => KittyImageFormat::Rgba,
+                        100 => KittyImageFormat::Png,
+                        other => KittyImageFormat::Unsupported(other),
+                    }
+                }
+                "t" => command.medium = byte,
+                "o" => command.compression = Some(byte),
+                "d" => command.delete = byte,
+                "s" => command.data_width = number()?,
+                "v" => command.data_height = number()?,
+                "i" => command.image_id = number()?,
+                "I" => command.image_number = number()?,
+                "p" => command.placement_id = number()?,
+                "m" => command.more_chunks = number()? == 1,
+                "q" => command.quiet = number()? as u8,
+                "c" => command.columns = number()?,
+                "r" => command.rows = number()?,
+                "X" => command.cell_offset.0 = number()?,
+                "Y" => command.cell_offset.1 = number()?,
+                "z" => command.z_index = value.parse().ok()?,
+                "C" => command.keep_cursor = number()? == 1,
+                "x" => command.x = number()?,
+                "y" => command.y = number()?,
+                _ => {} // Source rectangles, animation keys, etc.
+            }
+        }
+        
+        Some(command)
+    }
+    
+    /// Decode the payload into an image
+    pub fn decode_image(&self) -> Result<RgbaImage, KittyGraphicsError> {
+        if self.medium != b'd' {
+            return Err(KittyGraphicsError::Unsupported("only direct transmission is supported".to_string()));
+        }
+        
+        let data = PAYLOAD_ENGINE.decode(&self.payload)
+            .map_err(|e| KittyGraphicsError::InvalidArgument(format!("bad base64 payload: {}", e)))?;
+        if data.is_empty() {
+            return Err(KittyGraphicsError::NoData("no image data".to_string()));
+        }
+        
+        let data = match self.compression {
+            None => data,
+            Some(b'z') => miniz_oxide::inflate::decompress_to_vec_zlib(&data)
+                .map_err(|e| KittyGraphicsError::InvalidArgument(format!("bad zlib data: {:?}", e)))?,
+            Some(other) => {
+                return Err(KittyGraphicsError::InvalidArgument(format!("unknown compression {}", other as char)));
+            }
+        };
+        
+        let image = match self.format {
+            KittyImageFormat::Rgb => RgbaImage::from_rgb(self.data_width, self.data_height, &data),
+            KittyImageFormat::Rgba => RgbaImage::from_rgba(self.data_width, self.data_height, &data),
+            KittyImageFormat::Png => RgbaImage::decode_png(&data),
+            KittyImageFormat::Unsupported(format) => {
+                return Err(KittyGraphicsError::InvalidArgument(format!("unknown format {}", format)));
+            }
+        };
+        image.map_err(|e| KittyGraphicsError::InvalidArgument(e.to_string()))
+    }
+}
+
+/// Errors reported back to the client; the display form is the wire format
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum KittyGraphicsError {
+    #[error("ENOENT:{0}")]
+    NotFound(String),
+    
+    #[error("EINVAL:{0}")]
+    InvalidArgument(String),
+    
+    #[error("ENODATA:{0}")]
+    NoData(String),
+    
+    #[error("EFBIG:{0}")]
+    TooLarge(String),
+    
+    #[error("ENOTSUP:{0}")]
+    Unsupported(String),
+}
+
+/// Per-pane kitty graphics bookkeeping
+#[derive(Debug, Clone, Default)]
+pub struct KittyGraphicsState {
+    /// Client image ids mapped to images in the pane's image layer
+    images: HashMap<u32, ImageId>,
+    /// Image numbers (`I=`) mapped to the id most recently assigned to them
+    numbers: HashMap<u32, u32>,
+    next_auto_id: u32,
+    /// Chunked transmission in progress
+    pending: Option<KittyGraphicsCommand>,
+}
+
+impl KittyGraphicsState {
+    /// Resolve the client image id a command refers to
+    fn lookup(&self, command: &KittyGraphicsCommand) -> Option<u32> {
+        if command.image_id != 0 {
+            Some(command.image_id)
+        } else if command.image_number != 0 {
+            self.numbers.get(&command.image_number).copied()
+        } else {
+            None
+        }
+    }
+    
+    /// Pick an id for an image transmitted without one
+    fn allocate_id(&mut self) -> u32 {
+        loop {
+            self.next_auto_id = self.next_auto_id.max(AUTO_IMAGE_ID_START).wrapping_add(1);
+            if !self.images.contains_key(&self.next_auto_id) {
+                return self.next_auto_id;
+            }
+        }
+    }
+    
+    /// Forget ids whose images have been evicted from the layer
+    fn forget_missing(&mut self, layer: &ImageLayer) {
+        self.images.retain(|_, id| layer.image(*id).is_some());
+        let images = &self.images;
+        self.numbers.retain(|_, client_id| images.contains_key(client_id));
+    }
+}
+
+impl Pane {
+    /// Handle a kitty graphics command, assembling chunked transmissions first
+    pub(crate) fn handle_kitty_graphics(&mut self, chunk: KittyGraphicsCommand) {
+        let command = match self.kitty_graphics.pending.take() {
+            Some(mut pending) => {
+                pending.payload.extend_from_slice(&chunk.payload);
+                pending.more_chunks = chunk.more_chunks;
+                pending
+            }
+            None => chunk,
+        };
+        
+        if command.more_chunks {
+            // Encoded payloads are 4/3 the size of the decoded data. Without a memory limit,
+            // a chunked image may be as large as one unchunked payload.
+            let budget = self.image_memory_budget().unwrap_or(MAX_STRING_SEQUENCE_LENGTH);
+            if command.payload.len() / 4 * 3 > budget {
+                self.respond_kitty_graphics(&command, command.image_id, Err(
+                    KittyGraphicsError::TooLarge("image exceeds the pane memory limit".to_string())
+                ));
+            } else {
+                self.kitty_graphics.pending = Some(command);
+            }
+            return;
+        }
+        
+        let result = self.execute_kitty_graphics(&command);
+        if command.action != KittyGraphicsAction::Delete {
+            let image_id = match &result {
+                Ok(image_id) => *image_id,
+                Err(_) => command.image_id,
+            };
+            self.respond_kitty_graphics(&command, image_id, result.map(|_| ()));
+        }
+    }
+    
+    fn execute_kitty_graphics(&mut self, command: &KittyGraphicsCommand) -> Result<u32, KittyGraphicsError> {
+        match command.action {
+            KittyGraphicsAction::Query => {
+                command.decode_image()?;
+                Ok(command.image_id)
+            }
+            KittyGraphicsAction::Transmit | KittyGraphicsAction::TransmitAndPut => {
+                let image = command.decode_image()?;
+                let image_id = self.store_kitty_image(command, image)?;
+                if command.action == KittyGraphicsAction::TransmitAndPut {
+                    self.put_kitty_image(image_id, command)?;
+                }
+                Ok(image_id)
+            }
+            KittyGraphicsAction::Put => {
+                let image_id = self.kitty_graphics.lookup(command)
+                    .ok_or_else(|| KittyGraphicsError::NotFound("no such image".to_string()))?;
+                self.put_kitty_image(image_id, command)?;
+                Ok(image_id)
+            }
+            KittyGraphicsAction::Delete => {
+                self.delete_kitty_images(command)?;
+                Ok(command.image_id)
+            }
+            KittyGraphicsAction::Unsupported(action) => {
+                Err(KittyGraphicsError::Unsupported(format!("action {} is not supported", action as char)))
+            }
+        }
+    }
+    
+    fn store_kitty_image(&mut self, command: &KittyGraphicsCommand, image: RgbaImage) -> Result<u32, KittyGraphicsError> {
+        let image_id = match command.image_id {
+            0 => self.kitty_graphics.allocate_id(),
+            id => id,
+        };
+        
+        let budget = self.image_memory_budget();
+        let mut layer = self.images.write();
+        
+        // Retransmitting an id replaces the old image and its placements
+        if let Some(old) = self.kitty_graphics.images.remove(&image_id) {
+            layer.remove_image(old);
+        }
+        
+        let stored = layer.add_retained_image(image, budget)
+            .map_err(|e| KittyGraphicsError::TooLarge(e.to_string()))?;
+        self.kitty_graphics.images.insert(image_id, stored);
+        if command.image_number != 0 {
+            self.kitty_graphics.numbers.insert(command.image_number, image_id);
+        }
+        self.kitty_graphics.forget_missing(&layer);
+        Ok(image_id)
+    }
+    
+    fn put_kitty_image(&mut self, image_id: u32, command: &KittyGraphicsCommand) -> Result<(), KittyGraphicsError> {
+        let stored = self.kitty_graphics.images.get(&image_id).copied()
+            .ok_or_else(|| KittyGraphicsError::NotFound("no such image".to_string()))?;
+        let image = self.images.read().image(stored)
+            .ok_or_else(|| KittyGraphicsError::NotFound("image was evicted".to_string()))?;
+        
+        // Offsets are pixels within the first cell, so both fit in a u16
+        let (offset_x, offset_y) = command.cell_offset;
+        let (cell_width, cell_height) = self.config.cell_size;
+        if offset_x >= cell_width as u32 || offset_y >= cell_height as u32 {
+            return Err(KittyGraphicsError::InvalidArgument("cell offset must be smaller than a cell".to_string()));
+        }
+        let (natural_cols, natural_rows) = self.image_cell_extent(
+            image.width.saturating_add(offset_x),
+            image.height.saturating_add(offset_y),
+        );
+        let (cols, rows) = match (command.columns, command.rows) {
+            (0, 0) => (natural_cols, natural_rows),
+            (cols, 0) => (clamp_extent(cols), scale_extent(natural_rows, cols, natural_cols)),
+            (0, rows) => (scale_extent(natural_cols, rows, natural_rows), clamp_extent(rows)),
+            (cols, rows) => (clamp_extent(cols), clamp_extent(rows)),
+        };
+        
+        let col = self.cursor.position.col;
+        let placement = ImagePlacement::new(stored, self.cursor_absolute_row(), col, rows, cols)
+            .with_z_index(command.z_index)
+            .with_pixel_offset(offset_x as u16, offset_y as u16)
+            .with_placement_id(command.placement_id);
+        self.images.write().place(placement);
+        
+        if !command.keep_cursor {
+            // The cursor ends up just right of the image, on its last row
+            for _ in 1..rows {
+                self.cursor.line_feed(&mut self.screen_buffer, &mut self.scrollback, &self.modes)
+                    .map_err(|e| KittyGraphicsError::InvalidArgument(e.to_string()))?;
+            }
+            let last_col = self.screen_buffer.line_width(self.cursor.position.row).saturating_sub(1);
+            self.cursor.position.col = col.saturating_add(cols).min(last_col);
+            self.prune_scrolled_images();
+        }
+        Ok(())
+    }
+    
+    fn delete_kitty_images(&mut self, command: &KittyGraphicsCommand) -> Result<(), KittyGraphicsError> {
+        let free_data = command.delete.is_ascii_uppercase();
+        let origin = self.screen_origin_row();
+        let screen_rows = origin..origin + self.screen_buffer.height as u64;
+        let all_cols = 0..u16::MAX;
+        let cell = |x: u32, y: u32| {
+            let row = origin + y.saturating_sub(1) as u64;
+            let col = x.saturating_sub(1).min(u16::MAX as u32 - 1) as u16;
+            (row..row + 1, col..col + 1)
+        };
+        
+        let predicate: Box<dyn Fn(&ImagePlacement) -> bool> = match command.delete.to_ascii_lowercase() {
+            b'a' => Box::new(move |p| p.intersects(&screen_rows, &all_cols)),
+            b'i' | b'n' => {
+                let Some(client_id) = self.kitty_graphics.lookup(command) else { return Ok(()) };
+                let Some(&stored) = self.kitty_graphics.images.get(&client_id) else { return Ok(()) };
+                
+                if free_data && command.placement_id == 0 {
+                    self.images.write().remove_image(stored);
+                    self.kitty_graphics.images.remove(&client_id);
+                    self.kitty_graphics.forget_missing(&self.images.read());
+                    return Ok(());
+                }
+                let placement_id = command.placement_id;
+                Box::new(move |p| p.image_id == stored && (placement_id == 0 || p.placement_id == placement_id))
+            }
+            b'c' => {
+                let (rows, cols) = cell(self.cursor.position.col as u32 + 1, self.cursor.position.row as u32 + 1);
+                Box::new(move |p| p.intersects(&rows, &cols))
+            }
+            b'p' => {
+                let (rows, cols) = cell(command.x, command.y);
+                Box::new(move |p| p.intersects(&rows, &cols))
+            }
+            b'q' => {
+                let (rows, cols) = cell(command.x, command.y);
+                let z_index = command.z_index;
+                Box::new(move |p| p.z_index == z_index && p.intersects(&rows, &cols))
+            }
+            b'x' => {
+                let (_, cols) = cell(command.x, 1);
+                Box::new(move |p| p.intersects(&(0..u64::MAX), &cols))
+            }
+            b'y' => {
+                let (rows, _) = cell(1, command.y);
+                Box::new(move |p| p.intersects(&rows, &all_cols))
+            }
+            b'z' => {
+                let z_index = command.z_index;
+                Box::new(move |p| p.z_index == z_index)
+            }
+            other => {
+                return Err(KittyGraphicsError::Unsupported(format!("delete mode {} is not supported", other as char)));
+            }
+        };
+        
+        let mut layer = self.images.write();
+        let affected = layer.remove_placements(predicate);
+        if free_data {
+            layer.remove_unplaced(&affected);
+            self.kitty_graphics.forget_missing(&layer);
+        }
+        Ok(())
+    }
+    
+    fn respond_kitty_graphics(&mut self, command: &KittyGraphicsCommand, image_id: u32, result: Result<(), KittyGraphicsError>) {
+        // Clients that send neither an id nor a number never get a reply
+        if command.image_id == 0 && command.image_number == 0 {
+            return;
+        }
+        
+        let message = match result {
+            Ok(()) if command.quiet == 0 => "OK".to_string(),
+            Err(error) if command.quiet < 2 => error.to_string(),
+            _ => return,
+        };
+        
+        let mut keys = format!("i={}", image_id);
+        if command.image_number != 0 {
+            keys.push_str(&format!(",I={}", command.image_number));
+        }
+        if command.placement_id != 0 {
+            keys.push_str(&format!(",p={}", command.placement_id));
+        }
+        self.respond(format!("\x1b_G{};{}\x1b\\", keys, message).as_bytes());
+    }
+}
+
+/// A requested `c=`/`r=` extent, saturated to what a placement can hold
+fn clamp_extent(requested: u32) -> u16 {
+    requested.min(u16::MAX as u32) as u16
+}
+
+/// Scale one side of a cell extent to keep the image's aspect ratio
+fn scale_extent(natural: u16, requested_other: u32, natural_other: u16) -> u16 {
+    let scaled = (natural as u64 * requested_other as u64).div_ceil(natural_other.max(1) as u64);
+    scaled.clamp(1, u16::MAX as u64) as u16
+}
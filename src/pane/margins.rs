@@ -0,0 +1,246 @@
+use super::*;
+
+/// Scrolling margins set by DECSTBM and DECSLRM, 0-based and inclusive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScrollMargins {
+    pub top: u16,
+    pub bottom: u16,
+    pub left: u16,
+    pub right: u16,
+}
+
+impl ScrollMargins {
+    /// Margins at the edges of a screen
+    pub fn full(width: u16, height: u16) -> Self {
+        ScrollMargins {
+            top: 0,
+            bottom: height.saturating_sub(1),
+            left: 0,
+            right: width.saturating_sub(1),
+        }
+    }
+    
+    pub fn contains(&self, row: u16, col: u16) -> bool {
+        (self.top..=self.bottom).contains(&row) && (self.left..=self.right).contains(&col)
+    }
+    
+    /// Whether the left and right margins are at the screen edges
+    pub fn is_full_width(&self, width: u16) -> bool {
+        self.left == 0 && self.right + 1 >= width
+    }
+}
+
+/// Rows `top..=bottom` moved by `count` inside columns `left..=right`, for the image layer to follow
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct RowShift {
+    pub top: u16,
+    pub bottom: u16,
+    pub left: u16,
+    pub right: u16,
+    pub count: u16,
+    pub up: bool,
+    /// The rows scrolled off the top went to the scrollback, moving the screen origin
+    pub into_scrollback: bool,
+}
+
+impl ScreenBuffer {
+    /// Set the top and bottom margins, or reset them when the region is smaller than two rows
+    pub fn set_vertical_margins(&mut self, top: u16, bottom: u16) {
+        let bottom = bottom.min(self.height.saturating_sub(1));
+        let full = ScrollMargins::full(self.width, self.height);
+        (self.margins.top, self.margins.bottom) = if top < bottom { (top, bottom) } else { (full.top, full.bottom) };
+    }
+    
+    /// Set the left and right margins, or reset them when the region is smaller than two columns
+    pub fn set_horizontal_margins(&mut self, left: u16, right: u16) {
+        let right = right.min(self.width.saturating_sub(1));
+        let full = ScrollMargins::full(self.width, self.height);
+        (self.margins.left, self.margins.right) = if left < right { (left, right) } else { (full.left, full.right) };
+    }
+    
+    pub fn reset_margins(&mut self) {
+        self.margins = ScrollMargins::full(self.width, self.height);
+    }
+    
+    /// Scroll the margin region up (SU, or a line feed on the bottom margin)
+    ///
+    /// Lines only go to `scrollback` when the region spans the whole width and starts at the top.
+    pub fn scroll_up(&mut self, count: u16, scrollback: Option<&mut ScrollbackBuffer>) {
+        let scrollback = scrollback.filter(|_| self.margins.top == 0 && self.margins.is_full_width(self.width));
+        let into_scrollback = scrollback.is_some();
+        if let Some(scrollback) = scrollback {
+            let count = count.min(self.margins.bottom + 1);
+            for line in &self.lines[..count as usize] {
+                scrollback.push_line(line.clone());
+            }
+        }
+        self.remove_rows(self.margins.top, count);
+        if let Some(shift) = self.row_shifts.last_mut().filter(|_| into_scrollback) {
+            shift.into_scrollback = true;
+        }
+    }
+    
+    /// Scroll the margin region down (SD, or a reverse index on the top margin)
+    pub fn scroll_down(&mut self, count: u16) {
+        self.insert_rows(self.margins.top, count);
+    }
+    
+    /// Insert blank characters at the cursor, shifting the rest towards the right margin (ICH)
+    pub fn insert_chars(&mut self, count: u16, cursor: &Cursor) {
+        let CursorPosition { row, col, .. } = cursor.position;
+        if !self.margins.contains(row, col) {
+            return;
+        }
+        let right = self.margins.right.min(self.line_width(row).saturating_sub(1));
+        // Past the end of a double-width line there is nothing to shift
+        if col > right {
+            return;
+        }
+        if let Some(line) = self.lines.get_mut(row as usize) {
+            let span = &mut line.cells[col as usize..=right as usize];
+            let count = (count as usize).min(span.len());
+            span.rotate_right(count);
+            span[..count].fill(Cell::default());
+            line.dirty = true;
+            self.dirty_regions.mark_line_dirty(row);
+        }
+    }
+    
+    /// Delete characters at the cursor, pulling in blanks at the right margin (DCH)
+    pub fn delete_chars(&mut self, count: u16, cursor: &Cursor) {
+        let CursorPosition { row, col, .. } = cursor.position;
+        if !self.margins.contains(row, col) {
+            return;
+        }
+        let right = self.margins.right.min(self.line_width(row).saturating_sub(1));
+        // Past the end of a double-width line there is nothing to shift
+        if col > right {
+            return;
+        }
+        if let Some(line) = self.lines.get_mut(row as usize) {
+            let span = &mut line.cells[col as usize..=right as usize];
+            let count = (count as usize).min(span.len());
+            span.rotate_left(count);
+            let len = span.len();
+            span[len - count..].fill(Cell::default());
+            line.dirty = true;
+            self.dirty_regions.mark_line_dirty(row);
+        }
+    }
+    
+    /// Move rows `top..=bottom` up by `count` inside the left and right margins
+    pub(crate) fn remove_rows(&mut self, top: u16, count: u16) {
+        let ScrollMargins { bottom, left, right, .. } = self.margins;
+        if top > bottom {
+            return;
+        }
+        let count = count.min(bottom - top + 1);
+        self.row_shifts.push(RowShift { top, bottom, left, right, count, up: true, into_scrollback: false });
+        
+        if self.margins.is_full_width(self.width) {
+            self.lines.drain(top as usize..(top + count) as usize);
+            for _ in 0..count {
+                self.lines.insert((bottom + 1 - count) as usize, Line::new(self.width));
+            }
+        } else {
+            for row in top..=bottom {
+                let source = row + count;
+                let cells = if source <= bottom {
+                    self.lines[source as usize].cells[left as usize..=right as usize].to_vec()
+                } else {
+                    vec![Cell::default(); (right - left + 1) as usize]
+                };
+                self.lines[row as usize].cells[left as usize..=right as usize].clone_from_slice(&cells);
+            }
+        }
+        self.mark_all_dirty();
+    }
+    
+    /// Move rows `top..=bottom` down by `count` inside the left and right margins
+    pub(crate) fn insert_rows(&mut self, top: u16, count: u16) {
+        let ScrollMargins { bottom, left, right, .. } = self.margins;
+        if top > bottom {
+            return;
+        }
+        let count = count.min(bottom - top + 1);
+        self.row_shifts.push(RowShift { top, bottom, left, right, count, up: false, into_scrollback: false });
+        
+        if self.margins.is_full_width(self.width) {
+            self.lines.drain((bottom + 1 - count) as usize..=bottom as usize);
+            for _ in 0..count {
+                self.lines.insert(top as usize, Line::new(self.width));
+            }
+        } else {
+            for row in (top..=bottom).rev() {
+                let cells = if row >= top + count {
+                    self.lines[(row - count) as usize].cells[left as usize..=right as usize].to_vec()
+                } else {
+                    vec![Cell::default(); (right - left + 1) as usize]
+                };
+                self.lines[row as usize].cells[left as usize..=right as usize].clone_from_slice(&cells);
+            }
+        }
+        self.mark_all_dirty();
+    }
+}
+
+impl Pane {
+    /// Apply a margin or scrolling command
+    pub(crate) fn handle_margin_command(&mut self, command: VtCommand) -> PaneResult<()> {
+        match command {
+            VtCommand::SetScrollMargins(top, bottom) => {
+                let bottom = if bottom == 0 { self.screen_buffer.height } else { bottom };
+                self.screen_buffer.set_vertical_margins(top.max(1) - 1, bottom.saturating_sub(1));
+                self.cursor.set_position(1, 1, &self.screen_buffer)?;
+            }
+            VtCommand::SetLeftRightMargins(left, right) => {
+                // Without DECLRMM this is SCOSC, as in xterm
+                if !self.modes.left_right_margins {
+                    self.cursor.save_position();
+                    return Ok(());
+                }
+                let right = if right == 0 { self.screen_buffer.width } else { right };
+                self.screen_buffer.set_horizontal_margins(left.max(1) - 1, right.saturating_sub(1));
+                self.cursor.set_position(1, 1, &self.screen_buffer)?;
+            }
+            VtCommand::ScrollUp(n) => {
+                let scrollback = (!self.modes.alternate_screen).then_some(&mut self.scrollback);
+                self.screen_buffer.scroll_up(n.max(1), scrollback);
+                self.cursor.clamp_to_screen(&self.screen_buffer);
+                self.prune_scrolled_images();
+            }
+            VtCommand::ScrollDown(n) => {
+                self.screen_buffer.scroll_down(n.max(1));
+                self.cursor.clamp_to_screen(&self.screen_buffer);
+            }
+            VtCommand::InsertLines(n) => {
+                self.screen_buffer.insert_lines(n.max(1), &self.cursor)?;
+                self.cursor.carriage_return(&self.screen_buffer)?;
+            }
+            VtCommand::DeleteLines(n) => {
+                self.screen_buffer.delete_lines(n.max(1), &self.cursor)?;
+                self.cursor.carriage_return(&self.screen_buffer)?;
+            }
+            VtCommand::InsertChars(n) => {
+                self.screen_buffer.insert_chars(n.max(1), &self.cursor);
+            }
+            VtCommand::DeleteChars(n) => {
+                self.screen_buffer.delete_chars(n.max(1), &self.cursor);
+            }
+            VtCommand::ReverseIndex => {
+                self.cursor.reverse_index(&mut self.screen_buffer)?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+    
+    /// Turn DECLRMM (DECSET 69) on or off; turning it off resets the left and right margins
+    pub(crate) fn set_left_right_margin_mode(&mut self, enabled: bool) {
+        self.modes.left_right_margins = enabled;
+        if !enabled {
+            let width = self.screen_buffer.width;
+            self.screen_buffer.set_horizontal_margins(0, width.saturating_sub(1));
+        }
+    }
+}
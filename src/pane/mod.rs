@@ -1,26 +1,62 @@
 pub mod buffer;
 pub mod cursor;
 pub mod terminal;
+pub mod terminfo;
 pub mod pty;
 pub mod interface;
 pub mod events;
 pub mod errors;
 pub mod config;
+pub mod semantic;
+pub mod notification;
+pub mod sixel;
+pub mod image;
+pub mod kitty_graphics;
+pub mod iterm2;
+pub mod keyboard;
+pub mod mouse;
+pub mod screen;
+pub mod rect;
+pub mod margins;
+pub mod capabilities;
+pub mod window_ops;
+pub mod handlers;
+pub mod trace;
 
 #[cfg(test)]
 mod tests;
+#[cfg(test)]
+mod conformance;
+#[cfg(test)]
+mod fuzz;
 
 pub use buffer::*;
+pub use capabilities::*;
 pub use config::*;
 pub use cursor::*;
 pub use errors::*;
 pub use events::*;
+pub use handlers::*;
+pub use image::*;
 pub use interface::*;
+pub use iterm2::*;
+pub use keyboard::*;
+pub use kitty_graphics::*;
+pub use margins::*;
+pub use mouse::*;
+pub use notification::*;
 pub use pty::*;
+pub use rect::*;
+pub use semantic::*;
+pub use sixel::*;
 pub use terminal::*;
+pub use terminfo::*;
+pub use trace::*;
+pub use window_ops::*;
 
-use crate::sash::{PaneId, Theme};
-use std::collections::VecDeque;
+use crate::sash::{Color, PaneId, Theme};
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
 
 /// The main Pane structure - represents a single terminal instance
 pub struct Pane {
@@ -29,6 +65,8 @@ pub struct Pane {
     title: String,
     modified: bool,
     active: bool,
+    /// Whether the pane has keyboard focus (active in the focused window)
+    focused: bool,
     
     // Terminal emulation core
     terminal: Terminal,
@@ -36,13 +74,25 @@ pub struct Pane {
     
     // Text content management
     screen_buffer: ScreenBuffer,
+    /// Main screen, stashed while the alternate screen is shown
+    main_screen: Option<ScreenBuffer>,
     scrollback: ScrollbackBuffer,
     cursor: Cursor,
+    images: ImageStore,
+    kitty_graphics: KittyGraphicsState,
     
     // Terminal state
     modes: TerminalModes,
     character_sets: CharacterSets,
     tabs: TabStops,
+    keyboard_modes: KeyboardModeStack,
+    /// Start of the open synchronized update (mode 2026), if any
+    synchronized_update: Option<Instant>,
+    
+    // Shell integration
+    command_history: CommandHistory,
+    pending_notification: Option<DesktopNotification>,
+    user_vars: HashMap<String, String>,
     
     // Configuration and theming
     config: PaneConfig,
@@ -50,39 +100,64 @@ pub struct Pane {
     
     // Event handling
     event_handler: PaneEventHandler,
+    pending_responses: Vec<u8>,
     
     // Statistics and diagnostics
     stats: PaneStatistics,
+    /// Sequence trace, while `log_terminal_sequences` is on
+    trace: Option<SequenceTrace>,
 }
 
 impl Pane {
     /// Create a new Pane with default configuration
     pub fn new(id: PaneId, config: PaneConfig) -> PaneResult<Self> {
         let size = config.initial_size;
+        let mut cursor = Cursor::new();
+        cursor.set_shape(config.cursor_style, config.cursor_blink);
+        let trace = if config.log_terminal_sequences {
+            Some(SequenceTrace::for_pane(id, &config)?)
+        } else {
+            None
+        };
         
         Ok(Pane {
             id,
             title: config.default_title.clone(),
             modified: false,
             active: false,
+            focused: false,
             
             terminal: Terminal::new()?,
             pty: None,
             
             screen_buffer: ScreenBuffer::new(size.0, size.1),
+            main_screen: None,
             scrollback: ScrollbackBuffer::new(config.scrollback_lines),
-            cursor: Cursor::new(),
+            cursor,
+            images: ImageStore::new(),
+            kitty_graphics: KittyGraphicsState::default(),
             
-            modes: TerminalModes::default(),
+            modes: TerminalModes {
+                sgr21_double_underline: config.sgr21_double_underline,
+                ..TerminalModes::default()
+            },
             character_sets: CharacterSets::default(),
             tabs: TabStops::new(size.0),
+            keyboard_modes: KeyboardModeStack::new(),
+            synchronized_update: None,
+            
+            command_history: CommandHistory::default(),
+            pending_notification: None,
+            user_vars: HashMap::new(),
             
             config,
             local_theme_override: None,
             
             event_handler: PaneEventHandler::new(),
+            pending_responses: Vec::new(),
             
             stats: PaneStatistics::default(),
+            trace,
         })
     }
     
@@ -122,19 +197,64 @@ impl Pane {
         
         // Parse and process the data through the terminal emulator
         for byte in data {
-            if let Some(command) = self.terminal.process_byte(*byte)? {
+            if self.trace.is_some() {
+                self.trace_byte(*byte)?;
+            } else if let Some(command) = self.terminal.process_byte(*byte)? {
                 self.execute_terminal_command(command)?;
             }
         }
+        self.flush_trace();
         
+        self.flush_responses()?;
         self.update_activity();
         Ok(())
     }
     
+    /// Queue a reply to the program running in the pane (status reports, queries)
+    pub(crate) fn respond(&mut self, data: &[u8]) {
+        self.pending_responses.extend_from_slice(data);
+    }
+    
+    /// Write queued replies to the PTY, if one is attached
+    fn flush_responses(&mut self) -> PaneResult<()> {
+        if self.pending_responses.is_empty() || self.pty.is_none() {
+            return Ok(());
+        }
+        let responses = std::mem::take(&mut self.pending_responses);
+        self.write_input(&responses)?;
+        Ok(())
+    }
+    
     /// Execute a terminal command (from VT parser)
     fn execute_terminal_command(&mut self, command: VtCommand) -> PaneResult<()> {
+        // Anything but printing and rendition changes cancels a pending wrap
+        let keeps_wrap = matches!(
+            command,
+            VtCommand::PrintChar(_)
+                | VtCommand::Bell
+                | VtCommand::SetGraphicsRendition(_)
+                | VtCommand::SetCharacterProtection(_)
+                | VtCommand::DesignateCharacterSet(..)
+                | VtCommand::InvokeCharacterSet(_)
+        );
+        if !keeps_wrap {
+            self.cursor.pending_wrap = false;
+        }
+        
         match command {
             VtCommand::PrintChar(ch) => {
+                if self.cursor.pending_wrap {
+                    // Mark the line as continuing on the next, before a scroll moves it
+                    let row = self.cursor.position.row as usize;
+                    if let Some(line) = self.screen_buffer.get_line_mut(row) {
+                        line.wrapped = true;
+                    }
+                    if self.cursor.wrap(&self.screen_buffer) {
+                        self.cursor.line_feed(&mut self.screen_buffer, &mut self.scrollback, &self.modes)?;
+                        self.prune_scrolled_images();
+                    }
+                }
+                let ch = self.character_sets.translate(ch);
                 self.screen_buffer.write_char_at_cursor(ch, &self.cursor, &self.modes)?;
                 self.cursor.advance(&self.screen_buffer, &self.modes)?;
             }
@@ -148,22 +268,37 @@ impl Pane {
                 self.cursor.move_forward(n, &self.screen_buffer)?;
             }
             VtCommand::CursorBack(n) => {
-                self.cursor.move_back(n)?;
+                self.cursor.move_back(n, &self.screen_buffer)?;
             }
             VtCommand::CursorPosition(row, col) => {
                 self.cursor.set_position(row, col, &self.screen_buffer)?;
             }
+            VtCommand::SaveCursor => {
+                self.cursor.save_position();
+            }
+            VtCommand::RestoreCursor => {
+                self.cursor.restore_position(&self.screen_buffer)?;
+            }
             VtCommand::ClearScreen(clear_type) => {
-                self.screen_buffer.clear_screen(clear_type, &mut self.cursor)?;
+                self.erase_images(clear_type, true);
+                self.screen_buffer.clear_screen(clear_type, &self.cursor)?;
             }
             VtCommand::ClearLine(clear_type) => {
+                self.erase_images(clear_type, false);
                 self.screen_buffer.clear_line(clear_type, &self.cursor)?;
             }
+            VtCommand::SelectiveClearScreen(clear_type) => {
+                self.screen_buffer.selective_clear_screen(clear_type, &self.cursor)?;
+            }
+            VtCommand::SelectiveClearLine(clear_type) => {
+                self.screen_buffer.selective_clear_line(clear_type, &self.cursor)?;
+            }
             VtCommand::LineFeed => {
                 self.cursor.line_feed(&mut self.screen_buffer, &mut self.scrollback, &self.modes)?;
+                self.prune_scrolled_images();
             }
             VtCommand::CarriageReturn => {
-                self.cursor.carriage_return()?;
+                self.cursor.carriage_return(&self.screen_buffer)?;
             }
             VtCommand::Tab => {
                 self.cursor.tab_forward(&self.tabs, &self.screen_buffer)?;
@@ -174,6 +309,123 @@ impl Pane {
             VtCommand::SetGraphicsRendition(params) => {
                 self.modes.set_graphics_attributes(&params)?;
             }
+            VtCommand::SetCharacterProtection(mode) => {
+                // DECSCA: 1 protects, 0 and 2 do not
+                self.modes.current_attributes.protected = mode == 1;
+            }
+            VtCommand::DecPrivateModeSet(modes) => {
+                self.set_private_modes(&modes, true)?;
+            }
+            VtCommand::DecPrivateModeReset(modes) => {
+                self.set_private_modes(&modes, false)?;
+            }
+            VtCommand::RequestPrivateMode(mode) => {
+                self.report_private_mode(mode);
+            }
+            VtCommand::DesignateCharacterSet(slot, set) => {
+                self.character_sets.designate(slot, set);
+            }
+            VtCommand::InvokeCharacterSet(slot) => {
+                self.character_sets.active = slot;
+            }
+            VtCommand::SetTabStop => {
+                self.tabs.set_tab_stop(self.cursor.position.col);
+            }
+            VtCommand::ClearTabStop => {
+                self.tabs.clear_tab_stop(self.cursor.position.col);
+            }
+            VtCommand::ClearAllTabStops => {
+                self.tabs.clear_all();
+            }
+            VtCommand::PrimaryDeviceAttributes => {
+                self.respond(primary_device_attributes().as_bytes());
+            }
+            VtCommand::IdentifyVt52 => {
+                // A VT52 without a printer
+                self.respond(b"\x1b/Z");
+            }
+            VtCommand::SetLineSize(size) => {
+                let row = self.cursor.position.row;
+                self.screen_buffer.set_line_size(row, size);
+                // The cursor cannot stay beyond the halved line
+                let last_col = self.screen_buffer.line_width(row).saturating_sub(1);
+                self.cursor.position.col = self.cursor.position.col.min(last_col);
+            }
+            VtCommand::ScreenAlignmentTest => {
+                // DECALN also resets the margins and homes the cursor
+                self.screen_buffer.fill_alignment_pattern();
+                self.screen_buffer.reset_margins();
+                self.cursor.set_position(1, 1, &self.screen_buffer)?;
+            }
+            VtCommand::CopyRectangle(_)
+            | VtCommand::FillRectangle(_)
+            | VtCommand::EraseRectangle(_)
+            | VtCommand::SelectiveEraseRectangle(_)
+            | VtCommand::ChangeRectangleAttributes(_)
+            | VtCommand::ReverseRectangleAttributes(_)
+            | VtCommand::SetAttributeChangeExtent(_)
+            | VtCommand::RequestRectangleChecksum(_) => {
+                self.handle_rect_command(command);
+            }
+            VtCommand::SetScrollMargins(_, _)
+            | VtCommand::SetLeftRightMargins(_, _)
+            | VtCommand::ScrollUp(_)
+            | VtCommand::ScrollDown(_)
+            | VtCommand::InsertLines(_)
+            | VtCommand::DeleteLines(_)
+            | VtCommand::InsertChars(_)
+            | VtCommand::DeleteChars(_)
+            | VtCommand::ReverseIndex => {
+                self.handle_margin_command(command)?;
+            }
+            VtCommand::SetModifyOtherKeys(level) => {
+                self.modes.modify_other_keys = level;
+            }
+            VtCommand::SetCursorStyle(_)
+            | VtCommand::SetCursorColor(_)
+            | VtCommand::QueryCursorColor
+            | VtCommand::ResetCursorColor => {
+                self.handle_cursor_command(command);
+            }
+            VtCommand::RequestStatusString(request) => {
+                self.report_status_string(&request);
+            }
+            VtCommand::RequestTermcap(request) => {
+                self.report_termcap(&request);
+            }
+            VtCommand::WindowOperation(operation) => {
+                self.handle_window_operation(operation)?;
+            }
+            VtCommand::QueryKeyboardFlags
+            | VtCommand::PushKeyboardFlags(_)
+            | VtCommand::PopKeyboardFlags(_)
+            | VtCommand::SetKeyboardFlags(_, _) => {
+                self.handle_keyboard_command(command);
+            }
+            VtCommand::SemanticPrompt(mark) => {
+                self.apply_semantic_mark(mark)?;
+            }
+            VtCommand::Notify(notification) => {
+                self.request_notification(notification)?;
+            }
+            VtCommand::Iterm2(command) => {
+                // Like sixel, a bad image must not stall the output stream
+                if self.handle_iterm2_command(command).is_err() {
+                    self.stats.unhandled_sequences += 1;
+                }
+            }
+            VtCommand::KittyGraphics(graphics) => {
+                self.handle_kitty_graphics(graphics);
+            }
+            VtCommand::Custom(sequence) => {
+                self.dispatch_custom_sequence(&sequence);
+            }
+            VtCommand::Sixel { params, data } => {
+                // A malformed or oversized image must not stall the output stream
+                if self.display_sixel(&params, &data).is_err() {
+                    self.stats.unhandled_sequences += 1;
+                }
+            }
             // TODO: Implement remaining commands
             _ => {
                 // For now, just track unimplemented commands
@@ -181,8 +433,81 @@ impl Pane {
             }
         }
         
+        self.shift_scrolled_images();
+        Ok(())
+    }
+    
+    /// Apply DECSET/DECRST to each listed DEC private mode
+    fn set_private_modes(&mut self, modes: &[u16], enabled: bool) -> PaneResult<()> {
+        for &mode in modes {
+            match mode {
+                1 => self.modes.application_cursor = enabled,
+                // DECANM - reset selects VT52 mode; the parser has already switched
+                2 => self.modes.vt52 = !enabled,
+                6 => {
+                    // DECOM homes the cursor to the new origin
+                    self.modes.origin_mode = enabled;
+                    self.cursor.position.origin_mode = enabled;
+                    self.cursor.set_position(1, 1, &self.screen_buffer)?;
+                }
+                7 => self.modes.auto_wrap = enabled,
+                66 => self.modes.application_keypad = enabled,
+                69 => self.set_left_right_margin_mode(enabled),
+                1004 => self.modes.focus_reporting = enabled,
+                2004 => self.modes.bracketed_paste = enabled,
+                47 | 1047 => self.set_alternate_screen(enabled, false)?,
+                1049 => self.set_alternate_screen(enabled, true)?,
+                2026 => self.set_synchronized_update(enabled),
+                _ if self.modes.mouse.set_mode(mode, enabled) => {}
+                _ => self.stats.unhandled_sequences += 1,
+            }
+        }
         Ok(())
     }
+    
+    /// Current state of a DEC private mode, or `None` if it is not recognized
+    fn private_mode_state(&self, mode: u16) -> Option<bool> {
+        match mode {
+            1 => Some(self.modes.application_cursor),
+            2 => Some(!self.modes.vt52),
+            6 => Some(self.modes.origin_mode),
+            7 => Some(self.modes.auto_wrap),
+            66 => Some(self.modes.application_keypad),
+            69 => Some(self.modes.left_right_margins),
+            1004 => Some(self.modes.focus_reporting),
+            2004 => Some(self.modes.bracketed_paste),
+            47 | 1047 | 1049 => Some(self.modes.alternate_screen),
+            2026 => Some(self.synchronized_update_active()),
+            _ => self.modes.mouse.mode_state(mode),
+        }
+    }
+    
+    /// Answer DECRQM (`CSI ? mode $ p`) with `CSI ? mode ; state $ y`
+    fn report_private_mode(&mut self, mode: u16) {
+        let state = match self.private_mode_state(mode) {
+            Some(true) => 1,
+            Some(false) => 2,
+            None => 0,
+        };
+        let reply = format!("\x1b[?{};{}$y", mode, state);
+        self.respond(reply.as_bytes());
+    }
+    
+    /// Open or close a synchronized update; rendering is held while one is open
+    fn set_synchronized_update(&mut self, enabled: bool) {
+        if !enabled {
+            self.synchronized_update = None;
+        } else if !self.synchronized_update_active() {
+            // A repeated begin does not extend the batch past the timeout
+            self.synchronized_update = Some(Instant::now());
+        }
+    }
+    
+    /// Whether a synchronized update is open and has not hit the safety timeout
+    pub(crate) fn synchronized_update_active(&self) -> bool {
+        let timeout = Duration::from_millis(self.config.synchronized_update_timeout_ms);
+        self.synchronized_update.is_some_and(|started| started.elapsed() < timeout)
+    }
 }
 
 /// Terminal mode flags and state
@@ -193,7 +518,23 @@ pub struct TerminalModes {
     pub cursor_visible: bool,
     pub application_keypad: bool,
     pub application_cursor: bool,
+    /// DECANM reset - escape sequences and keys use VT52 encodings
+    pub vt52: bool,
+    /// xterm modifyOtherKeys level (0 = off, 1 or 2)
+    pub modify_other_keys: u8,
+    pub mouse: MouseReporting,
+    /// DECSET 1004 - report focus changes with `CSI I` / `CSI O`
+    pub focus_reporting: bool,
+    /// DECSET 2004 - wrap pasted text in `CSI 200 ~` / `CSI 201 ~`
+    pub bracketed_paste: bool,
+    pub alternate_screen: bool,
     pub origin_mode: bool,
+    /// DECLRMM (DECSET 69) - `CSI Pl ; Pr s` sets left and right margins instead of saving the cursor
+    pub left_right_margins: bool,
+    /// DECSACE - extent of DECCARA and DECRARA
+    pub attribute_change_extent: AttributeChangeExtent,
+    /// SGR 21 is double underline (ECMA-48) rather than bold-off
+    pub sgr21_double_underline: bool,
     pub current_attributes: CellAttributes,
 }
 
@@ -205,35 +546,92 @@ impl Default for TerminalModes {
             cursor_visible: true,
             application_keypad: false,
             application_cursor: false,
+            vt52: false,
+            modify_other_keys: 0,
+            mouse: MouseReporting::default(),
+            focus_reporting: false,
+            bracketed_paste: false,
+            alternate_screen: false,
             origin_mode: false,
+            left_right_margins: false,
+            attribute_change_extent: AttributeChangeExtent::default(),
+            sgr21_double_underline: true,
             current_attributes: CellAttributes::default(),
         }
     }
 }
 
 impl TerminalModes {
-    /// Set graphics rendition attributes from VT sequence parameters
-    pub fn set_graphics_attributes(&mut self, params: &[u8]) -> PaneResult<()> {
-        for &param in params {
+    /// Set graphics rendition attributes from VT sequence parameters, each with its sub-parameters
+    pub fn set_graphics_attributes(&mut self, params: &[Vec<u16>]) -> PaneResult<()> {
+        // `CSI m` with no parameters is SGR 0
+        if params.is_empty() {
+            return self.set_graphics_attributes(&[vec![0]]);
+        }
+        
+        let attrs = &mut self.current_attributes;
+        let mut params = params.iter();
+        while let Some(group) = params.next() {
+            let (param, sub) = match group.split_first() {
+                Some((&param, sub)) => (param, sub),
+                None => continue,
+            };
             match param {
-                0 => self.current_attributes = CellAttributes::default(),
-                1 => self.current_attributes.bold = true,
-                2 => self.current_attributes.dim = true,
-                3 => self.current_attributes.italic = true,
-                4 => self.current_attributes.underline = UnderlineType::Single,
-                5 | 6 => self.current_attributes.blink = BlinkType::Slow,
-                7 => self.current_attributes.reverse = true,
-                8 => self.current_attributes.invisible = true,
-                9 => self.current_attributes.strikethrough = true,
-                21 => self.current_attributes.underline = UnderlineType::Double,
-                22 => { self.current_attributes.bold = false; self.current_attributes.dim = false; },
-                23 => self.current_attributes.italic = false,
-                24 => self.current_attributes.underline = UnderlineType::None,
-                25 => self.current_attributes.blink = BlinkType::None,
-                27 => self.current_attributes.reverse = false,
-                28 => self.current_attributes.invisible = false,
-                29 => self.current_attributes.strikethrough = false,
-                // TODO: Implement color codes (30-37, 40-47, 90-97, 100-107)
+                // DECSCA protection is not an SGR attribute and survives a reset
+                0 => *attrs = CellAttributes { protected: attrs.protected, ..CellAttributes::default() },
+                1 => attrs.bold = true,
+                2 => attrs.dim = true,
+                3 => attrs.italic = true,
+                // `4:n` selects the underline style; unknown styles are ignored
+                4 => match sub.first().copied().unwrap_or(1) {
+                    0 => attrs.underline = UnderlineType::None,
+                    1 => attrs.underline = UnderlineType::Single,
+                    2 => attrs.underline = UnderlineType::Double,
+                    3 => attrs.underline = UnderlineType::Curly,
+                    4 => attrs.underline = UnderlineType::Dotted,
+                    5 => attrs.underline = UnderlineType::Dashed,
+                    _ => {}
+                },
+                5 => attrs.blink = BlinkType::Slow,
+                6 => attrs.blink = BlinkType::Fast,
+                7 => attrs.reverse = true,
+                8 => attrs.invisible = true,
+                9 => attrs.strikethrough = true,
+                10..=20 => attrs.font = (param - 10) as u8,
+                21 if self.sgr21_double_underline => attrs.underline = UnderlineType::Double,
+                // Otherwise 21 is normal intensity, as on the Linux console
+                21 | 22 => { attrs.bold = false; attrs.dim = false; },
+                // Fraktur is a style of italics, so 23 ends both
+                23 => {
+                    attrs.italic = false;
+                    if attrs.font == 10 {
+                        attrs.font = 0;
+                    }
+                }
+                24 => attrs.underline = UnderlineType::None,
+                25 => attrs.blink = BlinkType::None,
+                27 => attrs.reverse = false,
+                28 => attrs.invisible = false,
+                29 => attrs.strikethrough = false,
+                30..=37 => attrs.foreground = Some(SgrColor::Indexed(param as u8 - 30)),
+                38 => attrs.foreground = extended_color(sub, &mut params).or(attrs.foreground),
+                39 => attrs.foreground = None,
+                40..=47 => attrs.background = Some(SgrColor::Indexed(param as u8 - 40)),
+                48 => attrs.background = extended_color(sub, &mut params).or(attrs.background),
+                49 => attrs.background = None,
+                51 => attrs.frame = FrameType::Framed,
+                52 => attrs.frame = FrameType::Encircled,
+                53 => attrs.overline = true,
+                54 => attrs.frame = FrameType::None,
+                55 => attrs.overline = false,
+                58 => attrs.underline_color = extended_color(sub, &mut params).or(attrs.underline_color),
+                59 => attrs.underline_color = None,
+                73 => attrs.script = ScriptPosition::Superscript,
+                74 => attrs.script = ScriptPosition::Subscript,
+                75 => attrs.script = ScriptPosition::Normal,
+                90..=97 => attrs.foreground = Some(SgrColor::Indexed(param as u8 - 90 + 8)),
+                100..=107 => attrs.background = Some(SgrColor::Indexed(param as u8 - 100 + 8)),
+                // Proportional spacing (26, 50) and ideogram lines (60-65) have no meaning on a grid
                 _ => {} // Ignore unknown parameters
             }
         }
@@ -241,6 +639,27 @@ impl TerminalModes {
     }
 }
 
+/// Read an SGR 38/48/58 color, from its sub-parameters (`38:2::r:g:b`, `38:5:n`) or,
+/// in the older form, from the parameters that follow (`38;2;r;g;b`, `38;5;n`)
+fn extended_color<'a>(sub: &[u16], params: &mut impl Iterator<Item = &'a Vec<u16>>) -> Option<SgrColor> {
+    let channel = |value: u16| value.min(255) as u8;
+    if let Some((&kind, rest)) = sub.split_first() {
+        return match (kind, rest) {
+            (5, [index, ..]) => Some(SgrColor::Indexed(channel(*index))),
+            // The color space id before the channels is optional
+            (2, [_, r, g, b, ..]) | (2, [r, g, b]) => Some(SgrColor::Rgb(channel(*r), channel(*g), channel(*b))),
+            _ => None,
+        };
+    }
+    
+    let mut next = || params.next().and_then(|group| group.first().copied());
+    match next()? {
+        5 => next().map(|index| SgrColor::Indexed(channel(index))),
+        2 => Some(SgrColor::Rgb(channel(next()?), channel(next()?), channel(next()?))),
+        _ => None,
+    }
+}
+
 /// Character set handling for terminal emulation
 #[derive(Debug, Clone)]
 pub struct CharacterSets {
@@ -249,7 +668,7 @@ pub struct CharacterSets {
     pub active: CharacterSetSlot,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CharacterSet {
     Ascii,
     DecSpecialCharacter,
@@ -257,7 +676,7 @@ pub enum CharacterSet {
     DecAlternateRom,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CharacterSetSlot {
     G0,
     G1,
@@ -273,6 +692,67 @@ impl Default for CharacterSets {
     }
 }
 
+impl CharacterSets {
+    /// Designate the set a slot holds
+    pub fn designate(&mut self, slot: CharacterSetSlot, set: CharacterSet) {
+        match slot {
+            CharacterSetSlot::G0 => self.g0 = set,
+            CharacterSetSlot::G1 => self.g1 = set,
+        }
+    }
+    
+    /// Map a printed character through the active set
+    pub fn translate(&self, ch: char) -> char {
+        let set = match self.active {
+            CharacterSetSlot::G0 => self.g0,
+            CharacterSetSlot::G1 => self.g1,
+        };
+        match set {
+            CharacterSet::DecSpecialCharacter => dec_special_graphic(ch),
+            _ => ch,
+        }
+    }
+}
+
+/// The DEC special graphics (line drawing) glyph for `_` through `~`
+fn dec_special_graphic(ch: char) -> char {
+    match ch {
+        '_' => '\u{a0}',
+        '`' => '◆',
+        'a' => '▒',
+        'b' => '␉',
+        'c' => '␌',
+        'd' => '␍',
+        'e' => '␊',
+        'f' => '°',
+        'g' => '±',
+        'h' => '␤',
+        'i' => '␋',
+        'j' => '┘',
+        'k' => '┐',
+        'l' => '┌',
+        'm' => '└',
+        'n' => '┼',
+        'o' => '⎺',
+        'p' => '⎻',
+        'q' => '─',
+        'r' => '⎼',
+        's' => '⎽',
+        't' => '├',
+        'u' => '┤',
+        'v' => '┴',
+        'w' => '┬',
+        'x' => '│',
+        'y' => '≤',
+        'z' => '≥',
+        '{' => 'π',
+        '|' => '≠',
+        '}' => '£',
+        '~' => '·',
+        _ => ch,
+    }
+}
+
 /// Tab stop management
 #[derive(Debug, Clone)]
 pub struct TabStops {
@@ -0,0 +1,96 @@
+/// Mouse events the program asked to receive (DECSET 9/1000/1002/1003)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MouseTracking {
+    #[default]
+    Off,
+    /// 9 - button presses only
+    X10,
+    /// 1000 - presses, releases and the wheel
+    Normal,
+    /// 1002 - also motion while a button is held
+    ButtonEvent,
+    /// 1003 - all motion
+    AnyEvent,
+}
+
+/// Wire format of mouse reports (DECSET 1005/1006/1015/1016)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MouseEncoding {
+    /// `CSI M Cb Cx Cy` with single-byte values
+    #[default]
+    X10,
+    /// 1005 - like X10 but values are UTF-8 encoded
+    Utf8,
+    /// 1006 - `CSI < Cb ; Cx ; Cy M/m`
+    Sgr,
+    /// 1015 - `CSI Cb ; Cx ; Cy M`
+    Urxvt,
+    /// 1016 - SGR with pixel coordinates
+    SgrPixels,
+}
+
+/// Mouse reporting modes requested by the program
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MouseReporting {
+    pub tracking: MouseTracking,
+    pub encoding: MouseEncoding,
+    /// 1007 - the wheel sends cursor keys on the alternate screen
+    pub alternate_scroll: bool,
+}
+
+impl MouseReporting {
+    /// Apply DECSET/DECRST to a mouse mode, returning false if `mode` is not one
+    pub fn set_mode(&mut self, mode: u16, enabled: bool) -> bool {
+        if let Some(tracking) = Self::tracking_for(mode) {
+            // Like xterm, resetting any tracking mode turns tracking off
+            self.tracking = if enabled { tracking } else { MouseTracking::Off };
+            return true;
+        }
+        
+        if let Some(encoding) = Self::encoding_for(mode) {
+            if enabled {
+                self.encoding = encoding;
+            } else if self.encoding == encoding {
+                self.encoding = MouseEncoding::X10;
+            }
+            return true;
+        }
+        
+        if mode == 1007 {
+            self.alternate_scroll = enabled;
+            return true;
+        }
+        false
+    }
+    
+    /// Whether a mouse mode is set, or `None` if `mode` is not one
+    pub fn mode_state(&self, mode: u16) -> Option<bool> {
+        if let Some(tracking) = Self::tracking_for(mode) {
+            return Some(self.tracking == tracking);
+        }
+        if let Some(encoding) = Self::encoding_for(mode) {
+            return Some(self.encoding == encoding);
+        }
+        (mode == 1007).then_some(self.alternate_scroll)
+    }
+    
+    fn tracking_for(mode: u16) -> Option<MouseTracking> {
+        match mode {
+            9 => Some(MouseTracking::X10),
+            1000 => Some(MouseTracking::Normal),
+            1002 => Some(MouseTracking::ButtonEvent),
+            1003 => Some(MouseTracking::AnyEvent),
+            _ => None,
+        }
+    }
+    
+    fn encoding_for(mode: u16) -> Option<MouseEncoding> {
+        match mode {
+            1005 => Some(MouseEncoding::Utf8),
+            1006 => Some(MouseEncoding::Sgr),
+            1015 => Some(MouseEncoding::Urxvt),
+            1016 => Some(MouseEncoding::SgrPixels),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use super::*;
+use base64::Engine;
+
+/// A desktop notification requested by the program running in a pane
+#[derive(Debug, Clone, PartialEq)]
+pub struct DesktopNotification {
+    pub id: Option<String>,
+    pub title: String,
+    pub body: String,
+    /// Whether more chunks follow (kitty's `d=0`)
+    pub partial: bool,
+}
+
+impl DesktopNotification {
+    /// Create a complete notification with a title and body
+    pub fn new(title: impl Into<String>, body: impl Into<String>) -> Self {
+        DesktopNotification {
+            id: None,
+            title: title.into(),
+            body: body.into(),
+            partial: false,
+        }
+    }
+    
+    /// Parse OSC 9 (iTerm2 growl-style): `9;message`
+    pub fn parse_osc9(payload: &str) -> Option<Self> {
+        // ConEmu reuses OSC 9 with numeric subcommands (progress, cwd, ...)
+        if let Some((first, _)) = payload.split_once(';') {
+            if !first.is_empty() && first.bytes().all(|b| b.is_ascii_digit()) {
+                return None;
+            }
+        }
+        if payload.is_empty() {
+            return None;
+        }
+        Some(DesktopNotification::new("", payload))
+    }
+    
+    /// Parse OSC 777 (urxvt): `777;notify;title;body`
+    pub fn parse_osc777(payload: &str) -> Option<Self> {
+        let mut fields = payload.splitn(3, ';');
+        if fields.next()? != "notify" {
+            return None;
+        }
+        let title = fields.next().unwrap_or("");
+        let body = fields.next().unwrap_or("");
+        Some(DesktopNotification::new(title, body))
+    }
+    
+    /// Parse OSC 99 (kitty): `99;key=value:key=value;payload`
+    pub fn parse_osc99(payload: &str) -> Option<Self> {
+        let (metadata, text) = payload.split_once(';')?;
+        
+        let mut notification = DesktopNotification::new("", "");
+        let mut target_body = false;
+        let mut base64_encoded = false;
+        
+        for entry in metadata.split(':').filter(|entry| !entry.is_empty()) {
+            let (key, value) = entry.split_once('=')?;
+            match key {
+                "i" => notification.id = Some(value.to_string()),
+                "d" => notification.partial = value == "0",
+                "e" => base64_encoded = value == "1",
+                "p" => match value {
+                    "title" => target_body = false,
+                    "body" => target_body = true,
+                    // Icons, buttons and queries are not supported
+                    _ => return None,
+                },
+                _ => {} // Ignore urgency, actions and other keys
+            }
+        }
+        
+        let text = if base64_encoded {
+            let decoded = base64::engine::general_purpose::STANDARD.decode(text).ok()?;
+            String::from_utf8_lossy(&decoded).into_owned()
+        } else {
+            text.to_string()
+        };
+        
+        if target_body {
+            notification.body = text;
+        } else {
+            notification.title = text;
+        }
+        Some(notification)
+    }
+    
+    /// Append a follow-up kitty chunk to this notification
+    pub fn merge(&mut self, chunk: DesktopNotification) {
+        self.title.push_str(&chunk.title);
+        self.body.push_str(&chunk.body);
+        self.partial = chunk.partial;
+    }
+}
+
+impl Pane {
+    /// Handle a notification request, assembling kitty chunks first
+    ///
+    /// Complete notifications go out as `PaneEvent::NotificationRequested`, which the frame's
+    /// `PaneEventForwarder` hands on to its `NotificationCenter`.
+    pub(crate) fn request_notification(&mut self, chunk: DesktopNotification) -> PaneResult<()> {
+        let notification = match self.pending_notification.take() {
+            Some(mut pending) if pending.id == chunk.id => {
+                pending.merge(chunk);
+                pending
+            }
+            _ => chunk,
+        };
+        
+        if notification.partial {
+            self.pending_notification = Some(notification);
+            return Ok(());
+        }
+        
+        self.emit_event(PaneEvent::NotificationRequested(notification))
+    }
+}
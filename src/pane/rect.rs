@@ -0,0 +1,323 @@
+use super::*;
+
+/// A rectangle of cells, 0-based and inclusive on every edge
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CellRect {
+    pub top: u16,
+    pub left: u16,
+    pub bottom: u16,
+    pub right: u16,
+}
+
+impl CellRect {
+    pub fn new(top: u16, left: u16, bottom: u16, right: u16) -> Self {
+        CellRect { top, left, bottom, right }
+    }
+    
+    pub fn contains(&self, row: u16, col: u16) -> bool {
+        (self.top..=self.bottom).contains(&row) && (self.left..=self.right).contains(&col)
+    }
+}
+
+/// Which cells DECCARA and DECRARA change (DECSACE)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AttributeChangeExtent {
+    /// From the start position to the end position, wrapping like text
+    #[default]
+    Stream,
+    /// Exactly the rectangle
+    Rectangle,
+}
+
+/// Attribute that DECCARA sets or clears and DECRARA reverses
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RectAttribute {
+    Bold,
+    Underline,
+    Blink,
+    Reverse,
+}
+
+impl RectAttribute {
+    const ALL: [RectAttribute; 4] = [
+        RectAttribute::Bold,
+        RectAttribute::Underline,
+        RectAttribute::Blink,
+        RectAttribute::Reverse,
+    ];
+    
+    fn from_sgr(param: i32) -> Option<Self> {
+        match param {
+            1 => Some(RectAttribute::Bold),
+            4 => Some(RectAttribute::Underline),
+            5 => Some(RectAttribute::Blink),
+            7 => Some(RectAttribute::Reverse),
+            _ => None,
+        }
+    }
+    
+    /// Attribute turned off by an SGR reset parameter; 22 clears bold, not the faint 2 it would suggest
+    fn from_sgr_reset(param: i32) -> Option<Self> {
+        match param {
+            22 => Some(RectAttribute::Bold),
+            24 => Some(RectAttribute::Underline),
+            25 => Some(RectAttribute::Blink),
+            27 => Some(RectAttribute::Reverse),
+            _ => None,
+        }
+    }
+    
+    fn is_set(self, attrs: &CellAttributes) -> bool {
+        match self {
+            RectAttribute::Bold => attrs.bold,
+            RectAttribute::Underline => attrs.underline != UnderlineType::None,
+            RectAttribute::Blink => attrs.blink != BlinkType::None,
+            RectAttribute::Reverse => attrs.reverse,
+        }
+    }
+    
+    fn set(self, attrs: &mut CellAttributes, enabled: bool) {
+        match self {
+            RectAttribute::Bold => attrs.bold = enabled,
+            RectAttribute::Underline => {
+                attrs.underline = if enabled { UnderlineType::Single } else { UnderlineType::None };
+            }
+            RectAttribute::Blink => attrs.blink = if enabled { BlinkType::Slow } else { BlinkType::None },
+            RectAttribute::Reverse => attrs.reverse = enabled,
+        }
+    }
+}
+
+impl ScreenBuffer {
+    /// Copy a rectangle so its top-left lands at `(top, left)`, clipping at the screen edge (DECCRA)
+    pub fn copy_rect(&mut self, source: CellRect, top: u16, left: u16) {
+        // Snapshot first so overlapping copies read the original cells
+        let copied: Vec<Vec<Cell>> = (source.top..=source.bottom)
+            .map(|row| self.lines[row as usize].cells[source.left as usize..=source.right as usize].to_vec())
+            .collect();
+        
+        for (row_offset, cells) in copied.into_iter().enumerate() {
+            let row = top as usize + row_offset;
+            if row >= self.height as usize {
+                break;
+            }
+            let line = &mut self.lines[row];
+            for (col_offset, cell) in cells.into_iter().enumerate() {
+                if let Some(target) = line.cells.get_mut(left as usize + col_offset) {
+                    *target = cell;
+                }
+            }
+            line.dirty = true;
+            self.dirty_regions.mark_line_dirty(row as u16);
+        }
+    }
+    
+    /// Fill a rectangle with a character in the given attributes (DECFRA)
+    pub fn fill_rect(&mut self, rect: CellRect, ch: char, attrs: &CellAttributes) {
+        self.update_rect(rect, |cell| {
+            *cell = Cell { character: ch, attributes: attrs.clone(), ..Cell::default() };
+        });
+    }
+    
+    /// Erase a rectangle to default blanks (DECERA)
+    pub fn erase_rect(&mut self, rect: CellRect) {
+        self.update_rect(rect, |cell| *cell = Cell::default());
+    }
+    
+    /// Blank the unprotected characters of a rectangle but keep their attributes (DECSERA)
+    pub fn selective_erase_rect(&mut self, rect: CellRect) {
+        self.update_rect(rect, |cell| {
+            if !cell.attributes.protected {
+                cell.character = ' ';
+            }
+        });
+    }
+    
+    /// Set or clear attributes with SGR-style parameters (DECCARA)
+    pub fn change_rect_attributes(&mut self, rect: CellRect, extent: AttributeChangeExtent, params: &[i32]) {
+        let params = if params.is_empty() { &[0][..] } else { params };
+        self.update_extent(rect, extent, |cell| {
+            for &param in params {
+                match param {
+                    0 => RectAttribute::ALL.iter().for_each(|attr| attr.set(&mut cell.attributes, false)),
+                    22 | 24 | 25 | 27 => {
+                        if let Some(attr) = RectAttribute::from_sgr_reset(param) {
+                            attr.set(&mut cell.attributes, false);
+                        }
+                    }
+                    _ => {
+                        if let Some(attr) = RectAttribute::from_sgr(param) {
+                            attr.set(&mut cell.attributes, true);
+                        }
+                    }
+                }
+            }
+        });
+    }
+    
+    /// Toggle attributes with SGR-style parameters, 0 meaning all of them (DECRARA)
+    pub fn reverse_rect_attributes(&mut self, rect: CellRect, extent: AttributeChangeExtent, params: &[i32]) {
+        let attrs: Vec<RectAttribute> = if params.is_empty() || params.contains(&0) {
+            RectAttribute::ALL.to_vec()
+        } else {
+            params.iter().filter_map(|&param| RectAttribute::from_sgr(param)).collect()
+        };
+        self.update_extent(rect, extent, |cell| {
+            for attr in &attrs {
+                let enabled = attr.is_set(&cell.attributes);
+                attr.set(&mut cell.attributes, !enabled);
+            }
+        });
+    }
+    
+    /// Checksum of a rectangle as xterm computes it for DECRQCRA
+    pub fn rect_checksum(&self, rect: CellRect) -> u16 {
+        let mut total: u16 = 0;
+        for row in rect.top..=rect.bottom {
+            for cell in &self.lines[row as usize].cells[rect.left as usize..=rect.right as usize] {
+                let attrs = &cell.attributes;
+                let mut value = cell.character as u32 as u16;
+                if attrs.bold {
+                    value += 0x80;
+                }
+                if attrs.blink != BlinkType::None {
+                    value += 0x40;
+                }
+                if attrs.reverse {
+                    value += 0x20;
+                }
+                if attrs.underline != UnderlineType::None {
+                    value += 0x10;
+                }
+                if attrs.invisible {
+                    value += 0x08;
+                }
+                if attrs.protected {
+                    value += 0x04;
+                }
+                total = total.wrapping_add(value);
+            }
+        }
+        // The terminal reports the negated sum
+        total.wrapping_neg()
+    }
+    
+    fn update_rect(&mut self, rect: CellRect, mut update: impl FnMut(&mut Cell)) {
+        for row in rect.top..=rect.bottom {
+            let line = &mut self.lines[row as usize];
+            line.cells[rect.left as usize..=rect.right as usize].iter_mut().for_each(&mut update);
+            line.dirty = true;
+            self.dirty_regions.mark_line_dirty(row);
+        }
+    }
+    
+    fn update_extent(&mut self, rect: CellRect, extent: AttributeChangeExtent, update: impl FnMut(&mut Cell)) {
+        if extent == AttributeChangeExtent::Rectangle || rect.top == rect.bottom {
+            self.update_rect(rect, update);
+            return;
+        }
+        
+        // A stream runs from the start to the end of the screen rows in between
+        let last_col = self.width.saturating_sub(1);
+        let mut update = update;
+        for row in rect.top..=rect.bottom {
+            let left = if row == rect.top { rect.left } else { 0 };
+            let right = if row == rect.bottom { rect.right } else { last_col };
+            self.update_rect(CellRect::new(row, left, row, right), &mut update);
+        }
+    }
+}
+
+impl Pane {
+    /// Apply a VT400 rectangular area operation
+    pub(crate) fn handle_rect_command(&mut self, command: VtCommand) {
+        match command {
+            VtCommand::CopyRectangle(params) => {
+                // Pts;Pls;Pbs;Prs;Pps;Ptd;Pld;Ppd - pages are ignored, and the destination is only a corner
+                let corner: Vec<i32> = params.iter().skip(5).take(2).copied().collect();
+                let destination = self.rect_from_params(&corner);
+                if let (Some(source), Some(destination)) = (self.rect_from_params(&params), destination) {
+                    self.screen_buffer.copy_rect(source, destination.top, destination.left);
+                }
+            }
+            VtCommand::FillRectangle(params) => {
+                let ch = params.first().and_then(|&code| u32::try_from(code).ok()).and_then(char::from_u32);
+                // Only graphic characters can fill
+                let fill = ch.filter(|ch| matches!(*ch as u32, 32..=126 | 160..=255));
+                if let (Some(ch), Some(rect)) = (fill, self.rect_from_params(params.get(1..).unwrap_or(&[]))) {
+                    let attrs = self.modes.current_attributes.clone();
+                    self.screen_buffer.fill_rect(rect, ch, &attrs);
+                }
+            }
+            VtCommand::EraseRectangle(params) => {
+                if let Some(rect) = self.rect_from_params(&params) {
+                    self.screen_buffer.erase_rect(rect);
+                }
+            }
+            VtCommand::SelectiveEraseRectangle(params) => {
+                if let Some(rect) = self.rect_from_params(&params) {
+                    self.screen_buffer.selective_erase_rect(rect);
+                }
+            }
+            VtCommand::ChangeRectangleAttributes(params) => {
+                let extent = self.modes.attribute_change_extent;
+                if let Some(rect) = self.extent_from_params(&params, extent) {
+                    self.screen_buffer.change_rect_attributes(rect, extent, params.get(4..).unwrap_or(&[]));
+                }
+            }
+            VtCommand::ReverseRectangleAttributes(params) => {
+                let extent = self.modes.attribute_change_extent;
+                if let Some(rect) = self.extent_from_params(&params, extent) {
+                    self.screen_buffer.reverse_rect_attributes(rect, extent, params.get(4..).unwrap_or(&[]));
+                }
+            }
+            VtCommand::SetAttributeChangeExtent(mode) => {
+                self.modes.attribute_change_extent = match mode {
+                    2 => AttributeChangeExtent::Rectangle,
+                    _ => AttributeChangeExtent::Stream,
+                };
+            }
+            VtCommand::RequestRectangleChecksum(params) => {
+                // Pid;Pp;Pt;Pl;Pb;Pr - an empty area sums to zero
+                let id = params.first().copied().unwrap_or(0);
+                let checksum = self
+                    .rect_from_params(params.get(2..).unwrap_or(&[]))
+                    .map_or(0, |rect| self.screen_buffer.rect_checksum(rect));
+                let reply = format!("\x1bP{}!~{:04X}\x1b\\", id, checksum);
+                self.respond(reply.as_bytes());
+            }
+            _ => {}
+        }
+    }
+    
+    /// Corner that rectangle coordinates are measured from in origin mode: the top-left margin
+    fn rect_origin(&self) -> (u16, u16) {
+        (self.screen_buffer.margins.top, self.screen_buffer.margins.left)
+    }
+    
+    /// Turn 1-based `Pt;Pl;Pb;Pr` parameters into a clipped rectangle; 0 or missing means the edge
+    fn rect_from_params(&self, params: &[i32]) -> Option<CellRect> {
+        self.extent_from_params(params, AttributeChangeExtent::Rectangle)
+    }
+    
+    /// Like `rect_from_params`, but a stream over several rows may end left of where it starts
+    fn extent_from_params(&self, params: &[i32], extent: AttributeChangeExtent) -> Option<CellRect> {
+        let (height, width) = (self.screen_buffer.height, self.screen_buffer.width);
+        let (row_offset, col_offset) = if self.modes.origin_mode { self.rect_origin() } else { (0, 0) };
+        let coordinate = |index: usize, default: u16, offset: u16, limit: u16| {
+            let value = params.get(index).copied().filter(|&value| value > 0);
+            let value = value.map_or(default, |value| value.min(u16::MAX as i32) as u16);
+            (value.saturating_add(offset)).min(limit)
+        };
+        
+        let top = coordinate(0, 1, row_offset, height);
+        let left = coordinate(1, 1, col_offset, width);
+        let bottom = coordinate(2, height, row_offset, height);
+        let right = coordinate(3, width, col_offset, width);
+        let columns_reversed = left > right && !(extent == AttributeChangeExtent::Stream && top < bottom);
+        if top > bottom || columns_reversed || height == 0 || width == 0 {
+            return None;
+        }
+        Some(CellRect::new(top - 1, left - 1, bottom - 1, right - 1))
+    }
+}
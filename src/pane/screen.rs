@@ -0,0 +1,52 @@
+//! The alternate screen (DECSET 47/1047/1049)
+//!
+//! While it is shown, the main screen is stashed in `Pane::main_screen`, its image placements
+//! are set aside in the image layer, and line feeds at the bottom margin scroll nothing into
+//! the scrollback.
+
+use super::*;
+
+impl Pane {
+    /// Switch to or from the alternate screen (DECSET 47/1047/1049)
+    pub(crate) fn set_alternate_screen(&mut self, enabled: bool, save_cursor: bool) -> PaneResult<()> {
+        if enabled == self.modes.alternate_screen {
+            return Ok(());
+        }
+        
+        if enabled {
+            if save_cursor {
+                self.cursor.save_position();
+            }
+            let (width, height) = self.size();
+            let main = std::mem::replace(&mut self.screen_buffer, ScreenBuffer::new(width, height));
+            // The margins belong to the terminal, not to either screen
+            self.screen_buffer.margins = main.margins;
+            self.main_screen = Some(main);
+            self.images.write().stash_placements();
+        } else {
+            if let Some(main) = self.main_screen.take() {
+                let margins = self.screen_buffer.margins;
+                self.screen_buffer = main;
+                self.screen_buffer.margins = margins;
+            }
+            self.images.write().restore_placements();
+            if save_cursor {
+                self.cursor.restore_position(&self.screen_buffer)?;
+            }
+        }
+        
+        self.modes.alternate_screen = enabled;
+        self.keyboard_modes.set_alternate_screen(enabled);
+        self.screen_buffer.mark_all_dirty();
+        Ok(())
+    }
+    
+    /// Resize the visible screen, and the main screen while the alternate one is shown
+    pub(crate) fn resize_screens(&mut self, cols: u16, rows: u16) -> PaneResult<()> {
+        self.screen_buffer.resize(cols, rows)?;
+        if let Some(main) = self.main_screen.as_mut() {
+            main.resize(cols, rows)?;
+        }
+        Ok(())
+    }
+}
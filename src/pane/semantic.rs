@@ -0,0 +1,283 @@
+use super::*;
+use std::collections::VecDeque;
+use std::time::Instant;
+
+/// Default number of command blocks retained per pane
+pub const DEFAULT_COMMAND_HISTORY_LIMIT: usize = 1000;
+
+/// Semantic prompt marks (OSC 133 / FinalTerm shell integration)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SemanticMark {
+    /// OSC 133;A - a new prompt is about to be drawn
+    PromptStart,
+    /// OSC 133;B - the prompt ended, user input begins
+    CommandStart,
+    /// OSC 133;C - the command was submitted, output begins
+    OutputStart,
+    /// OSC 133;D[;exit] - the command finished
+    CommandFinished(Option<i32>),
+}
+
+impl SemanticMark {
+    /// Parse the payload of an OSC 133 sequence (everything after "133;")
+    pub fn parse(payload: &str) -> Option<Self> {
+        let mut fields = payload.split(';');
+        match fields.next()? {
+            "A" => Some(SemanticMark::PromptStart),
+            "B" => Some(SemanticMark::CommandStart),
+            "C" => Some(SemanticMark::OutputStart),
+            "D" => {
+                // Exit status is optional; extra key=value options are ignored
+                let exit_code = fields.next().and_then(|code| code.parse().ok());
+                Some(SemanticMark::CommandFinished(exit_code))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A zone marker attached to a line, recording where a mark was emitted
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ZoneMarker {
+    pub mark: SemanticMark,
+    pub col: u16,
+    pub block_id: u64,
+}
+
+/// A single shell command and its output
+#[derive(Debug, Clone)]
+pub struct CommandBlock {
+    pub id: u64,
+    pub command: String,
+    pub prompt_time: Instant,
+    pub start_time: Option<Instant>,
+    pub end_time: Option<Instant>,
+    pub exit_status: Option<i32>,
+}
+
+impl CommandBlock {
+    fn new(id: u64) -> Self {
+        CommandBlock {
+            id,
+            command: String::new(),
+            prompt_time: Instant::now(),
+            start_time: None,
+            end_time: None,
+            exit_status: None,
+        }
+    }
+    
+    /// Check if the command has finished running
+    pub fn is_finished(&self) -> bool {
+        self.end_time.is_some()
+    }
+    
+    /// Get the run time of a finished command
+    pub fn duration(&self) -> Option<std::time::Duration> {
+        match (self.start_time, self.end_time) {
+            (Some(start), Some(end)) => Some(end.duration_since(start)),
+            _ => None,
+        }
+    }
+}
+
+/// Per-pane history of command blocks built from semantic prompt marks
+#[derive(Debug, Clone)]
+pub struct CommandHistory {
+    blocks: VecDeque<CommandBlock>,
+    max_blocks: usize,
+    next_id: u64,
+}
+
+impl CommandHistory {
+    /// Create a new history retaining at most `max_blocks` blocks
+    pub fn new(max_blocks: usize) -> Self {
+        CommandHistory {
+            blocks: VecDeque::new(),
+            max_blocks,
+            next_id: 1,
+        }
+    }
+    
+    /// Start a new block for a prompt and return its id
+    pub fn begin_prompt(&mut self) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        
+        if self.blocks.len() >= self.max_blocks {
+            self.blocks.pop_front();
+        }
+        self.blocks.push_back(CommandBlock::new(id));
+        id
+    }
+    
+    /// Get the id of the current block, starting one if none exists
+    pub fn current_id(&mut self) -> u64 {
+        match self.blocks.back() {
+            Some(block) if !block.is_finished() => block.id,
+            _ => self.begin_prompt(),
+        }
+    }
+    
+    /// Record that the command was submitted
+    pub fn start_command(&mut self, command: String) {
+        if let Some(block) = self.blocks.back_mut() {
+            block.command = command;
+            block.start_time = Some(Instant::now());
+        }
+    }
+    
+    /// Record that the command finished
+    pub fn finish_command(&mut self, exit_status: Option<i32>) {
+        if let Some(block) = self.blocks.back_mut() {
+            if block.start_time.is_some() && !block.is_finished() {
+                block.end_time = Some(Instant::now());
+                block.exit_status = exit_status;
+            }
+        }
+    }
+    
+    /// Get a block by id
+    pub fn get(&self, id: u64) -> Option<&CommandBlock> {
+        self.blocks.iter().find(|block| block.id == id)
+    }
+    
+    /// The most recent block whose command has produced output
+    pub fn last_command(&self) -> Option<&CommandBlock> {
+        self.blocks.iter().rev().find(|block| block.start_time.is_some())
+    }
+    
+    /// Iterate over all retained blocks, oldest first
+    pub fn blocks(&self) -> impl Iterator<Item = &CommandBlock> {
+        self.blocks.iter()
+    }
+    
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+    
+    pub fn clear(&mut self) {
+        self.blocks.clear();
+    }
+}
+
+impl Default for CommandHistory {
+    fn default() -> Self {
+        Self::new(DEFAULT_COMMAND_HISTORY_LIMIT)
+    }
+}
+
+impl Pane {
+    /// Apply a semantic prompt mark at the cursor position
+    pub(crate) fn apply_semantic_mark(&mut self, mark: SemanticMark) -> PaneResult<()> {
+        let block_id = match mark {
+            SemanticMark::PromptStart => self.command_history.begin_prompt(),
+            _ => self.command_history.current_id(),
+        };
+        
+        if let SemanticMark::OutputStart = mark {
+            let command = self.command_text(block_id);
+            self.command_history.start_command(command);
+        }
+        if let SemanticMark::CommandFinished(exit_status) = mark {
+            self.command_history.finish_command(exit_status);
+        }
+        
+        let pos = self.cursor.position;
+        if let Some(line) = self.screen_buffer.get_line_mut(pos.row as usize) {
+            line.add_zone_marker(ZoneMarker { mark, col: pos.col, block_id });
+        }
+        Ok(())
+    }
+    
+    /// Iterate over scrollback and screen lines as one absolute line space
+    pub(crate) fn all_lines(&self) -> impl Iterator<Item = &Line> {
+        self.scrollback.lines.iter().chain(self.screen_buffer.lines.iter())
+    }
+    
+    /// Find the absolute line and column of a mark belonging to a block
+    fn find_zone_marker(&self, block_id: u64, matches: impl Fn(SemanticMark) -> bool) -> Option<(usize, u16)> {
+        self.all_lines().enumerate().find_map(|(idx, line)| {
+            line.zone_markers.iter()
+                .find(|marker| marker.block_id == block_id && matches(marker.mark))
+                .map(|marker| (idx, marker.col))
+        })
+    }
+    
+    /// Convert an absolute line index into a buffer-relative selection point
+    fn selection_point(&self, line: usize, column: usize) -> SelectionPoint {
+        let scrollback_len = self.scrollback.len();
+        if line < scrollback_len {
+            SelectionPoint { line, column, buffer_type: BufferType::Scrollback }
+        } else {
+            SelectionPoint { line: line - scrollback_len, column, buffer_type: BufferType::Screen }
+        }
+    }
+    
+    /// Extract text between two absolute positions (end is exclusive)
+    fn text_between(&self, start: (usize, u16), end: (usize, u16)) -> String {
+        let mut text = String::new();
+        for (idx, line) in self.all_lines().enumerate().skip(start.0).take(end.0.saturating_sub(start.0) + 1) {
+            let from = if idx == start.0 { start.1 as usize } else { 0 };
+            let to = if idx == end.0 { end.1 as usize } else { line.cells.len() };
+            let segment: String = line.cells.iter()
+                .skip(from)
+                .take(to.saturating_sub(from))
+                .map(|cell| cell.character)
+                .collect();
+            text.push_str(segment.trim_end());
+            if idx != end.0 && !line.wrapped {
+                text.push('\n');
+            }
+        }
+        text
+    }
+    
+    /// Recover the command line typed between the B and C marks
+    fn command_text(&self, block_id: u64) -> String {
+        let start = self.find_zone_marker(block_id, |mark| mark == SemanticMark::CommandStart);
+        let end = (self.scrollback.len() + self.cursor.position.row as usize, self.cursor.position.col);
+        match start {
+            Some(start) if start <= end => self.text_between(start, end).trim().to_string(),
+            _ => String::new(),
+        }
+    }
+    
+    /// Absolute line indices of all prompt starts, in order
+    fn prompt_lines(&self) -> Vec<usize> {
+        self.all_lines().enumerate()
+            .filter(|(_, line)| line.has_zone_marker(|mark| mark == SemanticMark::PromptStart))
+            .map(|(idx, _)| idx)
+            .collect()
+    }
+    
+    pub(crate) fn find_previous_prompt(&self, from_line: usize) -> Option<usize> {
+        self.prompt_lines().into_iter().rev().find(|&line| line < from_line)
+    }
+    
+    pub(crate) fn find_next_prompt(&self, from_line: usize) -> Option<usize> {
+        self.prompt_lines().into_iter().find(|&line| line > from_line)
+    }
+    
+    /// Build a selection covering the output of the most recent command
+    pub(crate) fn last_command_output_selection(&self) -> Option<Selection> {
+        let block = self.command_history.last_command()?;
+        let start = self.find_zone_marker(block.id, |mark| mark == SemanticMark::OutputStart)?;
+        
+        // Output runs until the D mark, or the next prompt if the shell never sent one
+        let end = self.find_zone_marker(block.id, |mark| matches!(mark, SemanticMark::CommandFinished(_)))
+            .or_else(|| self.find_next_prompt(start.0).map(|line| (line, 0)))
+            .unwrap_or_else(|| (self.scrollback.len() + self.cursor.position.row as usize, self.cursor.position.col));
+        
+        Some(Selection {
+            start: self.selection_point(start.0, start.1 as usize),
+            end: self.selection_point(end.0, end.1 as usize),
+            text: self.text_between(start, end).trim_end_matches('\n').to_string(),
+            selection_type: SelectionType::Character,
+        })
+    }
+}
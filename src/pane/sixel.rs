@@ -0,0 +1,240 @@
+use super::*;
+
+/// Largest sixel image accepted, in pixels per side
+pub const SIXEL_MAX_DIMENSION: usize = 4096;
+
+/// Number of palette registers supported by the decoder
+pub const SIXEL_PALETTE_SIZE: usize = 256;
+
+/// Streaming decoder for DEC sixel data (the body of `DCS P1;P2;P3 q ... ST`)
+#[derive(Debug, Clone)]
+pub struct SixelDecoder {
+    palette: Vec<[u8; 4]>,
+    current_color: usize,
+    transparent_background: bool,
+    
+    // Decoded pixels as palette-resolved RGBA, grown on demand
+    width: usize,
+    height: usize,
+    pixels: Vec<[u8; 4]>,
+    
+    // Drawing position
+    x: usize,
+    band: usize,
+}
+
+impl SixelDecoder {
+    /// Create a decoder from the DCS parameters
+    pub fn new(params: &[i32]) -> Self {
+        // P2 = 1 leaves unset pixels transparent; 0 and 2 paint the background
+        let transparent_background = params.get(1).copied() == Some(1);
+        
+        SixelDecoder {
+            palette: default_palette(),
+            current_color: 0,
+            transparent_background,
+            width: 0,
+            height: 0,
+            pixels: Vec::new(),
+            x: 0,
+            band: 0,
+        }
+    }
+    
+    /// Decode a complete sixel data stream
+    pub fn decode(params: &[i32], data: &[u8]) -> PaneResult<RgbaImage> {
+        let mut decoder = SixelDecoder::new(params);
+        decoder.feed(data)?;
+        Ok(decoder.finish())
+    }
+    
+    /// Feed sixel data bytes to the decoder
+    pub fn feed(&mut self, data: &[u8]) -> PaneResult<()> {
+        let mut i = 0;
+        while i < data.len() {
+            let byte = data[i];
+            i += 1;
+            
+            match byte {
+                b'?'..=b'~' => self.draw_sixel(byte - b'?', 1)?,
+                b'!' => {
+                    // Repeat introducer: !Pn <sixel>
+                    let (count, next) = read_numbers(data, i);
+                    i = next;
+                    if let Some(&sixel @ b'?'..=b'~') = data.get(i) {
+                        i += 1;
+                        let count = count.first().copied().unwrap_or(1).max(1) as usize;
+                        self.draw_sixel(sixel - b'?', count)?;
+                    }
+                }
+                b'"' => {
+                    // Raster attributes: "Pan;Pad;Ph;Pv
+                    let (values, next) = read_numbers(data, i);
+                    i = next;
+                    if let (Some(&width), Some(&height)) = (values.get(2), values.get(3)) {
+                        self.ensure_size(width.max(0) as usize, height.max(0) as usize)?;
+                    }
+                }
+                b'#' => {
+                    // Color introducer: #Pc or #Pc;Pu;Px;Py;Pz
+                    let (values, next) = read_numbers(data, i);
+                    i = next;
+                    self.select_or_define_color(&values);
+                }
+                b'$' => self.x = 0,
+                b'-' => {
+                    self.x = 0;
+                    self.band += 1;
+                }
+                _ => {} // Ignore whitespace and stray control bytes
+            }
+        }
+        Ok(())
+    }
+    
+    /// Finish decoding and produce the RGBA image
+    pub fn finish(self) -> RgbaImage {
+        let mut image = RgbaImage::new(self.width as u32, self.height as u32);
+        for (idx, pixel) in self.pixels.iter().enumerate() {
+            image.pixels[idx * 4..idx * 4 + 4].copy_from_slice(pixel);
+        }
+        image
+    }
+    
+    fn select_or_define_color(&mut self, values: &[i32]) {
+        let Some(&register) = values.first() else { return };
+        let register = register.max(0) as usize % SIXEL_PALETTE_SIZE;
+        
+        if values.len() >= 5 {
+            let (x, y, z) = (values[2], values[3], values[4]);
+            let rgb = match values[1] {
+                1 => hls_to_rgb(x, y, z),
+                2 => [percent_to_byte(x), percent_to_byte(y), percent_to_byte(z)],
+                _ => return,
+            };
+            self.palette[register] = [rgb[0], rgb[1], rgb[2], 255];
+        }
+        self.current_color = register;
+    }
+    
+    fn draw_sixel(&mut self, bits: u8, count: usize) -> PaneResult<()> {
+        let top = self.band * 6;
+        let right = self.x + count;
+        let bottom_needed = if bits == 0 { top } else { top + 6 };
+        self.ensure_size(right.max(self.width), bottom_needed.max(self.height))?;
+        
+        let color = self.palette[self.current_color];
+        for bit in 0..6 {
+            if bits & (1 << bit) == 0 {
+                continue;
+            }
+            let row = top + bit;
+            for col in self.x..right {
+                self.pixels[row * self.width + col] = color;
+            }
+        }
+        
+        self.x = right;
+        Ok(())
+    }
+    
+    fn ensure_size(&mut self, width: usize, height: usize) -> PaneResult<()> {
+        if width <= self.width && height <= self.height {
+            return Ok(());
+        }
+        if width > SIXEL_MAX_DIMENSION || height > SIXEL_MAX_DIMENSION {
+            return Err(PaneError::parse(
+                format!("Sixel image {}x{} exceeds maximum dimension {}", width, height, SIXEL_MAX_DIMENSION)
+            ));
+        }
+        
+        let new_width = width.max(self.width);
+        let new_height = height.max(self.height);
+        let background = if self.transparent_background { [0, 0, 0, 0] } else { self.palette[0] };
+        
+        let mut pixels = vec![background; new_width * new_height];
+        for row in 0..self.height {
+            let src = &self.pixels[row * self.width..(row + 1) * self.width];
+            pixels[row * new_width..row * new_width + self.width].copy_from_slice(src);
+        }
+        
+        self.pixels = pixels;
+        self.width = new_width;
+        self.height = new_height;
+        Ok(())
+    }
+}
+
+/// Read `;`-separated decimal numbers starting at `start`
+fn read_numbers(data: &[u8], start: usize) -> (Vec<i32>, usize) {
+    let mut values = Vec::new();
+    let mut current: Option<i32> = None;
+    let mut i = start;
+    
+    while let Some(&byte) = data.get(i) {
+        match byte {
+            b'0'..=b'9' => {
+                let digit = (byte - b'0') as i32;
+                current = Some(current.unwrap_or(0).saturating_mul(10).saturating_add(digit));
+            }
+            b';' => values.push(current.take().unwrap_or(0)),
+            _ => break,
+        }
+        i += 1;
+    }
+    
+    if let Some(value) = current {
+        values.push(value);
+    }
+    (values, i)
+}
+
+fn percent_to_byte(value: i32) -> u8 {
+    (value.clamp(0, 100) * 255 / 100) as u8
+}
+
+/// Convert sixel HLS (hue 0 = blue, lightness and saturation in percent) to RGB
+fn hls_to_rgb(hue: i32, lightness: i32, saturation: i32) -> [u8; 3] {
+    let h = ((hue.rem_euclid(360) + 240) % 360) as f32 / 360.0;
+    let l = lightness.clamp(0, 100) as f32 / 100.0;
+    let s = saturation.clamp(0, 100) as f32 / 100.0;
+    
+    if s == 0.0 {
+        let v = (l * 255.0).round() as u8;
+        return [v, v, v];
+    }
+    
+    let q = if l < 0.5 { l * (1.0 + s) } else { l + s - l * s };
+    let p = 2.0 * l - q;
+    let channel = |t: f32| {
+        let t = t.rem_euclid(1.0);
+        let v = if t < 1.0 / 6.0 {
+            p + (q - p) * 6.0 * t
+        } else if t < 0.5 {
+            q
+        } else if t < 2.0 / 3.0 {
+            p + (q - p) * (2.0 / 3.0 - t) * 6.0
+        } else {
+            p
+        };
+        (v * 255.0).round() as u8
+    };
+    
+    [channel(h + 1.0 / 3.0), channel(h), channel(h - 1.0 / 3.0)]
+}
+
+/// The VT340 default palette for the first 16 registers
+fn default_palette() -> Vec<[u8; 4]> {
+    const VT340: [[u8; 3]; 16] = [
+        [0, 0, 0], [51, 51, 204], [204, 36, 36], [51, 204, 51],
+        [204, 51, 204], [51, 204, 204], [204, 204, 51], [135, 135, 135],
+        [66, 66, 66], [84, 84, 153], [153, 66, 66], [84, 153, 84],
+        [153, 84, 153], [84, 153, 153], [153, 153, 84], [204, 204, 204],
+    ];
+    
+    let mut palette = vec![[0, 0, 0, 255]; SIXEL_PALETTE_SIZE];
+    for (slot, rgb) in palette.iter_mut().zip(VT340.iter()) {
+        *slot = [rgb[0], rgb[1], rgb[2], 255];
+    }
+    palette
+}
@@ -31,6 +31,20 @@ impl Terminal {
         Ok(None)
     }
     
+    /// Parse a byte without interpreting the result, for tracing
+    pub(crate) fn parse_byte(&mut self, byte: u8) -> PaneResult<Option<VtSequence>> {
+        self.parser.process_byte(byte)
+    }
+    
+    pub(crate) fn interpret(&self, sequence: VtSequence) -> PaneResult<Option<VtCommand>> {
+        self.interpreter.interpret(sequence)
+    }
+    
+    /// Whether the parser is between sequences
+    pub(crate) fn is_idle(&self) -> bool {
+        self.parser.is_idle()
+    }
+    
     /// Process multiple bytes of input
     pub fn process_bytes(&mut self, bytes: &[u8]) -> PaneResult<Vec<VtCommand>> {
         let mut commands = Vec::new();
@@ -44,6 +58,20 @@ impl Terminal {
         Ok(commands)
     }
     
+    /// Register a handler for unrecognized sequences, returning any it replaces
+    pub fn register_handler(&mut self, name: String, handler: Box<dyn VtCommandHandler>) -> Option<Box<dyn VtCommandHandler>> {
+        self.interpreter.register_handler(name, handler)
+    }
+    
+    /// Remove a handler by name
+    pub fn unregister_handler(&mut self, name: &str) -> Option<Box<dyn VtCommandHandler>> {
+        self.interpreter.unregister_handler(name)
+    }
+    
+    pub(crate) fn interpreter_mut(&mut self) -> &mut VtInterpreter {
+        &mut self.interpreter
+    }
+    
     /// Get current terminal mode
     pub fn current_mode(&self) -> TerminalMode {
         self.current_mode
@@ -61,6 +89,13 @@ pub struct VtParser {
     state_machine: ParserStateMachine,
     current_sequence: Vec<u8>,
     params: Vec<i32>,
+    /// Each parameter with its colon-separated sub-parameters (`4:3`, `58:2::255:0:0`), for SGR
+    param_groups: Vec<Vec<u16>>,
+    // CSI private marker (`?`, `>`, `<` or `=`) and intermediate bytes
+    private_marker: Option<u8>,
+    intermediates: Vec<u8>,
+    /// DECANM reset - escape sequences are read as VT52 until `ESC <`
+    vt52_mode: bool,
 }
 
 impl VtParser {
@@ -69,9 +104,23 @@ impl VtParser {
             state_machine: ParserStateMachine::Ground,
             current_sequence: Vec::new(),
             params: Vec::new(),
+            param_groups: Vec::new(),
+            private_marker: None,
+            intermediates: Vec::new(),
+            vt52_mode: false,
         }
     }
     
+    /// Whether escape sequences are read as VT52
+    pub fn vt52_mode(&self) -> bool {
+        self.vt52_mode
+    }
+    
+    /// Whether the parser is in the ground state, holding no partial sequence
+    pub fn is_idle(&self) -> bool {
+        matches!(self.state_machine, ParserStateMachine::Ground)
+    }
+    
     /// Process a byte and return a complete sequence if ready
     pub fn process_byte(&mut self, byte: u8) -> PaneResult<Option<VtSequence>> {
         match self.state_machine {
@@ -81,6 +130,13 @@ impl VtParser {
             ParserStateMachine::CsiParam => self.process_csi_param_state(byte),
             ParserStateMachine::CsiIntermediate => self.process_csi_intermediate_state(byte),
             ParserStateMachine::OscString => self.process_osc_string_state(byte),
+            ParserStateMachine::DcsString => self.process_dcs_string_state(byte),
+            ParserStateMachine::ApcString => self.process_apc_string_state(byte),
+            ParserStateMachine::StringIgnore => self.process_string_ignore_state(byte),
+            ParserStateMachine::OscStringIgnore => self.process_osc_string_ignore_state(byte),
+            ParserStateMachine::Vt52Escape => self.process_vt52_escape_state(byte),
+            ParserStateMachine::Vt52CursorRow => self.process_vt52_cursor_state(byte, false),
+            ParserStateMachine::Vt52CursorColumn => self.process_vt52_cursor_state(byte, true),
         }
     }
     
@@ -88,7 +144,11 @@ impl VtParser {
         match byte {
             0x1B => {
                 // ESC - start escape sequence
-                self.state_machine = ParserStateMachine::Escape;
+                self.state_machine = if self.vt52_mode {
+                    ParserStateMachine::Vt52Escape
+                } else {
+                    ParserStateMachine::Escape
+                };
                 self.current_sequence.clear();
                 self.current_sequence.push(byte);
                 Ok(None)
@@ -97,6 +157,8 @@ impl VtParser {
             0x09 => Ok(Some(VtSequence::Control(ControlCode::Tab))),
             0x0A => Ok(Some(VtSequence::Control(ControlCode::LineFeed))),
             0x0D => Ok(Some(VtSequence::Control(ControlCode::CarriageReturn))),
+            0x0E => Ok(Some(VtSequence::Control(ControlCode::ShiftOut))),
+            0x0F => Ok(Some(VtSequence::Control(ControlCode::ShiftIn))),
             0x07 => Ok(Some(VtSequence::Control(ControlCode::Bell))),
             0x20..=0x7E => {
                 // Printable ASCII
@@ -117,10 +179,42 @@ impl VtParser {
     fn process_escape_state(&mut self, byte: u8) -> PaneResult<Option<VtSequence>> {
         self.current_sequence.push(byte);
         
+        // ESC # Pn - line size and the screen alignment pattern
+        if self.current_sequence.len() == 3 && self.current_sequence[1] == b'#' {
+            self.state_machine = ParserStateMachine::Ground;
+            let esc = match byte {
+                b'3' => EscapeSequence::LineSize(LineSize::DoubleHeightTop),
+                b'4' => EscapeSequence::LineSize(LineSize::DoubleHeightBottom),
+                b'5' => EscapeSequence::LineSize(LineSize::Single),
+                b'6' => EscapeSequence::LineSize(LineSize::DoubleWidth),
+                b'8' => EscapeSequence::ScreenAlignment,
+                _ => return Ok(None),
+            };
+            return Ok(Some(VtSequence::Escape(esc)));
+        }
+        
+        // ESC ( F / ESC ) F - designate G0 or G1 (SCS)
+        if self.current_sequence.len() == 3 && matches!(self.current_sequence[1], b'(' | b')') {
+            self.state_machine = ParserStateMachine::Ground;
+            let slot = if self.current_sequence[1] == b'(' { CharacterSetSlot::G0 } else { CharacterSetSlot::G1 };
+            let set = match byte {
+                b'B' => CharacterSet::Ascii,
+                b'0' => CharacterSet::DecSpecialCharacter,
+                _ => return Ok(None),
+            };
+            return Ok(Some(VtSequence::Escape(EscapeSequence::Designate(slot, set))));
+        }
+        
         match byte {
+            b'#' | b'(' | b')' if self.current_sequence.len() == 2 => {
+                // Wait for the final byte
+                Ok(None)
+            }
             b'[' => {
                 // CSI sequence
                 self.state_machine = ParserStateMachine::CsiEntry;
+                self.private_marker = None;
+                self.intermediates.clear();
                 Ok(None)
             }
             b']' => {
@@ -128,6 +222,16 @@ impl VtParser {
                 self.state_machine = ParserStateMachine::OscString;
                 Ok(None)
             }
+            b'P' => {
+                // DCS sequence
+                self.state_machine = ParserStateMachine::DcsString;
+                Ok(None)
+            }
+            b'_' => {
+                // APC sequence
+                self.state_machine = ParserStateMachine::ApcString;
+                Ok(None)
+            }
             b'D' => {
                 // Index (move down)
                 self.state_machine = ParserStateMachine::Ground;
@@ -138,6 +242,11 @@ impl VtParser {
                 self.state_machine = ParserStateMachine::Ground;
                 Ok(Some(VtSequence::Escape(EscapeSequence::ReverseIndex)))
             }
+            b'H' => {
+                // HTS - set a tab stop at the cursor column
+                self.state_machine = ParserStateMachine::Ground;
+                Ok(Some(VtSequence::Escape(EscapeSequence::TabSet)))
+            }
             b'c' => {
                 // Reset
                 self.state_machine = ParserStateMachine::Ground;
@@ -153,6 +262,16 @@ impl VtParser {
                 self.state_machine = ParserStateMachine::Ground;
                 Ok(Some(VtSequence::Escape(EscapeSequence::RestoreCursor)))
             }
+            b'=' => {
+                // DECKPAM - application keypad
+                self.state_machine = ParserStateMachine::Ground;
+                Ok(Some(VtSequence::Escape(EscapeSequence::ApplicationKeypad)))
+            }
+            b'>' => {
+                // DECKPNM - numeric keypad
+                self.state_machine = ParserStateMachine::Ground;
+                Ok(Some(VtSequence::Escape(EscapeSequence::NumericKeypad)))
+            }
             _ => {
                 // Unknown escape sequence - return to ground state
                 self.state_machine = ParserStateMachine::Ground;
@@ -161,21 +280,81 @@ impl VtParser {
         }
     }
     
+    fn process_vt52_escape_state(&mut self, byte: u8) -> PaneResult<Option<VtSequence>> {
+        self.state_machine = ParserStateMachine::Ground;
+        
+        let sequence = match byte {
+            b'A' => VtSequence::Csi(CsiCommand::CursorUp(1)),
+            b'B' => VtSequence::Csi(CsiCommand::CursorDown(1)),
+            b'C' => VtSequence::Csi(CsiCommand::CursorForward(1)),
+            b'D' => VtSequence::Csi(CsiCommand::CursorBack(1)),
+            // Graphics mode uses the DEC special graphics set
+            b'F' => VtSequence::Escape(EscapeSequence::Designate(CharacterSetSlot::G0, CharacterSet::DecSpecialCharacter)),
+            b'G' => VtSequence::Escape(EscapeSequence::Designate(CharacterSetSlot::G0, CharacterSet::Ascii)),
+            b'H' => VtSequence::Csi(CsiCommand::CursorPosition(1, 1)),
+            b'I' => VtSequence::Escape(EscapeSequence::ReverseIndex),
+            b'J' => VtSequence::Csi(CsiCommand::ClearScreen(ClearType::ToEnd)),
+            b'K' => VtSequence::Csi(CsiCommand::ClearLine(ClearType::ToEnd)),
+            b'Y' => {
+                // Direct cursor address: row and column follow, offset by 32
+                self.params.clear();
+                self.state_machine = ParserStateMachine::Vt52CursorRow;
+                return Ok(None);
+            }
+            b'Z' => VtSequence::Escape(EscapeSequence::Vt52Identify),
+            b'=' => VtSequence::Escape(EscapeSequence::ApplicationKeypad),
+            b'>' => VtSequence::Escape(EscapeSequence::NumericKeypad),
+            b'<' => {
+                // Back to ANSI mode, as DECANM set
+                self.vt52_mode = false;
+                VtSequence::Csi(CsiCommand::DecPrivateModeSet(vec![2]))
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(sequence))
+    }
+    
+    fn process_vt52_cursor_state(&mut self, byte: u8, column: bool) -> PaneResult<Option<VtSequence>> {
+        self.params.push(byte.saturating_sub(0x20) as i32);
+        if !column {
+            self.state_machine = ParserStateMachine::Vt52CursorColumn;
+            return Ok(None);
+        }
+        
+        self.state_machine = ParserStateMachine::Ground;
+        let row = self.params[0] as u16 + 1;
+        let col = self.params[1] as u16 + 1;
+        Ok(Some(VtSequence::Csi(CsiCommand::CursorPosition(row, col))))
+    }
+    
     fn process_csi_entry_state(&mut self, byte: u8) -> PaneResult<Option<VtSequence>> {
+        match byte {
+            b'<'..=b'?' => {
+                // Private marker
+                self.current_sequence.push(byte);
+                self.private_marker = Some(byte);
+                self.state_machine = ParserStateMachine::CsiParam;
+                Ok(None)
+            }
+            _ => self.process_csi_param_state(byte),
+        }
+    }
+    
+    fn process_csi_param_state(&mut self, byte: u8) -> PaneResult<Option<VtSequence>> {
         self.current_sequence.push(byte);
         
         match byte {
-            b'0'..=b'9' | b';' => {
+            b'0'..=b'9' | b';' | b':' => {
                 // Parameter bytes
                 self.state_machine = ParserStateMachine::CsiParam;
-                self.parse_parameters()
+                Ok(None)
             }
-            b'A'..=b'Z' | b'a'..=b'z' => {
-                // Final byte
-                let sequence = self.build_csi_sequence(byte)?;
-                self.state_machine = ParserStateMachine::Ground;
-                Ok(Some(sequence))
+            0x20..=0x2F => {
+                self.intermediates.push(byte);
+                self.state_machine = ParserStateMachine::CsiIntermediate;
+                Ok(None)
             }
+            0x40..=0x7E => self.finish_csi_sequence(byte),
             _ => {
                 // Invalid - return to ground
                 self.state_machine = ParserStateMachine::Ground;
@@ -184,78 +363,232 @@ impl VtParser {
         }
     }
     
-    fn process_csi_param_state(&mut self, byte: u8) -> PaneResult<Option<VtSequence>> {
+    fn process_csi_intermediate_state(&mut self, byte: u8) -> PaneResult<Option<VtSequence>> {
         self.current_sequence.push(byte);
         
         match byte {
-            b'0'..=b'9' | b';' => {
-                // More parameter bytes
-                self.parse_parameters()
-            }
-            b'A'..=b'Z' | b'a'..=b'z' => {
-                // Final byte
-                let sequence = self.build_csi_sequence(byte)?;
-                self.state_machine = ParserStateMachine::Ground;
-                Ok(Some(sequence))
+            0x20..=0x2F => {
+                self.intermediates.push(byte);
+                Ok(None)
             }
+            0x40..=0x7E => self.finish_csi_sequence(byte),
             _ => {
-                // Invalid - return to ground
+                // Parameters after intermediates are invalid
                 self.state_machine = ParserStateMachine::Ground;
                 Ok(None)
             }
         }
     }
     
-    fn process_csi_intermediate_state(&mut self, byte: u8) -> PaneResult<Option<VtSequence>> {
-        // TODO: Handle intermediate bytes
-        self.current_sequence.push(byte);
+    fn finish_csi_sequence(&mut self, final_byte: u8) -> PaneResult<Option<VtSequence>> {
         self.state_machine = ParserStateMachine::Ground;
-        Ok(None)
+        self.parse_parameters()?;
+        let sequence = self.build_csi_sequence(final_byte)?;
+        
+        // DECANM reset switches the parser into VT52 mode
+        if let VtSequence::Csi(CsiCommand::DecPrivateModeReset(modes)) = &sequence {
+            if modes.contains(&2) {
+                self.vt52_mode = true;
+            }
+        }
+        Ok(Some(sequence))
     }
     
     fn process_osc_string_state(&mut self, byte: u8) -> PaneResult<Option<VtSequence>> {
+        // OSC sequences end with ST (String Terminator) or BEL
+        if byte == 0x07 {
+            self.current_sequence.push(byte);
+            self.state_machine = ParserStateMachine::Ground;
+            return Ok(Some(VtSequence::Osc(self.build_osc_command())));
+        }
+        
+        if self.push_string_byte(byte) {
+            return Ok(Some(VtSequence::Osc(self.build_osc_command())));
+        }
+        if let ParserStateMachine::StringIgnore = self.state_machine {
+            self.state_machine = ParserStateMachine::OscStringIgnore;
+        }
+        Ok(None)
+    }
+    
+    fn process_dcs_string_state(&mut self, byte: u8) -> PaneResult<Option<VtSequence>> {
+        if self.push_string_byte(byte) {
+            return Ok(Some(VtSequence::Dcs(self.build_dcs_command())));
+        }
+        Ok(None)
+    }
+    
+    fn process_apc_string_state(&mut self, byte: u8) -> PaneResult<Option<VtSequence>> {
+        if self.push_string_byte(byte) {
+            return Ok(Some(VtSequence::Apc(self.build_apc_command())));
+        }
+        Ok(None)
+    }
+    
+    /// Buffer a byte of an ST-terminated string (OSC, DCS, APC); returns true once complete
+    fn push_string_byte(&mut self, byte: u8) -> bool {
         self.current_sequence.push(byte);
         
-        // OSC sequences end with ST (String Terminator) or BEL
-        if byte == 0x07 || (self.current_sequence.len() >= 2 && 
-                            self.current_sequence[self.current_sequence.len()-2] == 0x1B && 
-                            byte == b'\\') {
-            // End of OSC sequence
+        if is_string_terminator(&self.current_sequence) {
             self.state_machine = ParserStateMachine::Ground;
-            // TODO: Parse OSC sequence
-            Ok(None)
-        } else {
-            Ok(None)
+            return true;
         }
+        
+        if self.current_sequence.len() > MAX_STRING_SEQUENCE_LENGTH {
+            // Drop oversized payloads but keep consuming until ST
+            self.current_sequence.clear();
+            self.current_sequence.push(byte);
+            self.state_machine = ParserStateMachine::StringIgnore;
+        }
+        false
     }
     
-    fn parse_parameters(&mut self) -> PaneResult<Option<VtSequence>> {
-        // Extract parameter string (everything after CSI '[' until now)
-        if self.current_sequence.len() < 3 {
+    fn process_string_ignore_state(&mut self, byte: u8) -> PaneResult<Option<VtSequence>> {
+        let previous = self.current_sequence.last().copied();
+        self.current_sequence.clear();
+        self.current_sequence.push(byte);
+        
+        if previous == Some(0x1B) && byte == b'\\' {
+            self.state_machine = ParserStateMachine::Ground;
+        }
+        Ok(None)
+    }
+    
+    /// Like `StringIgnore`, but BEL also ends the dropped OSC
+    fn process_osc_string_ignore_state(&mut self, byte: u8) -> PaneResult<Option<VtSequence>> {
+        if byte == 0x07 {
+            self.current_sequence.clear();
+            self.state_machine = ParserStateMachine::Ground;
             return Ok(None);
         }
+        self.process_string_ignore_state(byte)
+    }
+    
+    fn build_apc_command(&self) -> ApcCommand {
+        // Strip the leading ESC _ and the ESC \ terminator
+        let end = self.current_sequence.len().saturating_sub(2).max(2);
+        let payload = &self.current_sequence[2..end];
+        
+        match payload.split_first() {
+            Some((b'G', control)) => match KittyGraphicsCommand::parse(control) {
+                Some(command) => ApcCommand::KittyGraphics(command),
+                None => ApcCommand::Unknown(payload.to_vec()),
+            },
+            _ => ApcCommand::Unknown(payload.to_vec()),
+        }
+    }
+    
+    fn build_dcs_command(&self) -> DcsCommand {
+        // Strip the leading ESC P and the ESC \ terminator
+        let end = self.current_sequence.len().saturating_sub(2).max(2);
+        let payload = &self.current_sequence[2..end];
+        
+        // Header: parameters, intermediates, then the final byte
+        let mut idx = 0;
+        while idx < payload.len() && matches!(payload[idx], b'0'..=b'9' | b';') {
+            idx += 1;
+        }
+        let params: Vec<i32> = if idx == 0 {
+            Vec::new()
+        } else {
+            String::from_utf8_lossy(&payload[..idx])
+                .split(';')
+                .map(|param| param.parse().unwrap_or(0))
+                .collect()
+        };
+        
+        let intermediates_start = idx;
+        while idx < payload.len() && (0x20..=0x2F).contains(&payload[idx]) {
+            idx += 1;
+        }
+        let intermediates = payload[intermediates_start..idx].to_vec();
+        
+        let Some(&final_byte) = payload.get(idx) else {
+            return DcsCommand::Unknown { params, intermediates, final_byte: 0, data: Vec::new() };
+        };
+        let data = payload[idx + 1..].to_vec();
         
-        let param_start = 2; // After ESC and '['
-        let param_end = self.current_sequence.len() - 1; // Before final byte
+        match (final_byte, intermediates.as_slice()) {
+            (b'q', []) => DcsCommand::Sixel { params, data },
+            (b'q', [b'$']) => DcsCommand::RequestStatusString(data),
+            (b'q', [b'+']) => DcsCommand::RequestTermcap(data),
+            _ => DcsCommand::Unknown { params, intermediates, final_byte, data },
+        }
+    }
+    
+    fn build_osc_command(&self) -> OscCommand {
+        // Strip the leading ESC ] and the BEL or ESC \ terminator
+        let terminator_len = if self.current_sequence.last() == Some(&0x07) { 1 } else { 2 };
+        let end = self.current_sequence.len().saturating_sub(terminator_len).max(2);
+        let payload = &self.current_sequence[2..end];
+        
+        let text = String::from_utf8_lossy(payload);
+        let (command, rest) = text.split_once(';').unwrap_or((&text, ""));
+        
+        match command {
+            "133" => match SemanticMark::parse(rest) {
+                Some(mark) => OscCommand::SemanticPrompt(mark),
+                None => OscCommand::Unknown(payload.to_vec()),
+            },
+            "9" | "777" | "99" => {
+                let notification = match command {
+                    "9" => DesktopNotification::parse_osc9(rest),
+                    "777" => DesktopNotification::parse_osc777(rest),
+                    _ => DesktopNotification::parse_osc99(rest),
+                };
+                match notification {
+                    Some(notification) => OscCommand::Notify(notification),
+                    None => OscCommand::Unknown(payload.to_vec()),
+                }
+            }
+            "1337" => match Iterm2Command::parse(rest) {
+                Some(command) => OscCommand::Iterm2(command),
+                None => OscCommand::Unknown(payload.to_vec()),
+            },
+            "12" if rest == "?" => OscCommand::QueryCursorColor,
+            "12" => match parse_xterm_color(rest) {
+                Some(color) => OscCommand::SetCursorColor(color),
+                None => OscCommand::Unknown(payload.to_vec()),
+            },
+            "112" => OscCommand::ResetCursorColor,
+            _ => OscCommand::Unknown(payload.to_vec()),
+        }
+    }
+    
+    fn parse_parameters(&mut self) -> PaneResult<()> {
+        // Extract parameter string (everything after CSI '[' and any private marker)
+        let param_start = 2 + self.private_marker.is_some() as usize;
+        let param_end = self.current_sequence.len() - 1 - self.intermediates.len(); // Before intermediates and final byte
         let param_str = std::str::from_utf8(&self.current_sequence[param_start..param_end])
             .map_err(|_| PaneError::ParseError("Invalid UTF-8 in CSI parameters".to_string()))?;
         
         self.params.clear();
+        self.param_groups.clear();
         if !param_str.is_empty() {
             for param in param_str.split(';') {
-                if param.is_empty() {
+                // Empty values default to 0; values too large for u16 saturate
+                let group: Vec<u16> = param
+                    .split(':')
+                    .map(|value| if value.is_empty() { 0 } else { value.parse().unwrap_or(u16::MAX) })
+                    .collect();
+                let first = param.split(':').next().unwrap_or("");
+                if first.is_empty() {
                     self.params.push(0); // Default parameter
                 } else {
-                    self.params.push(param.parse().unwrap_or(0));
+                    self.params.push(first.parse().unwrap_or(0));
                 }
+                self.param_groups.push(group);
             }
         }
         
-        Ok(None)
+        Ok(())
     }
     
     fn build_csi_sequence(&self, final_byte: u8) -> PaneResult<VtSequence> {
         let params = self.params.clone();
+        if self.private_marker.is_some() || !self.intermediates.is_empty() {
+            return Ok(VtSequence::Csi(self.build_private_csi_command(final_byte, params)));
+        }
         
         let csi_command = match final_byte {
             b'A' => CsiCommand::CursorUp(params.first().copied().unwrap_or(1) as u16),
@@ -267,34 +600,149 @@ impl VtParser {
                 let col = params.get(1).copied().unwrap_or(1) as u16;
                 CsiCommand::CursorPosition(row, col)
             }
-            b'J' => {
-                let param = params.first().copied().unwrap_or(0);
-                let clear_type = match param {
-                    0 => ClearType::ToEnd,
-                    1 => ClearType::ToBeginning,
-                    2 => ClearType::All,
-                    _ => ClearType::All,
-                };
-                CsiCommand::ClearScreen(clear_type)
-            }
-            b'K' => {
-                let param = params.first().copied().unwrap_or(0);
-                let clear_type = match param {
-                    0 => ClearType::ToEnd,
-                    1 => ClearType::ToBeginning,
-                    2 => ClearType::All,
-                    _ => ClearType::All,
-                };
-                CsiCommand::ClearLine(clear_type)
-            }
-            b'm' => CsiCommand::SetGraphicsRendition(params.iter().map(|&p| p as u8).collect()),
+            b'J' => CsiCommand::ClearScreen(ClearType::from_param(params.first().copied().unwrap_or(0))),
+            b'K' => CsiCommand::ClearLine(ClearType::from_param(params.first().copied().unwrap_or(0))),
+            b'm' => CsiCommand::SetGraphicsRendition(self.param_groups.clone()),
             b'L' => CsiCommand::InsertLines(params.first().copied().unwrap_or(1) as u16),
             b'M' => CsiCommand::DeleteLines(params.first().copied().unwrap_or(1) as u16),
-            _ => CsiCommand::Unknown(final_byte, params),
+            b'@' => CsiCommand::InsertChars(params.first().copied().unwrap_or(1) as u16),
+            b'P' => CsiCommand::DeleteChars(params.first().copied().unwrap_or(1) as u16),
+            b'S' => CsiCommand::ScrollUp(params.first().copied().unwrap_or(1) as u16),
+            b'T' => CsiCommand::ScrollDown(params.first().copied().unwrap_or(1) as u16),
+            b'r' => {
+                let top = params.first().copied().unwrap_or(0) as u16;
+                let bottom = params.get(1).copied().unwrap_or(0) as u16;
+                CsiCommand::SetScrollMargins(top, bottom)
+            }
+            // DECSLRM, or SCOSC when left/right margin mode is off
+            b's' => {
+                let left = params.first().copied().unwrap_or(0) as u16;
+                let right = params.get(1).copied().unwrap_or(0) as u16;
+                CsiCommand::SetLeftRightMargins(left, right)
+            }
+            // TBC
+            b'g' => match params.first().copied().unwrap_or(0) {
+                0 => CsiCommand::ClearTabStop,
+                3 => CsiCommand::ClearAllTabStops,
+                _ => self.unknown_csi_command(final_byte, params),
+            },
+            // XTWINOPS
+            b't' => match WindowOperation::from_params(&params) {
+                Some(operation) => CsiCommand::WindowOperation(operation),
+                None => self.unknown_csi_command(final_byte, params),
+            },
+            // SCORC
+            b'u' => CsiCommand::RestoreCursor,
+            // DA1
+            b'c' if params.first().copied().unwrap_or(0) == 0 => CsiCommand::PrimaryDeviceAttributes,
+            _ => self.unknown_csi_command(final_byte, params),
         };
         
         Ok(VtSequence::Csi(csi_command))
     }
+    
+    /// Build a CSI command that carries a private marker or intermediate bytes
+    fn build_private_csi_command(&self, final_byte: u8, params: Vec<i32>) -> CsiCommand {
+        let first = params.first().copied().unwrap_or(0);
+        
+        match (self.private_marker, self.intermediates.as_slice(), final_byte) {
+            // Kitty keyboard protocol
+            (Some(b'?'), [], b'u') => CsiCommand::QueryKeyboardFlags,
+            (Some(b'>'), [], b'u') => CsiCommand::PushKeyboardFlags(KeyboardFlags::from_bits(first)),
+            (Some(b'<'), [], b'u') => CsiCommand::PopKeyboardFlags(first.clamp(1, u16::MAX as i32) as u16),
+            (Some(b'='), [], b'u') => {
+                let mode = KeyboardFlagsMode::from_param(params.get(1).copied().unwrap_or(1));
+                CsiCommand::SetKeyboardFlags(KeyboardFlags::from_bits(first), mode)
+            }
+            // DEC private modes
+            (Some(b'?'), [], b'h') => CsiCommand::DecPrivateModeSet(params.iter().map(|&p| p as u16).collect()),
+            (Some(b'?'), [], b'l') => CsiCommand::DecPrivateModeReset(params.iter().map(|&p| p as u16).collect()),
+            
+            // DECRQM for DEC private modes
+            (Some(b'?'), [b'$'], b'p') => CsiCommand::RequestPrivateMode(first as u16),
+            
+            // DECSCUSR - cursor shape
+            (None, [b' '], b'q') => CsiCommand::SetCursorStyle(first as u16),
+            
+            // DECSCA - character protection
+            (None, [b'"'], b'q') => CsiCommand::SetCharacterProtection(first as u16),
+            
+            // DECSED/DECSEL - selective erase
+            (Some(b'?'), [], b'J') => CsiCommand::SelectiveClearScreen(ClearType::from_param(first)),
+            (Some(b'?'), [], b'K') => CsiCommand::SelectiveClearLine(ClearType::from_param(first)),
+            
+            // VT400 rectangular area operations
+            (None, [b'$'], b'v') => CsiCommand::CopyRectangle(params),
+            (None, [b'$'], b'x') => CsiCommand::FillRectangle(params),
+            (None, [b'$'], b'z') => CsiCommand::EraseRectangle(params),
+            (None, [b'$'], b'{') => CsiCommand::SelectiveEraseRectangle(params),
+            (None, [b'$'], b'r') => CsiCommand::ChangeRectangleAttributes(params),
+            (None, [b'$'], b't') => CsiCommand::ReverseRectangleAttributes(params),
+            (None, [b'*'], b'x') => CsiCommand::SetAttributeChangeExtent(first as u16),
+            (None, [b'*'], b'y') => CsiCommand::RequestRectangleChecksum(params),
+            
+            // XTMODKEYS - only the modifyOtherKeys resource is supported
+            (Some(b'>'), [], b'm') if first == 4 => {
+                CsiCommand::SetModifyOtherKeys(params.get(1).copied().unwrap_or(0).clamp(0, 2) as u8)
+            }
+            _ => self.unknown_csi_command(final_byte, params),
+        }
+    }
+    
+    fn unknown_csi_command(&self, final_byte: u8, params: Vec<i32>) -> CsiCommand {
+        CsiCommand::Unknown {
+            private_marker: self.private_marker,
+            intermediates: self.intermediates.clone(),
+            final_byte,
+            params,
+        }
+    }
+}
+
+/// Parse an X11 color specification (`rgb:r/g/b` or `#rgb`) as used by xterm's color OSCs
+pub fn parse_xterm_color(spec: &str) -> Option<Color> {
+    // Scale 1-4 hex digits to 8 bits
+    fn component(digits: &str, scale: bool) -> Option<u8> {
+        if digits.is_empty() || digits.len() > 4 {
+            return None;
+        }
+        let value = u32::from_str_radix(digits, 16).ok()?;
+        let max = (1u32 << (4 * digits.len())) - 1;
+        let scaled = if scale {
+            value * 255 / max // `rgb:` values are fractions of the maximum
+        } else {
+            (value << 8 >> (4 * digits.len())) & 0xff // `#` values are the high-order bits
+        };
+        Some(scaled as u8)
+    }
+    
+    if let Some(rgb) = spec.strip_prefix("rgb:") {
+        let mut parts = rgb.split('/');
+        let (r, g, b) = (parts.next()?, parts.next()?, parts.next()?);
+        if parts.next().is_some() {
+            return None;
+        }
+        return Some(Color::from_rgb(component(r, true)?, component(g, true)?, component(b, true)?));
+    }
+    
+    let hex = spec.strip_prefix('#')?;
+    if hex.is_empty() || hex.len() % 3 != 0 || !hex.is_ascii() {
+        return None;
+    }
+    let n = hex.len() / 3;
+    Some(Color::from_rgb(
+        component(&hex[..n], false)?,
+        component(&hex[n..2 * n], false)?,
+        component(&hex[2 * n..], false)?,
+    ))
+}
+
+/// Format a color the way xterm answers color queries (`rgb:rrrr/gggg/bbbb`)
+pub fn xterm_color_spec(color: Color) -> String {
+    format!(
+        "rgb:{:02x}{:02x}/{:02x}{:02x}/{:02x}{:02x}",
+        color.r, color.r, color.g, color.g, color.b, color.b
+    )
 }
 
 /// Parser state machine states
@@ -306,6 +754,21 @@ enum ParserStateMachine {
     CsiParam,
     CsiIntermediate,
     OscString,
+    DcsString,
+    ApcString,
+    StringIgnore,
+    OscStringIgnore,
+    Vt52Escape,
+    Vt52CursorRow,
+    Vt52CursorColumn,
+}
+
+/// Upper bound on a buffered OSC/DCS/APC payload (large enough for big images)
+pub(crate) const MAX_STRING_SEQUENCE_LENGTH: usize = 16 * 1024 * 1024;
+
+/// Check whether a buffered sequence ends with ST (ESC \)
+fn is_string_terminator(sequence: &[u8]) -> bool {
+    sequence.len() >= 2 && sequence[sequence.len() - 2] == 0x1B && sequence[sequence.len() - 1] == b'\\'
 }
 
 /// Complete VT sequence types
@@ -316,6 +779,8 @@ pub enum VtSequence {
     Escape(EscapeSequence),
     Csi(CsiCommand),
     Osc(OscCommand),
+    Dcs(DcsCommand),
+    Apc(ApcCommand),
 }
 
 #[derive(Debug, Clone)]
@@ -325,6 +790,10 @@ pub enum ControlCode {
     Tab,
     LineFeed,
     CarriageReturn,
+    /// SO - invoke G1
+    ShiftOut,
+    /// SI - invoke G0
+    ShiftIn,
 }
 
 #[derive(Debug, Clone)]
@@ -334,6 +803,18 @@ pub enum EscapeSequence {
     Reset,
     SaveCursor,
     RestoreCursor,
+    ApplicationKeypad,
+    NumericKeypad,
+    /// DECSWL, DECDWL and DECDHL
+    LineSize(LineSize),
+    /// DECALN
+    ScreenAlignment,
+    /// Designate a character set into G0 or G1
+    Designate(CharacterSetSlot, CharacterSet),
+    /// HTS
+    TabSet,
+    /// VT52 identify (`ESC Z`)
+    Vt52Identify,
 }
 
 #[derive(Debug, Clone)]
@@ -347,16 +828,104 @@ pub enum CsiCommand {
     ClearLine(ClearType),
     InsertLines(u16),
     DeleteLines(u16),
-    SetGraphicsRendition(Vec<u8>),
-    Unknown(u8, Vec<i32>),
+    InsertChars(u16),
+    DeleteChars(u16),
+    ScrollUp(u16),
+    ScrollDown(u16),
+    SetScrollMargins(u16, u16),
+    SetLeftRightMargins(u16, u16),
+    RestoreCursor,
+    /// Parameters with their sub-parameters
+    SetGraphicsRendition(Vec<Vec<u16>>),
+    QueryKeyboardFlags,
+    PushKeyboardFlags(KeyboardFlags),
+    PopKeyboardFlags(u16),
+    SetKeyboardFlags(KeyboardFlags, KeyboardFlagsMode),
+    DecPrivateModeSet(Vec<u16>),
+    DecPrivateModeReset(Vec<u16>),
+    RequestPrivateMode(u16),
+    SetModifyOtherKeys(u8),
+    SetCursorStyle(u16),
+    SetCharacterProtection(u16),
+    SelectiveClearScreen(ClearType),
+    SelectiveClearLine(ClearType),
+    CopyRectangle(Vec<i32>),
+    FillRectangle(Vec<i32>),
+    EraseRectangle(Vec<i32>),
+    SelectiveEraseRectangle(Vec<i32>),
+    ChangeRectangleAttributes(Vec<i32>),
+    ReverseRectangleAttributes(Vec<i32>),
+    SetAttributeChangeExtent(u16),
+    RequestRectangleChecksum(Vec<i32>),
+    WindowOperation(WindowOperation),
+    PrimaryDeviceAttributes,
+    /// TBC 0 - clear the tab stop at the cursor column
+    ClearTabStop,
+    /// TBC 3
+    ClearAllTabStops,
+    Unknown { private_marker: Option<u8>, intermediates: Vec<u8>, final_byte: u8, params: Vec<i32> },
 }
 
 #[derive(Debug, Clone)]
 pub enum OscCommand {
     SetTitle(String),
+    SemanticPrompt(SemanticMark),
+    Notify(DesktopNotification),
+    Iterm2(Iterm2Command),
+    SetCursorColor(Color),
+    QueryCursorColor,
+    ResetCursorColor,
     Unknown(Vec<u8>),
 }
 
+#[derive(Debug, Clone)]
+pub enum ApcCommand {
+    KittyGraphics(KittyGraphicsCommand),
+    Unknown(Vec<u8>),
+}
+
+#[derive(Debug, Clone)]
+pub enum DcsCommand {
+    Sixel { params: Vec<i32>, data: Vec<u8> },
+    /// DECRQSS - `DCS $ q Pt ST`
+    RequestStatusString(Vec<u8>),
+    /// XTGETTCAP - `DCS + q Pt ST` with hex-encoded capability names
+    RequestTermcap(Vec<u8>),
+    Unknown { params: Vec<i32>, intermediates: Vec<u8>, final_byte: u8, data: Vec<u8> },
+}
+
+/// A sequence ratty does not recognize, offered to registered `VtCommandHandler`s
+#[derive(Debug, Clone, PartialEq)]
+pub enum CustomSequence {
+    Csi { private_marker: Option<u8>, intermediates: Vec<u8>, final_byte: u8, params: Vec<i32> },
+    /// OSC payload between `ESC ]` and the terminator
+    Osc(Vec<u8>),
+    Dcs { params: Vec<i32>, intermediates: Vec<u8>, final_byte: u8, data: Vec<u8> },
+    /// APC payload between `ESC _` and ST
+    Apc(Vec<u8>),
+}
+
+impl CustomSequence {
+    /// The numeric command of an OSC sequence (`7777` in `OSC 7777 ; ... ST`)
+    pub fn osc_command(&self) -> Option<&[u8]> {
+        match self {
+            CustomSequence::Osc(payload) => payload.split(|&b| b == b';').next(),
+            _ => None,
+        }
+    }
+    
+    /// The text after the OSC command and its `;`
+    pub fn osc_argument(&self) -> Option<&[u8]> {
+        match self {
+            CustomSequence::Osc(payload) => {
+                let start = payload.iter().position(|&b| b == b';')? + 1;
+                Some(&payload[start..])
+            }
+            _ => None,
+        }
+    }
+}
+
 /// VT command interpreter
 pub struct VtInterpreter {
     handler_map: HashMap<String, Box<dyn VtCommandHandler>>,
@@ -377,6 +946,34 @@ impl VtInterpreter {
         }
     }
     
+    /// Register a handler for unrecognized sequences, returning any it replaces
+    pub fn register_handler(&mut self, name: String, handler: Box<dyn VtCommandHandler>) -> Option<Box<dyn VtCommandHandler>> {
+        self.handler_map.insert(name, handler)
+    }
+    
+    /// Remove a handler by name
+    pub fn unregister_handler(&mut self, name: &str) -> Option<Box<dyn VtCommandHandler>> {
+        self.handler_map.remove(name)
+    }
+    
+    /// Take the handlers out while they run against the pane
+    pub(crate) fn take_handlers(&mut self) -> HashMap<String, Box<dyn VtCommandHandler>> {
+        std::mem::take(&mut self.handler_map)
+    }
+    
+    pub(crate) fn restore_handlers(&mut self, handlers: HashMap<String, Box<dyn VtCommandHandler>>) {
+        self.handler_map = handlers;
+    }
+    
+    /// A command for an unrecognized sequence if some handler claims it
+    fn custom(&self, sequence: CustomSequence) -> PaneResult<Option<VtCommand>> {
+        if self.handler_map.values().any(|handler| handler.can_handle(&sequence)) {
+            Ok(Some(VtCommand::Custom(sequence)))
+        } else {
+            Ok(None)
+        }
+    }
+    
     /// Interpret a VT sequence into a command
     pub fn interpret(&self, sequence: VtSequence) -> PaneResult<Option<VtCommand>> {
         match sequence {
@@ -385,6 +982,8 @@ impl VtInterpreter {
             VtSequence::Escape(esc) => self.interpret_escape(esc),
             VtSequence::Csi(csi) => self.interpret_csi(csi),
             VtSequence::Osc(osc) => self.interpret_osc(osc),
+            VtSequence::Dcs(dcs) => self.interpret_dcs(dcs),
+            VtSequence::Apc(apc) => self.interpret_apc(apc),
         }
     }
     
@@ -395,17 +994,27 @@ impl VtInterpreter {
             ControlCode::Tab => VtCommand::Tab,
             ControlCode::LineFeed => VtCommand::LineFeed,
             ControlCode::CarriageReturn => VtCommand::CarriageReturn,
+            ControlCode::ShiftOut => VtCommand::InvokeCharacterSet(CharacterSetSlot::G1),
+            ControlCode::ShiftIn => VtCommand::InvokeCharacterSet(CharacterSetSlot::G0),
         };
         Ok(Some(command))
     }
     
     fn interpret_escape(&self, esc: EscapeSequence) -> PaneResult<Option<VtCommand>> {
         let command = match esc {
-            EscapeSequence::Index => VtCommand::CursorDown(1),
-            EscapeSequence::ReverseIndex => VtCommand::CursorUp(1),
+            EscapeSequence::Index => VtCommand::LineFeed,
+            EscapeSequence::ReverseIndex => VtCommand::ReverseIndex,
             EscapeSequence::Reset => VtCommand::Reset,
             EscapeSequence::SaveCursor => VtCommand::SaveCursor,
             EscapeSequence::RestoreCursor => VtCommand::RestoreCursor,
+            // DECKPAM/DECKPNM behave like DECNKM (mode 66)
+            EscapeSequence::ApplicationKeypad => VtCommand::DecPrivateModeSet(vec![66]),
+            EscapeSequence::NumericKeypad => VtCommand::DecPrivateModeReset(vec![66]),
+            EscapeSequence::LineSize(size) => VtCommand::SetLineSize(size),
+            EscapeSequence::ScreenAlignment => VtCommand::ScreenAlignmentTest,
+            EscapeSequence::Designate(slot, set) => VtCommand::DesignateCharacterSet(slot, set),
+            EscapeSequence::TabSet => VtCommand::SetTabStop,
+            EscapeSequence::Vt52Identify => VtCommand::IdentifyVt52,
         };
         Ok(Some(command))
     }
@@ -421,15 +1030,78 @@ impl VtInterpreter {
             CsiCommand::ClearLine(clear_type) => VtCommand::ClearLine(clear_type),
             CsiCommand::InsertLines(n) => VtCommand::InsertLines(n),
             CsiCommand::DeleteLines(n) => VtCommand::DeleteLines(n),
+            CsiCommand::InsertChars(n) => VtCommand::InsertChars(n),
+            CsiCommand::DeleteChars(n) => VtCommand::DeleteChars(n),
+            CsiCommand::ScrollUp(n) => VtCommand::ScrollUp(n),
+            CsiCommand::ScrollDown(n) => VtCommand::ScrollDown(n),
+            CsiCommand::SetScrollMargins(top, bottom) => VtCommand::SetScrollMargins(top, bottom),
+            CsiCommand::SetLeftRightMargins(left, right) => VtCommand::SetLeftRightMargins(left, right),
+            CsiCommand::RestoreCursor => VtCommand::RestoreCursor,
             CsiCommand::SetGraphicsRendition(params) => VtCommand::SetGraphicsRendition(params),
-            CsiCommand::Unknown(_, _) => return Ok(None), // Skip unknown commands
+            CsiCommand::QueryKeyboardFlags => VtCommand::QueryKeyboardFlags,
+            CsiCommand::PushKeyboardFlags(flags) => VtCommand::PushKeyboardFlags(flags),
+            CsiCommand::PopKeyboardFlags(count) => VtCommand::PopKeyboardFlags(count),
+            CsiCommand::SetKeyboardFlags(flags, mode) => VtCommand::SetKeyboardFlags(flags, mode),
+            CsiCommand::DecPrivateModeSet(modes) => VtCommand::DecPrivateModeSet(modes),
+            CsiCommand::DecPrivateModeReset(modes) => VtCommand::DecPrivateModeReset(modes),
+            CsiCommand::RequestPrivateMode(mode) => VtCommand::RequestPrivateMode(mode),
+            CsiCommand::SetModifyOtherKeys(level) => VtCommand::SetModifyOtherKeys(level),
+            CsiCommand::SetCursorStyle(style) => VtCommand::SetCursorStyle(style),
+            CsiCommand::SetCharacterProtection(mode) => VtCommand::SetCharacterProtection(mode),
+            CsiCommand::SelectiveClearScreen(clear_type) => VtCommand::SelectiveClearScreen(clear_type),
+            CsiCommand::SelectiveClearLine(clear_type) => VtCommand::SelectiveClearLine(clear_type),
+            CsiCommand::CopyRectangle(params) => VtCommand::CopyRectangle(params),
+            CsiCommand::FillRectangle(params) => VtCommand::FillRectangle(params),
+            CsiCommand::EraseRectangle(params) => VtCommand::EraseRectangle(params),
+            CsiCommand::SelectiveEraseRectangle(params) => VtCommand::SelectiveEraseRectangle(params),
+            CsiCommand::ChangeRectangleAttributes(params) => VtCommand::ChangeRectangleAttributes(params),
+            CsiCommand::ReverseRectangleAttributes(params) => VtCommand::ReverseRectangleAttributes(params),
+            CsiCommand::SetAttributeChangeExtent(mode) => VtCommand::SetAttributeChangeExtent(mode),
+            CsiCommand::RequestRectangleChecksum(params) => VtCommand::RequestRectangleChecksum(params),
+            CsiCommand::WindowOperation(operation) => VtCommand::WindowOperation(operation),
+            CsiCommand::PrimaryDeviceAttributes => VtCommand::PrimaryDeviceAttributes,
+            CsiCommand::ClearTabStop => VtCommand::ClearTabStop,
+            CsiCommand::ClearAllTabStops => VtCommand::ClearAllTabStops,
+            CsiCommand::Unknown { private_marker, intermediates, final_byte, params } => {
+                return self.custom(CustomSequence::Csi { private_marker, intermediates, final_byte, params });
+            }
         };
         Ok(Some(command))
     }
     
-    fn interpret_osc(&self, _osc: OscCommand) -> PaneResult<Option<VtCommand>> {
-        // TODO: Implement OSC command interpretation
-        Ok(None)
+    fn interpret_osc(&self, osc: OscCommand) -> PaneResult<Option<VtCommand>> {
+        let command = match osc {
+            OscCommand::SemanticPrompt(mark) => VtCommand::SemanticPrompt(mark),
+            OscCommand::Notify(notification) => VtCommand::Notify(notification),
+            OscCommand::Iterm2(command) => VtCommand::Iterm2(command),
+            OscCommand::SetCursorColor(color) => VtCommand::SetCursorColor(color),
+            OscCommand::QueryCursorColor => VtCommand::QueryCursorColor,
+            OscCommand::ResetCursorColor => VtCommand::ResetCursorColor,
+            OscCommand::Unknown(payload) => return self.custom(CustomSequence::Osc(payload)),
+            // TODO: Implement remaining OSC commands
+            _ => return Ok(None),
+        };
+        Ok(Some(command))
+    }
+    
+    fn interpret_dcs(&self, dcs: DcsCommand) -> PaneResult<Option<VtCommand>> {
+        let command = match dcs {
+            DcsCommand::Sixel { params, data } => VtCommand::Sixel { params, data },
+            DcsCommand::RequestStatusString(request) => VtCommand::RequestStatusString(request),
+            DcsCommand::RequestTermcap(request) => VtCommand::RequestTermcap(request),
+            DcsCommand::Unknown { params, intermediates, final_byte, data } => {
+                return self.custom(CustomSequence::Dcs { params, intermediates, final_byte, data });
+            }
+        };
+        Ok(Some(command))
+    }
+    
+    fn interpret_apc(&self, apc: ApcCommand) -> PaneResult<Option<VtCommand>> {
+        let command = match apc {
+            ApcCommand::KittyGraphics(graphics) => VtCommand::KittyGraphics(graphics),
+            ApcCommand::Unknown(payload) => return self.custom(CustomSequence::Apc(payload)),
+        };
+        Ok(Some(command))
     }
 }
 
@@ -452,15 +1124,50 @@ pub enum VtCommand {
     CursorPosition(u16, u16),
     SaveCursor,
     RestoreCursor,
+    SetCursorStyle(u16),
+    SetCursorColor(Color),
+    QueryCursorColor,
+    ResetCursorColor,
     
     // Screen manipulation
     ClearScreen(ClearType),
     ClearLine(ClearType),
+    SelectiveClearScreen(ClearType),
+    SelectiveClearLine(ClearType),
     InsertLines(u16),
     DeleteLines(u16),
+    InsertChars(u16),
+    DeleteChars(u16),
+    ReverseIndex,
+    SetLineSize(LineSize),
+    ScreenAlignmentTest,
+    DesignateCharacterSet(CharacterSetSlot, CharacterSet),
+    InvokeCharacterSet(CharacterSetSlot),
+    
+    // Tab stops
+    SetTabStop,
+    ClearTabStop,
+    ClearAllTabStops,
+    
+    // Rectangular areas, with parameters as received
+    CopyRectangle(Vec<i32>),
+    FillRectangle(Vec<i32>),
+    EraseRectangle(Vec<i32>),
+    SelectiveEraseRectangle(Vec<i32>),
+    ChangeRectangleAttributes(Vec<i32>),
+    ReverseRectangleAttributes(Vec<i32>),
+    SetAttributeChangeExtent(u16),
+    RequestRectangleChecksum(Vec<i32>),
+    
+    // Scrolling region
+    SetScrollMargins(u16, u16),
+    SetLeftRightMargins(u16, u16),
+    ScrollUp(u16),
+    ScrollDown(u16),
     
     // Attributes
-    SetGraphicsRendition(Vec<u8>),
+    SetGraphicsRendition(Vec<Vec<u16>>),
+    SetCharacterProtection(u16),
     
     // Terminal control
     Reset,
@@ -468,11 +1175,36 @@ pub enum VtCommand {
     // Mode changes
     SetMode(Vec<u16>),
     ResetMode(Vec<u16>),
-    DecPrivateModeSet(u16),
-    DecPrivateModeReset(u16),
+    DecPrivateModeSet(Vec<u16>),
+    DecPrivateModeReset(Vec<u16>),
+    RequestPrivateMode(u16),
+    SetModifyOtherKeys(u8),
+    
+    // Kitty keyboard protocol
+    QueryKeyboardFlags,
+    PushKeyboardFlags(KeyboardFlags),
+    PopKeyboardFlags(u16),
+    SetKeyboardFlags(KeyboardFlags, KeyboardFlagsMode),
     
     // Advanced features
     DeviceStatusReport,
+    PrimaryDeviceAttributes,
+    IdentifyVt52,
+    RequestStatusString(Vec<u8>),
+    RequestTermcap(Vec<u8>),
+    WindowOperation(WindowOperation),
+    
+    // Shell integration
+    SemanticPrompt(SemanticMark),
+    Notify(DesktopNotification),
+    Iterm2(Iterm2Command),
+    
+    // Graphics
+    Sixel { params: Vec<i32>, data: Vec<u8> },
+    KittyGraphics(KittyGraphicsCommand),
+    
+    // Extensions
+    Custom(CustomSequence),
 }
 
 /// Terminal operating mode
@@ -483,8 +1215,9 @@ pub enum TerminalMode {
     ApplicationCursor,
 }
 
-/// VT command handler trait
+/// Handler for sequences ratty does not recognize, such as private OSC extensions
 pub trait VtCommandHandler: Send + Sync {
-    fn handle(&self, command: VtCommand) -> PaneResult<()>;
-    fn can_handle(&self, command: &VtCommand) -> bool;
+    /// Act on a claimed sequence; errors and panics are counted, not propagated
+    fn handle(&mut self, sequence: &CustomSequence, pane: &mut PaneHandle<'_>) -> PaneResult<()>;
+    fn can_handle(&self, sequence: &CustomSequence) -> bool;
 }
\ No newline at end of file
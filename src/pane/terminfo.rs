@@ -0,0 +1,119 @@
+//! Terminfo source for `TERM=ratty`, generated from the capability table
+//!
+//! `ratty --print-terminfo > ratty.ti && tic -x ratty.ti` installs both entries.
+//! Panes set `TERM=ratty` when the entry is installed and fall back to
+//! `xterm-256color` otherwise, so programs never see a TERM they cannot look up.
+//! The kitty keyboard protocol has no terminfo capability; programs detect it
+//! by sending `CSI ? u`, which the pane answers.
+
+use super::*;
+use std::fmt::Write as _;
+use std::path::PathBuf;
+
+/// A terminfo entry describing ratty
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TerminfoEntry {
+    /// The 256-color palette
+    Ratty,
+    /// 24-bit color, with `setaf`/`setab` taking packed RGB
+    RattyDirect,
+}
+
+impl TerminfoEntry {
+    pub const ALL: [TerminfoEntry; 2] = [TerminfoEntry::Ratty, TerminfoEntry::RattyDirect];
+    
+    pub fn name(self) -> &'static str {
+        match self {
+            TerminfoEntry::Ratty => "ratty",
+            TerminfoEntry::RattyDirect => "ratty-direct",
+        }
+    }
+    
+    fn description(self) -> &'static str {
+        match self {
+            TerminfoEntry::Ratty => "ratty terminal emulator",
+            TerminfoEntry::RattyDirect => "ratty terminal emulator with direct color",
+        }
+    }
+    
+    /// Every capability in the entry, in table order
+    pub fn capabilities(self) -> Vec<Capability> {
+        let overrides: &[Capability] = match self {
+            TerminfoEntry::Ratty => &[],
+            TerminfoEntry::RattyDirect => DIRECT_COLOR_CAPABILITIES,
+        };
+        let mut capabilities: Vec<Capability> = CAPABILITIES
+            .iter()
+            .map(|cap| *overrides.iter().find(|over| over.name == cap.name).unwrap_or(cap))
+            .collect();
+        capabilities.extend(overrides.iter().filter(|over| find_capability(over.name).is_none()));
+        capabilities
+    }
+    
+    /// Terminfo source for this entry
+    pub fn source(self) -> String {
+        let capabilities = self.capabilities();
+        let mut out = format!("{}|{},\n", self.name(), self.description());
+        
+        // Flags and numbers share a line each; strings get one line apiece
+        let flags: Vec<String> = capabilities
+            .iter()
+            .filter(|cap| cap.value == CapabilityValue::Flag)
+            .map(|cap| format!("{},", cap.name))
+            .collect();
+        let numbers: Vec<String> = capabilities
+            .iter()
+            .filter_map(|cap| match cap.value {
+                CapabilityValue::Number(value) => Some(format!("{}#{},", cap.name, value)),
+                _ => None,
+            })
+            .collect();
+        for line in [flags, numbers].iter().filter(|line| !line.is_empty()) {
+            let _ = writeln!(out, "\t{}", line.join(" "));
+        }
+        for cap in &capabilities {
+            if let CapabilityValue::String(value) = cap.value {
+                let _ = writeln!(out, "\t{}={},", cap.name, value.replace(',', "\\,"));
+            }
+        }
+        out
+    }
+}
+
+/// TERM for programs started in a pane: `ratty` if its entry is installed, xterm's otherwise
+pub fn term_name() -> &'static str {
+    if terminfo_installed(TERM_NAME) {
+        TERM_NAME
+    } else {
+        FALLBACK_TERM_NAME
+    }
+}
+
+/// Whether ncurses can find a compiled entry, searching the directories it does
+fn terminfo_installed(name: &str) -> bool {
+    let mut dirs: Vec<PathBuf> = Vec::new();
+    dirs.extend(std::env::var_os("TERMINFO").map(PathBuf::from));
+    dirs.extend(std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".terminfo")));
+    if let Some(list) = std::env::var_os("TERMINFO_DIRS") {
+        dirs.extend(std::env::split_paths(&list).filter(|dir| !dir.as_os_str().is_empty()));
+    }
+    dirs.extend(
+        ["/etc/terminfo", "/lib/terminfo", "/usr/share/terminfo", "/usr/lib/terminfo", "/usr/local/share/terminfo"]
+            .iter()
+            .map(PathBuf::from),
+    );
+    
+    // Entries live under their first letter, or its hex code on case-insensitive filesystems
+    let Some(first) = name.bytes().next() else { return false };
+    let subdirs = [(first as char).to_string(), format!("{:02x}", first)];
+    dirs.iter().any(|dir| subdirs.iter().any(|subdir| dir.join(subdir).join(name).is_file()))
+}
+
+/// Source for every ratty terminfo entry, as printed by `ratty --print-terminfo`
+pub fn terminfo_source() -> String {
+    let mut out = String::from("# ratty terminfo, generated by `ratty --print-terminfo`; compile with `tic -x`\n");
+    for entry in TerminfoEntry::ALL {
+        out.push_str(&entry.source());
+    }
+    out
+}
@@ -1,11 +1,21 @@
 #[cfg(test)]
 mod pane_tests {
     use super::*;
-    use crate::sash::{PaneId, Theme};
+    use crate::sash::{Color, PaneId, Theme};
     use crate::pane::{
         Pane, PaneConfig, ScreenBuffer, Line, CellAttributes, BuiltinProfiles, PaneProfile,
         ScrollbackBuffer, Cursor, CursorStyle, CursorVisibility, Terminal, TerminalMode,
-        VtCommand, PtyFactory, PtyConfig, PaneInterface
+        VtCommand, PtyFactory, PtyConfig, PaneInterface, SemanticMark,
+        DesktopNotification, PaneEvent, PaneEventType, PaneEventListener, PaneResult,
+        SixelDecoder, ImageLayer, RgbaImage, KittyGraphicsCommand, KittyGraphicsAction,
+        KittyImageFormat, InlineImage, ImageDimension, Iterm2Command, KeyboardFlags,
+        KeyboardFlagsMode, KeyboardModeStack, KEYBOARD_STACK_LIMIT, MouseReporting,
+        MouseTracking, MouseEncoding, parse_xterm_color, xterm_color_spec, LineSize,
+        UnderlineType, BlinkType, AttributeChangeExtent, ScrollMargins,
+        StatusSetting, CapabilityValue, CAPABILITIES, find_capability, decode_terminfo_string,
+        WindowOperation, WindowManipulation, CustomSequence, VtCommandHandler, PaneHandle, PaneError, TraceStatus,
+        TraceEntry, TerminfoEntry, terminfo_source, SgrColor, Cell, ScriptPosition, FrameType, VtParser,
+        VtSequence, MAX_STRING_SEQUENCE_LENGTH, term_name
     };
     
     // Helper function to create a test pane
@@ -246,7 +256,7 @@ mod pane_tests {
         assert_eq!(cursor.position.col, 5);
         
         // Test backward movement
-        cursor.move_back(2).expect("Move should succeed");
+        cursor.move_back(2, &screen).expect("Move should succeed");
         assert_eq!(cursor.position.col, 3);
         
         // Test down movement
@@ -341,6 +351,25 @@ mod pane_tests {
         assert!(matches!(command, Some(VtCommand::CarriageReturn)));
     }
     
+    #[test]
+    fn test_terminal_csi_parameters() {
+        let mut terminal = Terminal::new().expect("Terminal creation should succeed");
+        
+        // Every parameter digit counts, including the one before the final byte
+        let commands = terminal.process_bytes(b"\x1b[12A\x1b[3;14H").expect("Processing should succeed");
+        assert!(matches!(commands.as_slice(), [VtCommand::CursorUp(12), VtCommand::CursorPosition(3, 14)]));
+        
+        // Private markers and intermediates select different commands
+        let commands = terminal.process_bytes(b"\x1b[>5u\x1b[=3;2u\x1b[?u\x1b[<u").expect("Processing should succeed");
+        assert_eq!(commands.len(), 4);
+        assert!(matches!(commands[0], VtCommand::PushKeyboardFlags(flags) if flags.bits() == 5));
+        assert!(matches!(commands[1], VtCommand::SetKeyboardFlags(flags, KeyboardFlagsMode::Set) if flags.bits() == 3));
+        assert!(matches!(commands[2], VtCommand::QueryKeyboardFlags));
+        assert!(matches!(commands[3], VtCommand::PopKeyboardFlags(1)));
+        let commands = terminal.process_bytes(b"\x1b[?12A\x1b[2 A").expect("Processing should succeed");
+        assert!(commands.is_empty());
+    }
+    
     // ========== Statistics Tests ==========
     
     #[test]
@@ -451,4 +480,1712 @@ mod pane_tests {
         assert_eq!(profile.tags, vec!["test"]);
         assert_eq!(profile.config.initial_size, (120, 40));
     }
-}
\ No newline at end of file
+    
+    // ========== Shell Integration Tests ==========
+    
+    const PROMPT_SESSION: &[u8] = b"\x1b]133;A\x07$ \x1b]133;B\x07ls\r\n\x1b]133;C\x07file1\r\nfile2\r\n\x1b]133;D;0\x07\x1b]133;A\x07$ ";
+    
+    #[test]
+    fn test_semantic_prompt_parsing() {
+        let mut terminal = Terminal::new().expect("Terminal creation should succeed");
+        
+        let commands = terminal.process_bytes(b"\x1b]133;D;127\x1b\\").expect("Processing should succeed");
+        assert!(matches!(commands.as_slice(), [VtCommand::SemanticPrompt(SemanticMark::CommandFinished(Some(127)))]));
+        
+        let commands = terminal.process_bytes(b"\x1b]133;A;aid=42\x07").expect("Processing should succeed");
+        assert!(matches!(commands.as_slice(), [VtCommand::SemanticPrompt(SemanticMark::PromptStart)]));
+        
+        assert_eq!(SemanticMark::parse("D"), Some(SemanticMark::CommandFinished(None)));
+        assert_eq!(SemanticMark::parse("Z"), None);
+    }
+    
+    #[test]
+    fn test_command_history() {
+        let mut pane = create_test_pane();
+        pane.process_output(PROMPT_SESSION).expect("Processing should succeed");
+        
+        let blocks = pane.command_blocks();
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0].command, "ls");
+        assert_eq!(blocks[0].exit_status, Some(0));
+        assert!(blocks[0].is_finished());
+        assert!(blocks[0].duration().is_some());
+        assert!(!blocks[1].is_finished());
+    }
+    
+    #[test]
+    fn test_prompt_navigation_and_output_selection() {
+        let mut pane = create_test_pane();
+        pane.process_output(PROMPT_SESSION).expect("Processing should succeed");
+        
+        assert_eq!(pane.previous_prompt(3), Some(0));
+        assert_eq!(pane.next_prompt(0), Some(3));
+        assert_eq!(pane.next_prompt(3), None);
+        
+        let selection = pane.select_last_command_output().expect("Output should be selectable");
+        assert_eq!(selection.text, "file1\nfile2");
+        assert_eq!(selection.start.line, 1);
+        assert_eq!(selection.end.line, 3);
+    }
+    
+    #[test]
+    fn test_zone_markers_survive_scrolling() {
+        let config = PaneConfig::default().with_size(20, 3);
+        let mut pane = create_test_pane_with_config(config);
+        pane.process_output(PROMPT_SESSION).expect("Processing should succeed");
+        pane.process_output(b"\r\n\r\n\r\n").expect("Processing should succeed");
+        
+        let scrollback = pane.get_scrollback();
+        assert!(scrollback.len() >= 3);
+        assert!(scrollback.get_line(0).unwrap().has_zone_marker(|mark| mark == SemanticMark::PromptStart));
+        
+        let selection = pane.select_last_command_output().expect("Output should be selectable");
+        assert_eq!(selection.text, "file1\nfile2");
+    }
+    
+    #[test]
+    fn test_iterm2_shell_integration() {
+        assert_eq!(Iterm2Command::parse("SetMark"), Some(Iterm2Command::SetMark));
+        assert_eq!(Iterm2Command::parse("SetUserVar=x=%%%"), None);
+        assert_eq!(Iterm2Command::parse("StealFocus"), None);
+        
+        let mut pane = create_test_pane();
+        pane.process_output(b"\x1b]1337;SetUserVar=branch=bWFpbg==\x07\x1b]1337;CurrentDir=/home/user/src\x07")
+            .expect("Processing should succeed");
+        assert_eq!(pane.user_var("branch"), Some("main"));
+        assert_eq!(pane.working_directory(), Some("/home/user/src"));
+        
+        pane.process_output(b"\x1b]1337;SetMark\x07one\r\ntwo\r\n\x1b]1337;SetMark\x07three")
+            .expect("Processing should succeed");
+        assert_eq!(pane.next_mark(0), Some(2));
+        assert_eq!(pane.previous_mark(2), Some(0));
+        assert_eq!(pane.previous_mark(0), None);
+        
+        let mut pane = create_test_pane_with_config(PaneConfig::default().with_size(20, 2));
+        pane.process_output(b"a\r\nb\r\nc\r\n").expect("Processing should succeed");
+        assert!(pane.get_scrollback().len() > 0);
+        pane.process_output(b"\x1b]1337;ClearScrollback\x07").expect("Processing should succeed");
+        assert_eq!(pane.get_scrollback().len(), 0);
+    }
+    
+    // ========== Notification Tests ==========
+    
+    struct NotificationRecorder {
+        received: std::sync::Arc<std::sync::Mutex<Vec<DesktopNotification>>>,
+    }
+    
+    impl PaneEventListener for NotificationRecorder {
+        fn handle_pane_event(&mut self, event: &PaneEvent) -> PaneResult<()> {
+            if let PaneEvent::NotificationRequested(notification) = event {
+                self.received.lock().unwrap().push(notification.clone());
+            }
+            Ok(())
+        }
+        
+        fn can_handle(&self, event_type: PaneEventType) -> bool {
+            event_type == PaneEventType::NotificationRequested
+        }
+    }
+    
+    #[test]
+    fn test_notification_parsing() {
+        assert_eq!(DesktopNotification::parse_osc9("Build finished"),
+                   Some(DesktopNotification::new("", "Build finished")));
+        assert_eq!(DesktopNotification::parse_osc9("4;1;50"), None); // ConEmu progress
+        
+        assert_eq!(DesktopNotification::parse_osc777("notify;Title;Body; with semicolon"),
+                   Some(DesktopNotification::new("Title", "Body; with semicolon")));
+        assert_eq!(DesktopNotification::parse_osc777("preexec"), None);
+        
+        let kitty = DesktopNotification::parse_osc99("i=1:d=0:e=1;SGVsbG8=").unwrap();
+        assert_eq!(kitty.title, "Hello");
+        assert_eq!(kitty.id.as_deref(), Some("1"));
+        assert!(kitty.partial);
+    }
+    
+    #[test]
+    fn test_notification_events() {
+        let mut pane = create_test_pane();
+        let received = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        pane.register_event_listener(
+            PaneEventType::NotificationRequested,
+            Box::new(NotificationRecorder { received: received.clone() }),
+        );
+        
+        pane.process_output(b"\x1b]9;done\x07").expect("Processing should succeed");
+        pane.process_output(b"\x1b]99;i=7:d=0;Build\x1b\\").expect("Processing should succeed");
+        pane.process_output(b"\x1b]99;i=7:p=body;all green\x1b\\").expect("Processing should succeed");
+        
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 2);
+        assert_eq!(received[0].body, "done");
+        assert_eq!(received[1].title, "Build");
+        assert_eq!(received[1].body, "all green");
+    }
+    
+    // ========== Graphics Tests ==========
+    
+    // 8x12 image: a red band over a half-width green band, transparent background
+    const SIXEL_IMAGE: &[u8] = b"\x1bP0;1;0q\"1;1;8;12#1;2;100;0;0#1!8~-#2;2;0;100;0!4~\x1b\\";
+    
+    #[test]
+    fn test_sixel_decoding() {
+        let image = SixelDecoder::decode(&[0, 1, 0], b"\"1;1;8;12#1;2;100;0;0#1!8~-#2;2;0;100;0!4~")
+            .expect("Sixel data should decode");
+        
+        assert_eq!((image.width, image.height), (8, 12));
+        assert_eq!(image.pixel(7, 5), Some([255, 0, 0, 255]));
+        assert_eq!(image.pixel(3, 6), Some([0, 255, 0, 255]));
+        assert_eq!(image.pixel(4, 6), Some([0, 0, 0, 0]));
+        
+        // Background select 0 fills with palette register 0 instead
+        let opaque = SixelDecoder::decode(&[0, 0, 0], b"#1;2;100;100;100@").unwrap();
+        assert_eq!((opaque.width, opaque.height), (1, 6));
+        assert_eq!(opaque.pixel(0, 0), Some([255, 255, 255, 255]));
+        assert_eq!(opaque.pixel(0, 1), Some([0, 0, 0, 255]));
+        
+        // HLS: hue 120 is red in the sixel colour wheel
+        let hls = SixelDecoder::decode(&[], b"#3;1;120;50;100~").unwrap();
+        assert_eq!(hls.pixel(0, 0), Some([255, 0, 0, 255]));
+    }
+    
+    #[test]
+    fn test_sixel_image_scrolls_into_scrollback() {
+        let mut pane = create_test_pane_with_config(PaneConfig::default().with_size(10, 3));
+        
+        pane.process_output(SIXEL_IMAGE).expect("Processing should succeed");
+        assert_eq!(pane.get_cursor_position(), (0, 1)); // Line below the image
+        
+        let placement = pane.image_layer().placements()[0].clone();
+        assert_eq!((placement.row, placement.col, placement.rows, placement.cols), (0, 0, 1, 1));
+        
+        // Scroll the image off screen; it stays anchored to its line in scrollback
+        pane.process_output(b"\n\n\n\x1b[2J").expect("Processing should succeed");
+        assert_eq!(pane.get_scrollback().len(), 2);
+        assert_eq!(pane.image_layer().placements(), &[placement]);
+        
+        // Clearing scrollback drops it
+        pane.clear_scrollback().unwrap();
+        assert!(pane.image_layer().is_empty());
+    }
+    
+    #[test]
+    fn test_images_follow_scroll_regions() {
+        let mut pane = create_test_pane_with_config(PaneConfig::default().with_size(10, 6));
+        // Placements by screen row, negative in the scrollback
+        let placed = |pane: &Pane| -> Vec<(i64, u16)> {
+            let origin = pane.screen_origin_row() as i64;
+            pane.image_layer().placements().iter().map(|p| (p.row as i64 - origin, p.col)).collect()
+        };
+        
+        // One image above a DECSTBM region, one inside it
+        pane.process_output(b"\x1b[1;1H").unwrap();
+        pane.process_output(SIXEL_IMAGE).unwrap();
+        pane.process_output(b"\x1b[2;5r\x1b[3;1H").unwrap();
+        pane.process_output(SIXEL_IMAGE).unwrap();
+        assert_eq!(placed(&pane), [(0, 0), (2, 0)]);
+        
+        // IL and DL at the cursor, SD and SU, and a line feed on the bottom margin move only the region
+        pane.process_output(b"\x1b[2;1H\x1b[L").unwrap();
+        assert_eq!(placed(&pane), [(0, 0), (3, 0)]);
+        pane.process_output(b"\x1b[2;1H\x1b[2M").unwrap();
+        assert_eq!(placed(&pane), [(0, 0), (1, 0)]);
+        pane.process_output(b"\x1b[2T").unwrap();
+        assert_eq!(placed(&pane), [(0, 0), (3, 0)]);
+        pane.process_output(b"\x1b[S\x1b[5;1H\n").unwrap();
+        assert_eq!(placed(&pane), [(0, 0), (1, 0)]);
+        assert!(pane.get_scrollback().is_empty());
+        
+        // Scrolled past the top margin, the image goes with its text
+        pane.process_output(b"\x1b[S").unwrap();
+        assert_eq!(placed(&pane), [(0, 0)]);
+        assert_eq!(pane.image_layer().image_count(), 1);
+        
+        // A region at the top scrolls into the scrollback; rows below it stay put on screen
+        pane.process_output(b"\x1b[r\x1b[5;1H").unwrap();
+        pane.process_output(SIXEL_IMAGE).unwrap();
+        pane.process_output(b"\x1b[1;3r\x1b[3;1H\n\n").unwrap();
+        assert_eq!(pane.get_scrollback().len(), 2);
+        assert_eq!(placed(&pane), [(-2, 0), (4, 0)]);
+        
+        // Left and right margins leave images outside their columns alone
+        pane.process_output(b"\x1b[r\x1b[?69h\x1b[1;4s\x1b[2;8H").unwrap();
+        pane.process_output(SIXEL_IMAGE).unwrap();
+        pane.process_output(b"\x1b[S").unwrap();
+        assert_eq!(placed(&pane)[2], (1, 7));
+        pane.process_output(b"\x1b[?69l\x1b[S").unwrap();
+        assert_eq!(placed(&pane)[2], (0, 7));
+    }
+    
+    #[test]
+    fn test_images_stay_with_main_screen() {
+        let mut pane = create_test_pane_with_config(PaneConfig::default().with_size(10, 6));
+        let rows = |pane: &Pane| -> Vec<u64> {
+            pane.image_layer().placements().iter().map(|p| p.row - pane.screen_origin_row()).collect()
+        };
+        
+        pane.process_output(b"\x1b[2;1H").unwrap();
+        pane.process_output(SIXEL_IMAGE).unwrap();
+        assert_eq!(rows(&pane), [1]);
+        
+        // The alternate screen hides main screen images without dropping them
+        pane.process_output(b"\x1b[?1049h").unwrap();
+        assert!(rows(&pane).is_empty());
+        assert_eq!(pane.image_layer().image_count(), 1);
+        pane.process_output(b"\x1b[4;1H").unwrap();
+        pane.process_output(SIXEL_IMAGE).unwrap();
+        pane.process_output(b"\x1b[2J").unwrap();
+        pane.process_output(SIXEL_IMAGE).unwrap();
+        assert_eq!(pane.image_layer().image_count(), 2);
+        
+        // Leaving it brings them back and discards the alternate screen's
+        pane.process_output(b"\x1b[?1049l").unwrap();
+        assert_eq!(rows(&pane), [1]);
+        assert_eq!(pane.image_layer().image_count(), 1);
+        pane.process_output(b"\x1b[2J").unwrap();
+        assert!(pane.image_layer().is_empty());
+    }
+    
+    #[test]
+    fn test_sixel_image_cleared_by_erase() {
+        let mut pane = create_test_pane_with_config(PaneConfig::default().with_size(10, 5));
+        
+        pane.process_output(SIXEL_IMAGE).expect("Processing should succeed");
+        pane.process_output(b"\x1b[1;5H\x1b[K").expect("Processing should succeed");
+        assert_eq!(pane.image_layer().image_count(), 1); // Erased cells are right of the image
+        
+        pane.process_output(b"\x1b[1;1H\x1b[K").expect("Processing should succeed");
+        assert!(pane.image_layer().is_empty());
+        assert_eq!(pane.image_layer().memory_usage(), 0);
+    }
+    
+    #[test]
+    fn test_image_memory_limit() {
+        let mut layer = ImageLayer::new();
+        let first = layer.add_image(RgbaImage::new(4, 4), Some(128)).unwrap();
+        let second = layer.add_image(RgbaImage::new(4, 4), Some(128)).unwrap();
+        
+        // A third image evicts the oldest
+        layer.add_image(RgbaImage::new(4, 4), Some(128)).unwrap();
+        assert!(layer.image(first).is_none());
+        assert!(layer.image(second).is_some());
+        assert_eq!(layer.memory_usage(), 128);
+        
+        assert!(layer.add_image(RgbaImage::new(8, 8), Some(128)).is_err());
+        
+        // The pane budget comes from max_memory_usage
+        let mut pane = create_test_pane_with_config(PaneConfig::default().with_memory_limit(1));
+        pane.process_output(SIXEL_IMAGE).expect("Rejected images must not fail processing");
+        assert!(pane.image_layer().is_empty());
+        assert_eq!(pane.get_statistics().unhandled_sequences, 1);
+    }
+    
+    #[test]
+    fn test_kitty_graphics_parsing() {
+        let command = KittyGraphicsCommand::parse(b"a=T,f=100,i=5,p=2,c=4,r=2,z=-1,X=3,q=1;iVBO").unwrap();
+        assert_eq!(command.action, KittyGraphicsAction::TransmitAndPut);
+        assert_eq!(command.format, KittyImageFormat::Png);
+        assert_eq!((command.image_id, command.placement_id), (5, 2));
+        assert_eq!((command.columns, command.rows, command.z_index), (4, 2, -1));
+        assert_eq!(command.cell_offset, (3, 0));
+        assert_eq!(command.quiet, 1);
+        assert_eq!(command.payload, b"iVBO");
+        
+        // PNG payloads are decoded to RGBA
+        let mut png = Vec::new();
+        image::RgbaImage::from_pixel(3, 2, image::Rgba([1, 2, 3, 4]))
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+        use base64::Engine;
+        let command = KittyGraphicsCommand {
+            format: KittyImageFormat::Png,
+            payload: base64::engine::general_purpose::STANDARD.encode(&png).into_bytes(),
+            ..Default::default()
+        };
+        let decoded = command.decode_image().expect("PNG should decode");
+        assert_eq!((decoded.width, decoded.height), (3, 2));
+        assert_eq!(decoded.pixel(2, 1), Some([1, 2, 3, 4]));
+    }
+    
+    #[test]
+    fn test_kitty_graphics_chunked_transmit_and_put() {
+        let mut pane = create_test_pane();
+        
+        // A 2x2 RGB image split across two chunks
+        pane.process_output(b"\x1b_Ga=T,f=24,s=2,v=2,i=7,z=-1,m=1;/wAAAP8A\x1b\\").unwrap();
+        assert!(pane.image_layer().is_empty());
+        pane.process_output(b"\x1b_Gm=0;AAD/////\x1b\\").unwrap();
+        
+        assert_eq!(pane.take_responses(), b"\x1b_Gi=7;OK\x1b\\");
+        
+        let layer = pane.image_layer();
+        let placement = &layer.placements()[0];
+        assert_eq!((placement.rows, placement.cols, placement.z_index), (1, 1, -1));
+        let image = layer.image(placement.image_id).unwrap();
+        assert_eq!(image.pixel(0, 0), Some([255, 0, 0, 255]));
+        assert_eq!(image.pixel(1, 1), Some([255, 255, 255, 255]));
+        drop(layer);
+        
+        // The cursor moves past the right edge of the image
+        assert_eq!(pane.get_cursor_position(), (1, 0));
+    }
+    
+    #[test]
+    fn test_kitty_graphics_responses() {
+        let mut pane = create_test_pane();
+        
+        pane.process_output(b"\x1b_Ga=p,i=99\x1b\\").unwrap();
+        assert_eq!(pane.take_responses(), b"\x1b_Gi=99;ENOENT:no such image\x1b\\");
+        
+        // Queries validate without storing
+        pane.process_output(b"\x1b_Ga=q,i=31,s=1,v=1,f=24;AAAA\x1b\\").unwrap();
+        assert_eq!(pane.take_responses(), b"\x1b_Gi=31;OK\x1b\\");
+        assert!(pane.image_layer().is_empty());
+        
+        // q=1 hides OK, q=2 hides errors, no id means no reply at all
+        pane.process_output(b"\x1b_Ga=q,i=31,s=1,v=1,f=24,q=1;AAAA\x1b\\").unwrap();
+        pane.process_output(b"\x1b_Ga=p,i=99,q=2\x1b\\").unwrap();
+        pane.process_output(b"\x1b_Ga=p\x1b\\").unwrap();
+        assert!(pane.take_responses().is_empty());
+        
+        pane.process_output(b"\x1b_Ga=t,i=3,t=f;L3RtcC9pbWc=\x1b\\").unwrap();
+        assert!(pane.take_responses().starts_with(b"\x1b_Gi=3;ENOTSUP:"));
+        
+        // Transmissions over the pane's memory limit are refused
+        let mut pane = create_test_pane_with_config(PaneConfig::default().with_memory_limit(1));
+        pane.process_output(b"\x1b_Ga=t,i=4,s=1,v=1,f=24;AAAA\x1b\\").unwrap();
+        assert!(pane.take_responses().starts_with(b"\x1b_Gi=4;EFBIG:"));
+        
+        // Without a limit, chunks still stop growing at one unchunked payload's size
+        let mut pane = create_test_pane_with_config(PaneConfig { max_memory_usage: None, ..PaneConfig::default() });
+        let chunk = || KittyGraphicsCommand {
+            action: KittyGraphicsAction::Transmit,
+            image_id: 5,
+            more_chunks: true,
+            payload: vec![b'A'; 12 * 1024 * 1024],
+            ..Default::default()
+        };
+        pane.handle_kitty_graphics(chunk());
+        assert!(pane.take_responses().is_empty());
+        pane.handle_kitty_graphics(chunk());
+        assert!(pane.take_responses().starts_with(b"\x1b_Gi=5;EFBIG:"));
+    }
+    
+    #[test]
+    fn test_kitty_graphics_extreme_placement_keys() {
+        let mut pane = create_test_pane();
+        
+        // Offsets must stay inside the first cell
+        pane.process_output(b"\x1b_Ga=T,i=1,s=1,v=1,f=24,X=4294967295,Y=4294967295;AAAA\x1b\\").unwrap();
+        assert!(pane.take_responses().starts_with(b"\x1b_Gi=1;EINVAL:"));
+        assert!(pane.image_layer().placements().is_empty());
+        
+        // Oversized extents saturate rather than wrapping to zero
+        pane.process_output(b"\x1b_Ga=T,i=2,s=1,v=1,f=24,X=7,Y=15,c=65536,r=1;AAAA\x1b\\").unwrap();
+        assert_eq!(pane.take_responses(), b"\x1b_Gi=2;OK\x1b\\");
+        let layer = pane.image_layer();
+        let placement = &layer.placements()[0];
+        assert_eq!((placement.rows, placement.cols, placement.pixel_offset), (1, u16::MAX, (7, 15)));
+    }
+    
+    #[test]
+    fn test_kitty_graphics_delete() {
+        let mut pane = create_test_pane();
+        pane.process_output(b"\x1b_Ga=t,i=7,s=1,v=1,f=24,q=2;AAAA\x1b\\").unwrap();
+        pane.process_output(b"\x1b_Ga=p,i=7,p=1,q=2\x1b\\\x1b_Ga=p,i=7,p=2,C=1,q=2\x1b\\").unwrap();
+        assert_eq!(pane.image_layer().placements().len(), 2);
+        
+        // Lowercase deletes placements but keeps the image data
+        pane.process_output(b"\x1b_Ga=d,d=i,i=7,p=1\x1b\\").unwrap();
+        assert_eq!(pane.image_layer().placements().len(), 1);
+        pane.process_output(b"\x1b_Ga=d,d=a\x1b\\").unwrap();
+        assert!(pane.image_layer().placements().is_empty());
+        assert_eq!(pane.image_layer().image_count(), 1);
+        
+        // Uppercase frees it
+        pane.process_output(b"\x1b_Ga=d,d=I,i=7\x1b\\").unwrap();
+        assert!(pane.image_layer().is_empty());
+        pane.process_output(b"\x1b_Ga=p,i=7\x1b\\").unwrap();
+        assert!(pane.take_responses().ends_with(b"ENOENT:no such image\x1b\\"));
+    }
+    
+    fn encode_image(image: image::DynamicImage, format: image::ImageFormat) -> Vec<u8> {
+        let mut data = Vec::new();
+        image.write_to(&mut std::io::Cursor::new(&mut data), format).expect("Encoding should succeed");
+        data
+    }
+    
+    #[test]
+    fn test_iterm2_inline_image() {
+        use base64::Engine;
+        let base64 = |data: &[u8]| base64::engine::general_purpose::STANDARD.encode(data);
+        let png = encode_image(
+            image::RgbaImage::from_pixel(16, 32, image::Rgba([0, 0, 255, 255])).into(),
+            image::ImageFormat::Png,
+        );
+        
+        let mut pane = create_test_pane();
+        let sequence = format!("\x1b]1337;File=name={};size={};inline=1:{}\x07", base64(b"chart.png"), png.len(), base64(&png));
+        pane.process_output(sequence.as_bytes()).unwrap();
+        
+        let layer = pane.image_layer();
+        let placement = &layer.placements()[0];
+        assert_eq!((placement.row, placement.col, placement.rows, placement.cols), (0, 0, 2, 2));
+        assert_eq!(layer.image(placement.image_id).unwrap().pixel(15, 31), Some([0, 0, 255, 255]));
+        drop(layer);
+        assert_eq!(pane.get_cursor_position(), (2, 1));
+        
+        // Downloads are not displayed
+        let sequence = format!("\x1b]1337;File=inline=0:{}\x07", base64(&png));
+        pane.process_output(sequence.as_bytes()).unwrap();
+        assert_eq!(pane.image_layer().placements().len(), 1);
+        assert_eq!(pane.get_statistics().unhandled_sequences, 1);
+    }
+    
+    #[test]
+    fn test_iterm2_image_sizing() {
+        assert_eq!(ImageDimension::parse("12"), Some(ImageDimension::Cells(12)));
+        assert_eq!(ImageDimension::parse("300px"), Some(ImageDimension::Pixels(300)));
+        assert_eq!(ImageDimension::parse("50%"), Some(ImageDimension::Percent(50)));
+        assert_eq!(ImageDimension::parse("wide"), None);
+        
+        // An 80x24 pane with 8x16 cells showing a 16x32 image
+        let pane = create_test_pane();
+        let extent = |args: &str| {
+            let request = InlineImage::parse(&format!("inline=1;{}:", args)).unwrap();
+            pane.inline_image_extent(&request, 16, 32)
+        };
+        assert_eq!(extent(""), (2, 2));
+        assert_eq!(extent("width=4"), (4, 4));
+        assert_eq!(extent("height=48px"), (3, 3));
+        assert_eq!(extent("width=auto;height=25%"), (6, 6));
+        assert_eq!(extent("width=50%"), (40, 40));
+        
+        // Both given: fit inside the box, or stretch to it
+        assert_eq!(extent("width=10;height=2"), (2, 2));
+        assert_eq!(extent("width=10;height=2;preserveAspectRatio=0"), (10, 2));
+    }
+    
+    #[test]
+    fn test_inline_image_formats() {
+        let pixels = image::RgbImage::from_pixel(5, 3, image::Rgb([200, 200, 200]));
+        for format in [image::ImageFormat::Png, image::ImageFormat::Jpeg, image::ImageFormat::Gif] {
+            let data = encode_image(pixels.clone().into(), format);
+            let decoded = RgbaImage::decode(&data).expect("Image should decode");
+            assert_eq!((decoded.width, decoded.height), (5, 3));
+            assert_eq!(decoded.pixel(4, 2), Some([200, 200, 200, 255]));
+        }
+        
+        assert!(RgbaImage::decode(b"not an image").is_err());
+    }
+    
+    // ========== Keyboard Protocol Tests ==========
+    
+    #[test]
+    fn test_keyboard_mode_stack() {
+        let mut stack = KeyboardModeStack::new();
+        assert_eq!(stack.current(), KeyboardFlags::NONE);
+        
+        stack.push(KeyboardFlags::DISAMBIGUATE);
+        stack.set(KeyboardFlags::REPORT_EVENT_TYPES, KeyboardFlagsMode::Set);
+        assert_eq!(stack.current().bits(), 0b11);
+        stack.set(KeyboardFlags::DISAMBIGUATE, KeyboardFlagsMode::Clear);
+        assert_eq!(stack.current(), KeyboardFlags::REPORT_EVENT_TYPES);
+        
+        // The alternate screen keeps its own stack
+        stack.set_alternate_screen(true);
+        assert_eq!(stack.current(), KeyboardFlags::NONE);
+        stack.push(KeyboardFlags::REPORT_ALL_KEYS);
+        stack.set_alternate_screen(false);
+        assert_eq!(stack.current(), KeyboardFlags::REPORT_EVENT_TYPES);
+        
+        // Popping past the bottom restores legacy encoding
+        stack.pop(10);
+        assert_eq!(stack.current(), KeyboardFlags::NONE);
+        
+        for bits in 0..40 {
+            stack.push(KeyboardFlags::from_bits(bits));
+        }
+        stack.pop(KEYBOARD_STACK_LIMIT - 1);
+        assert_eq!(stack.current(), KeyboardFlags::from_bits(40 - KEYBOARD_STACK_LIMIT as i32));
+        stack.pop(1);
+        assert!(stack.current().is_empty());
+    }
+    
+    #[test]
+    fn test_kitty_keyboard_requests() {
+        let mut pane = create_test_pane();
+        
+        pane.process_output(b"\x1b[>1u\x1b[?u").unwrap();
+        assert_eq!(pane.keyboard_flags(), KeyboardFlags::DISAMBIGUATE);
+        assert_eq!(pane.take_responses(), b"\x1b[?1u");
+        
+        pane.process_output(b"\x1b[>31u\x1b[=4;3u").unwrap();
+        assert_eq!(pane.keyboard_flags().bits(), 27);
+        
+        pane.process_output(b"\x1b[<u\x1b[?u").unwrap();
+        assert_eq!(pane.take_responses(), b"\x1b[?1u");
+        pane.process_output(b"\x1b[<u").unwrap();
+        assert!(pane.keyboard_flags().is_empty());
+        assert_eq!(pane.get_statistics().unhandled_sequences, 0);
+    }
+    
+    #[test]
+    fn test_key_encoding_modes() {
+        let mut pane = create_test_pane();
+        assert!(!pane.get_terminal_modes().application_cursor);
+        
+        // DECCKM, DECKPAM and modifyOtherKeys
+        pane.process_output(b"\x1b[?1h\x1b=\x1b[>4;2m").unwrap();
+        let modes = pane.get_terminal_modes();
+        assert!(modes.application_cursor);
+        assert!(modes.application_keypad);
+        assert_eq!(modes.modify_other_keys, 2);
+        
+        pane.process_output(b"\x1b[?1l\x1b>\x1b[>4m").unwrap();
+        let modes = pane.get_terminal_modes();
+        assert!(!modes.application_cursor);
+        assert!(!modes.application_keypad);
+        assert_eq!(modes.modify_other_keys, 0);
+        
+        // DECNKM is the mode form of DECKPAM
+        pane.process_output(b"\x1b[?66h").unwrap();
+        assert!(pane.get_terminal_modes().application_keypad);
+        assert_eq!(pane.get_statistics().unhandled_sequences, 0);
+        
+        // Unsupported private modes are counted
+        pane.process_output(b"\x1b[?12345h").unwrap();
+        assert_eq!(pane.get_statistics().unhandled_sequences, 1);
+    }
+
+    
+    #[test]
+    fn test_synchronized_output() {
+        let mut pane = create_test_pane();
+        pane.screen_buffer.clear_dirty();
+        
+        // DECRQM reports the mode as supported and reset
+        pane.process_output(b"\x1b[?2026$p").unwrap();
+        assert_eq!(pane.take_responses(), b"\x1b[?2026;2$y");
+        
+        // Output inside the batch is held back from rendering
+        pane.process_output(b"\x1b[?2026hredraw").unwrap();
+        assert!(pane.is_synchronized_update());
+        assert!(!pane.needs_render());
+        pane.process_output(b"\x1b[?2026$p").unwrap();
+        assert_eq!(pane.take_responses(), b"\x1b[?2026;1$y");
+        
+        pane.process_output(b"\x1b[?2026l").unwrap();
+        assert!(!pane.is_synchronized_update());
+        assert!(pane.needs_render());
+        
+        // Unknown modes are reported as not recognized
+        pane.process_output(b"\x1b[?4242$p").unwrap();
+        assert_eq!(pane.take_responses(), b"\x1b[?4242;0$y");
+    }
+    
+    #[test]
+    fn test_synchronized_output_timeout() {
+        let mut config = PaneConfig::default();
+        config.synchronized_update_timeout_ms = 0;
+        let mut pane = create_test_pane_with_config(config);
+        
+        // A program that never ends its batch cannot freeze the display
+        pane.process_output(b"\x1b[?2026hstuck").unwrap();
+        assert!(!pane.is_synchronized_update());
+        assert!(pane.needs_render());
+    }
+    
+    #[test]
+    fn test_mouse_tracking_modes() {
+        let mut pane = create_test_pane();
+        assert_eq!(pane.get_terminal_modes().mouse, MouseReporting::default());
+        
+        pane.process_output(b"\x1b[?1002;1006;1007h").unwrap();
+        let mouse = pane.get_terminal_modes().mouse;
+        assert_eq!(mouse.tracking, MouseTracking::ButtonEvent);
+        assert_eq!(mouse.encoding, MouseEncoding::Sgr);
+        assert!(mouse.alternate_scroll);
+        assert_eq!(pane.get_statistics().unhandled_sequences, 0);
+        
+        // The last tracking mode set wins, and DECRQM reports it
+        pane.process_output(b"\x1b[?1003h\x1b[?1003$p\x1b[?1002$p\x1b[?1006$p").unwrap();
+        assert_eq!(pane.take_responses(), b"\x1b[?1003;1$y\x1b[?1002;2$y\x1b[?1006;1$y");
+        
+        // Resetting another encoding leaves the current one alone
+        pane.process_output(b"\x1b[?1015l").unwrap();
+        assert_eq!(pane.get_terminal_modes().mouse.encoding, MouseEncoding::Sgr);
+        
+        // Resetting any tracking mode turns tracking off
+        pane.process_output(b"\x1b[?1000l\x1b[?1006l").unwrap();
+        let mouse = pane.get_terminal_modes().mouse;
+        assert_eq!(mouse.tracking, MouseTracking::Off);
+        assert_eq!(mouse.encoding, MouseEncoding::X10);
+    }
+    
+    #[test]
+    fn test_alternate_screen() {
+        let mut pane = create_test_pane();
+        pane.process_output(b"main\x1b[2;3H").unwrap();
+        
+        pane.process_output(b"\x1b[?1049h").unwrap();
+        assert!(pane.get_terminal_modes().alternate_screen);
+        assert!(!pane.screen_buffer.get_line(0).unwrap().text().starts_with("main"));
+        
+        // Scrolling on the alternate screen does not feed the scrollback
+        let scrollback = pane.get_scrollback().len();
+        let (_, height) = pane.size();
+        for _ in 0..height * 2 {
+            pane.process_output(b"alt\r\n").unwrap();
+        }
+        assert_eq!(pane.get_scrollback().len(), scrollback);
+        
+        pane.process_output(b"\x1b[?1049$p").unwrap();
+        assert_eq!(pane.take_responses(), b"\x1b[?1049;1$y");
+        
+        // Leaving restores the main screen and the saved cursor
+        pane.process_output(b"\x1b[?1049l").unwrap();
+        assert!(!pane.get_terminal_modes().alternate_screen);
+        assert!(pane.screen_buffer.get_line(0).unwrap().text().starts_with("main"));
+        assert_eq!(pane.get_cursor_position(), (2, 1));
+    }
+    
+    #[test]
+    fn test_focus_reporting_mode() {
+        let mut pane = create_test_pane();
+        
+        // Nothing is reported until the program asks
+        pane.set_focused(true).unwrap();
+        assert!(pane.take_responses().is_empty());
+        
+        pane.process_output(b"\x1b[?1004h\x1b[?1004$p").unwrap();
+        assert_eq!(pane.take_responses(), b"\x1b[?1004;1$y");
+        
+        pane.set_focused(false).unwrap();
+        pane.set_focused(false).unwrap();
+        pane.set_focused(true).unwrap();
+        assert_eq!(pane.take_responses(), b"\x1b[O\x1b[I");
+    }
+    
+    #[test]
+    fn test_cursor_shape_control() {
+        let mut pane = create_test_pane();
+        
+        // DECSCUSR: odd parameters blink, even ones are steady
+        pane.process_output(b"\x1b[5 q").unwrap();
+        assert_eq!(pane.cursor.style, CursorStyle::Bar);
+        assert_eq!(pane.cursor.visibility, CursorVisibility::BlinkingBar);
+        pane.process_output(b"\x1b[4 q").unwrap();
+        assert_eq!(pane.cursor.style, CursorStyle::Underline);
+        assert_eq!(pane.cursor.visibility, CursorVisibility::Visible);
+        
+        // DECRQSS reports the shape
+        pane.process_output(b"\x1bP$q q\x1b\\").unwrap();
+        assert_eq!(pane.take_responses(), b"\x1bP1$r4 q\x1b\\");
+        pane.process_output(b"\x1bP$qzz\x1b\\").unwrap();
+        assert_eq!(pane.take_responses(), b"\x1bP0$r\x1b\\");
+        
+        // 0 restores the configured shape
+        pane.process_output(b"\x1b[0 q").unwrap();
+        assert_eq!(pane.cursor.style, CursorStyle::Block);
+        assert_eq!(pane.cursor.visibility, CursorVisibility::BlinkingBlock);
+        
+        // A hidden cursor changes shape without reappearing
+        pane.cursor.set_visibility(CursorVisibility::Hidden);
+        pane.process_output(b"\x1b[2 q").unwrap();
+        assert_eq!(pane.cursor.visibility, CursorVisibility::Hidden);
+        
+        pane.process_output(b"\x1b[9 q").unwrap();
+        assert_eq!(pane.get_statistics().unhandled_sequences, 1);
+    }
+    
+    #[test]
+    fn test_cursor_color_osc() {
+        let mut pane = create_test_pane();
+        let orange = Color::from_rgb(255, 128, 0);
+        
+        pane.process_output(b"\x1b]12;#ff8000\x07").unwrap();
+        assert_eq!(pane.cursor.color, Some(orange));
+        pane.process_output(b"\x1b]12;?\x07").unwrap();
+        assert_eq!(pane.take_responses(), b"\x1b]12;rgb:ffff/8080/0000\x1b\\");
+        
+        pane.process_output(b"\x1b]112\x07").unwrap();
+        assert_eq!(pane.cursor.color, None);
+        pane.process_output(b"\x1b]12;?\x1b\\").unwrap();
+        let expected = format!("\x1b]12;{}\x1b\\", xterm_color_spec(Theme::default().colors.cursor));
+        assert_eq!(pane.take_responses(), expected.as_bytes());
+        
+        assert_eq!(parse_xterm_color("rgb:f/80/0000"), Some(orange));
+        assert_eq!(parse_xterm_color("rgb:ffff/0/8"), Some(Color::from_rgb(255, 0, 136)));
+        assert_eq!(parse_xterm_color("#fff"), Some(Color::from_rgb(240, 240, 240)));
+        assert_eq!(parse_xterm_color("#123456789abc"), Some(Color::from_rgb(0x12, 0x56, 0x9a)));
+        for invalid in ["rgb:1/2", "rgb:1/2/3/4", "#12", "red", ""] {
+            assert_eq!(parse_xterm_color(invalid), None, "{}", invalid);
+        }
+    }
+    
+    #[test]
+    fn test_oversized_osc_is_dropped() {
+        // A title past the string limit is discarded, whichever terminator ends it
+        for terminator in [&b"\x07"[..], b"\x1b\\"] {
+            let mut parser = VtParser::new();
+            for &byte in b"\x1b]2;".iter().chain(std::iter::repeat_n(&b'A', MAX_STRING_SEQUENCE_LENGTH + 1)) {
+                assert!(parser.process_byte(byte).unwrap().is_none());
+            }
+            for &byte in terminator {
+                assert!(parser.process_byte(byte).unwrap().is_none());
+            }
+            assert!(parser.is_idle());
+            assert!(matches!(parser.process_byte(b'x').unwrap(), Some(VtSequence::Character('x'))));
+        }
+    }
+    
+    #[test]
+    fn test_deferred_wrap_and_autowrap() {
+        let mut pane = create_test_pane();
+        let (width, _) = pane.size();
+        let full_row = vec![b'a'; width as usize];
+        
+        // A full row leaves the cursor in the last column until the next character
+        pane.process_output(&full_row).unwrap();
+        assert_eq!(pane.get_cursor_position(), (width - 1, 0));
+        assert!(pane.cursor.pending_wrap);
+        assert!(!pane.screen_buffer.get_line(0).unwrap().wrapped);
+        
+        // Rendition changes keep the pending wrap; the next character wraps and marks the line
+        pane.process_output(b"\x1b[1mb").unwrap();
+        assert_eq!(pane.get_cursor_position(), (1, 1));
+        assert!(pane.screen_buffer.get_line(0).unwrap().wrapped);
+        
+        // Cursor movement cancels it, and erasing the line clears the wrapped flag
+        pane.process_output(b"\x1b[3;1H").unwrap();
+        pane.process_output(&full_row).unwrap();
+        pane.process_output(b"\x1b[3;1H\x1b[2K").unwrap();
+        assert!(!pane.cursor.pending_wrap);
+        assert!(!pane.screen_buffer.get_line(2).unwrap().wrapped);
+        
+        // Without DECAWM the last column is overwritten in place
+        pane.process_output(b"\x1b[?7l\x1b[5;1H").unwrap();
+        pane.process_output(&full_row).unwrap();
+        pane.process_output(b"yz").unwrap();
+        assert_eq!(pane.get_cursor_position(), (width - 1, 4));
+        assert!(pane.screen_buffer.get_line(4).unwrap().text().trim_end().ends_with("az"));
+        pane.process_output(b"\x1b[?7$p").unwrap();
+        assert_eq!(pane.take_responses(), b"\x1b[?7;2$y");
+        pane.process_output(b"\x1b[?7h").unwrap();
+        assert!(pane.get_terminal_modes().auto_wrap);
+        
+        // ED 2 erases everything but leaves the cursor where it was
+        pane.process_output(b"\x1b[5;10H\x1b[2J").unwrap();
+        assert_eq!(pane.get_cursor_position(), (9, 4));
+        assert_eq!(pane.get_statistics().unhandled_sequences, 0);
+    }
+    
+    #[test]
+    fn test_tab_stops_and_character_sets() {
+        let mut pane = create_test_pane();
+        let (width, _) = pane.size();
+        
+        // TBC 3 clears every stop, HTS sets one, TBC 0 clears the one under the cursor
+        pane.process_output(b"\x1b[3g\x1b[1;5H\x1bH\r\t").unwrap();
+        assert_eq!(pane.get_cursor_position(), (4, 0));
+        pane.process_output(b"\x1b[g\r\t").unwrap();
+        assert_eq!(pane.get_cursor_position(), (width - 1, 0));
+        
+        // SCS designates G1, SO and SI switch between G1 and G0
+        pane.process_output(b"\x1b[2;1H\x1b)0\x0eqx\x0fqx\x1b(0q\x1b(B").unwrap();
+        let text = pane.screen_buffer.get_line(1).unwrap().text();
+        assert_eq!(text.trim_end(), "\u{2500}\u{2502}qx\u{2500}");
+        assert_eq!(pane.get_statistics().unhandled_sequences, 0);
+    }
+    
+    #[test]
+    fn test_line_size_and_alignment() {
+        let mut pane = create_test_pane();
+        let size_of = |pane: &Pane, row: usize| pane.screen_buffer.get_line(row).unwrap().size;
+        
+        // Text past the middle is lost when a line becomes double-width
+        pane.process_output(b"\x1b[1;61HZ\x1b[1;1Hab\x1b#6").unwrap();
+        assert_eq!(size_of(&pane, 0), LineSize::DoubleWidth);
+        assert_eq!(pane.screen_buffer.get_line(0).unwrap().cells[60].character, ' ');
+        assert!(pane.screen_buffer.get_line(0).unwrap().text().starts_with("ab"));
+        
+        // Cursor math uses the halved width
+        pane.process_output(b"\x1b[1;70H").unwrap();
+        assert_eq!(pane.get_cursor_position(), (39, 0));
+        pane.process_output(b"xy").unwrap();
+        assert_eq!(pane.get_cursor_position(), (1, 1));
+        
+        pane.process_output(b"\x1b#3\x1b[3H\x1b#4\x1b[4H\x1b#6\x1b#5").unwrap();
+        assert_eq!(size_of(&pane, 1), LineSize::DoubleHeightTop);
+        assert_eq!(size_of(&pane, 2), LineSize::DoubleHeightBottom);
+        assert_eq!(size_of(&pane, 3), LineSize::Single);
+        
+        // DECALN fills the screen with single-width lines of E and homes the cursor
+        pane.process_output(b"\x1b[5;5H\x1b#8").unwrap();
+        assert_eq!(pane.get_cursor_position(), (0, 0));
+        let (width, height) = pane.size();
+        for row in 0..height as usize {
+            assert_eq!(size_of(&pane, row), LineSize::Single);
+            assert_eq!(pane.screen_buffer.get_line(row).unwrap().text(), "E".repeat(width as usize));
+        }
+        
+        // ED 2 also returns lines to single width
+        pane.process_output(b"\x1b#6\x1b[2J").unwrap();
+        assert_eq!(size_of(&pane, 0), LineSize::Single);
+        
+        // A scroll that brings a double-width line under the cursor pulls it onto the line, so ICH
+        // and DCH stay inside it
+        for edit in [&b"\x1b[@"[..], b"\x1b[P"] {
+            pane.process_output(b"\x1b[2J\x1b[5;1H\x1b#6\x1b[4;71H\x1b[S").unwrap();
+            assert_eq!(pane.get_cursor_position(), (39, 3));
+            pane.process_output(edit).unwrap();
+            pane.validate_state().unwrap();
+        }
+        
+        // So does a line feed onto one
+        pane.process_output(b"\x1b[2J\x1b[2;1H\x1b#6\x1b[1;71H\n").unwrap();
+        assert_eq!(pane.get_cursor_position(), (39, 1));
+        assert_eq!(pane.get_statistics().unhandled_sequences, 0);
+    }
+    
+    #[test]
+    fn test_rectangular_area_editing() {
+        let mut pane = create_test_pane();
+        let cell = |pane: &Pane, row: usize, col: usize| pane.screen_buffer.get_line(row).unwrap().cells[col].clone();
+        
+        // A blank screen sums to 80 * 24 spaces
+        pane.process_output(b"\x1b[1;1*y").unwrap();
+        assert_eq!(pane.take_responses(), b"\x1bP1!~1000\x1b\\");
+        
+        // DECFRA fills rows 2-4, columns 3-6
+        pane.process_output(b"\x1b[88;2;3;4;6$x").unwrap();
+        assert_eq!(cell(&pane, 1, 1).character, ' ');
+        assert_eq!(cell(&pane, 1, 2).character, 'X');
+        assert_eq!(cell(&pane, 3, 5).character, 'X');
+        assert_eq!(cell(&pane, 3, 6).character, ' ');
+        assert_eq!(cell(&pane, 4, 2).character, ' ');
+        pane.process_output(b"\x1b[7;1;2;3;4;6*y").unwrap();
+        assert_eq!(pane.take_responses(), b"\x1bP7!~FBE0\x1b\\");
+        
+        // Control characters cannot fill
+        pane.process_output(b"\x1b[10;1;1;1;1$x").unwrap();
+        assert_eq!(cell(&pane, 0, 0).character, ' ');
+        
+        // DECCRA copies, DECERA erases; the destination is Ptd;Pld, with its page after
+        pane.process_output(b"\x1b[2;3;2;4;1;10;20;1$v").unwrap();
+        assert_eq!(cell(&pane, 9, 19).character, 'X');
+        assert_eq!(cell(&pane, 9, 20).character, 'X');
+        assert_eq!(cell(&pane, 9, 21).character, ' ');
+        pane.process_output(b"\x1b[2;3;2;3;1;0;;1$v").unwrap();
+        assert_eq!(cell(&pane, 0, 0).character, 'X');
+        assert_eq!(cell(&pane, 0, 1).character, ' ');
+        pane.process_output(b"\x1b[3;3;3;6$z").unwrap();
+        assert!((2..6).all(|col| cell(&pane, 2, col).character == ' '));
+        assert_eq!(cell(&pane, 3, 2).character, 'X');
+        
+        // DECSERA blanks characters but keeps their attributes
+        pane.process_output(b"\x1b[1m\x1b[65;5;1;5;2$x\x1b[0m\x1b[5;1;5;1${").unwrap();
+        assert_eq!(cell(&pane, 4, 0).character, ' ');
+        assert!(cell(&pane, 4, 0).attributes.bold);
+        assert_eq!(cell(&pane, 4, 1).character, 'A');
+        
+        // Rectangles that are inside out do nothing
+        pane.process_output(b"\x1b[4;1;2;80$z").unwrap();
+        assert_eq!(cell(&pane, 3, 2).character, 'X');
+        assert_eq!(pane.get_statistics().unhandled_sequences, 0);
+    }
+    
+    #[test]
+    fn test_rectangular_attribute_changes() {
+        let mut pane = create_test_pane();
+        let attrs = |pane: &Pane, row: usize, col: usize| pane.screen_buffer.get_line(row).unwrap().cells[col].attributes.clone();
+        
+        // Stream extent runs to the end of the first row and from the start of the last
+        pane.process_output(b"\x1b[2;70;3;5;1$r").unwrap();
+        assert!(attrs(&pane, 1, 69).bold && attrs(&pane, 1, 79).bold);
+        assert!(attrs(&pane, 2, 0).bold && attrs(&pane, 2, 4).bold);
+        assert!(!attrs(&pane, 1, 68).bold);
+        assert!(!attrs(&pane, 2, 5).bold);
+        
+        // Rectangle extent changes exactly the rectangle
+        pane.process_output(b"\x1b[2*x\x1b[5;2;6;3;4;7$r").unwrap();
+        assert_eq!(pane.modes.attribute_change_extent, AttributeChangeExtent::Rectangle);
+        assert_eq!(attrs(&pane, 5, 1).underline, UnderlineType::Single);
+        assert!(attrs(&pane, 4, 2).reverse);
+        assert!(!attrs(&pane, 4, 3).reverse);
+        pane.process_output(b"\x1b[5;2;5;2;24$r").unwrap();
+        assert_eq!(attrs(&pane, 4, 1).underline, UnderlineType::None);
+        assert!(attrs(&pane, 4, 1).reverse);
+        
+        // DECRARA toggles the listed attributes, or all of them for 0
+        pane.process_output(b"\x1b[5;2;6;3;7$t").unwrap();
+        assert!(!attrs(&pane, 4, 1).reverse);
+        assert!(!attrs(&pane, 5, 2).reverse);
+        pane.process_output(b"\x1b[5;2;5;2;0$t").unwrap();
+        let toggled = attrs(&pane, 4, 1);
+        assert!(toggled.bold && toggled.reverse);
+        assert_eq!(toggled.underline, UnderlineType::Single);
+        assert_eq!(toggled.blink, BlinkType::Slow);
+        
+        // The checksum counts attributes
+        pane.process_output(b"\x1b[3;1;5;2;5;2*y").unwrap();
+        assert_eq!(pane.take_responses(), b"\x1bP3!~FEF0\x1b\\");
+        
+        pane.process_output(b"\x1b[5;2;5;2;0$r").unwrap();
+        assert_eq!(attrs(&pane, 4, 1), CellAttributes::default());
+        
+        // Each reset parameter clears only its own attribute
+        pane.process_output(b"\x1b[1;1;1;3;1;4;5;7$r\x1b[1;1;1;1;22$r").unwrap();
+        let reset = attrs(&pane, 0, 0);
+        assert!(!reset.bold && reset.reverse);
+        assert_eq!(reset.underline, UnderlineType::Single);
+        assert_eq!(reset.blink, BlinkType::Slow);
+        pane.process_output(b"\x1b[1;2;1;2;24$r").unwrap();
+        let reset = attrs(&pane, 0, 1);
+        assert!(reset.bold && reset.reverse);
+        assert_eq!(reset.underline, UnderlineType::None);
+        assert_eq!(reset.blink, BlinkType::Slow);
+        pane.process_output(b"\x1b[1;3;1;3;25;27$r").unwrap();
+        let reset = attrs(&pane, 0, 2);
+        assert!(reset.bold && !reset.reverse);
+        assert_eq!(reset.underline, UnderlineType::Single);
+        assert_eq!(reset.blink, BlinkType::None);
+        
+        // DECOM homes the cursor and is reported by DECRQM
+        pane.process_output(b"\x1b[5;5H\x1b[?6h\x1b[?6$p").unwrap();
+        assert_eq!(pane.get_cursor_position(), (0, 0));
+        assert_eq!(pane.take_responses(), b"\x1b[?6;1$y");
+        pane.process_output(b"\x1b[0*x").unwrap();
+        assert_eq!(pane.modes.attribute_change_extent, AttributeChangeExtent::Stream);
+    }
+    
+    #[test]
+    fn test_scroll_margins() {
+        let mut pane = create_test_pane();
+        let row_text = |pane: &Pane, row: usize| pane.screen_buffer.get_line(row).unwrap().text().trim_end().to_string();
+        let rows = |pane: &Pane| (0..5).map(|row| row_text(pane, row)).collect::<Vec<_>>();
+        pane.process_output(b"\x1b[1;1Ha\x1b[2;1Hb\x1b[3;1Hc\x1b[4;1Hd\x1b[5;1He").unwrap();
+        
+        // DECSTBM homes the cursor; a line feed on the bottom margin scrolls only the region
+        pane.process_output(b"\x1b[2;4r").unwrap();
+        assert_eq!(pane.get_cursor_position(), (0, 0));
+        pane.process_output(b"\x1b[4;1H\n").unwrap();
+        assert_eq!(rows(&pane), ["a", "c", "d", "", "e"]);
+        assert_eq!(pane.get_cursor_position(), (0, 3));
+        assert_eq!(pane.scrollback.len(), 0);
+        
+        // Reverse index on the top margin scrolls the region down
+        pane.process_output(b"\x1b[2;1H\x1bM").unwrap();
+        assert_eq!(rows(&pane), ["a", "", "c", "d", "e"]);
+        
+        // Vertical movement stops at the margins
+        pane.process_output(b"\x1b[10B").unwrap();
+        assert_eq!(pane.get_cursor_position(), (0, 3));
+        pane.process_output(b"\x1b[10A").unwrap();
+        assert_eq!(pane.get_cursor_position(), (0, 1));
+        
+        // IL does nothing outside the region, DL and SU stay inside it
+        pane.process_output(b"\x1b[5;1H\x1b[L").unwrap();
+        assert_eq!(rows(&pane), ["a", "", "c", "d", "e"]);
+        pane.process_output(b"\x1b[3;1H\x1b[M").unwrap();
+        assert_eq!(rows(&pane), ["a", "", "d", "", "e"]);
+        pane.process_output(b"\x1b[S").unwrap();
+        assert_eq!(rows(&pane), ["a", "d", "", "", "e"]);
+        
+        // Origin mode measures from the top margin and cannot leave the region
+        pane.process_output(b"\x1b[?6h").unwrap();
+        assert_eq!(pane.get_cursor_position(), (0, 1));
+        pane.process_output(b"\x1b[10;1H").unwrap();
+        assert_eq!(pane.get_cursor_position(), (0, 3));
+        pane.process_output(b"\x1bP$qr\x1b\\").unwrap();
+        assert_eq!(pane.take_responses(), b"\x1bP1$r2;4r\x1b\\");
+        
+        // With the margins reset, scrolled lines go to scrollback, including on autowrap
+        pane.process_output(b"\x1b[?6l\x1b[r\x1b[24;1H\n").unwrap();
+        assert_eq!(pane.scrollback.len(), 1);
+        assert_eq!(pane.scrollback.get_line(0).unwrap().text().trim_end(), "a");
+        pane.process_output(b"\x1b[24;80HXY").unwrap();
+        assert_eq!(pane.scrollback.len(), 2);
+        assert_eq!(pane.screen_buffer.get_line(22).unwrap().cells[79].character, 'X');
+        assert_eq!(row_text(&pane, 23), "Y");
+        assert_eq!(pane.get_cursor_position(), (1, 23));
+        
+        // DECALN resets the margins
+        pane.process_output(b"\x1b[2;4r\x1b#8").unwrap();
+        assert_eq!(pane.screen_buffer.margins, ScrollMargins::full(80, 24));
+        assert_eq!(pane.get_statistics().unhandled_sequences, 0);
+    }
+    
+    #[test]
+    fn test_left_right_margins() {
+        let mut pane = create_test_pane();
+        let row_text = |pane: &Pane, row: usize| pane.screen_buffer.get_line(row).unwrap().text().trim_end().to_string();
+        
+        // Without DECLRMM, CSI s saves the cursor (SCOSC) and CSI u restores it
+        pane.process_output(b"\x1b[3;7H\x1b[5;10s\x1b[1;1H\x1b[u").unwrap();
+        assert_eq!(pane.get_cursor_position(), (6, 2));
+        assert_eq!(pane.screen_buffer.margins, ScrollMargins::full(80, 24));
+        
+        pane.process_output(b"\x1b[1;12HZ\x1b[?69h\x1b[?69$p").unwrap();
+        assert_eq!(pane.take_responses(), b"\x1b[?69;1$y");
+        pane.process_output(b"\x1b[5;10s").unwrap();
+        assert_eq!(pane.get_cursor_position(), (0, 0));
+        pane.process_output(b"\x1bP$qs\x1b\\").unwrap();
+        assert_eq!(pane.take_responses(), b"\x1bP1$r5;10s\x1b\\");
+        
+        // Autowrap and carriage return go to the left margin
+        pane.process_output(b"\x1b[1;5Habcdefghij").unwrap();
+        assert_eq!(row_text(&pane, 0), "    abcdef Z");
+        assert_eq!(row_text(&pane, 1), "    ghij");
+        assert_eq!(pane.get_cursor_position(), (8, 1));
+        pane.process_output(b"\r").unwrap();
+        assert_eq!(pane.get_cursor_position(), (4, 1));
+        
+        // ICH and DCH shift only up to the right margin
+        pane.process_output(b"\x1b[1;5H\x1b[2@").unwrap();
+        assert_eq!(row_text(&pane, 0), "      abcd Z");
+        pane.process_output(b"\x1b[1P").unwrap();
+        assert_eq!(row_text(&pane, 0), "     abcd  Z");
+        
+        // Scrolling moves only the columns between the margins
+        pane.process_output(b"\x1b[S").unwrap();
+        assert_eq!(row_text(&pane, 0), "    ghij   Z");
+        assert_eq!(row_text(&pane, 1), "");
+        
+        // Horizontal movement stops at a margin the cursor starts inside of
+        pane.process_output(b"\x1b[1;5H\x1b[20C").unwrap();
+        assert_eq!(pane.get_cursor_position(), (9, 0));
+        pane.process_output(b"\x1b[1;12H\x1b[99C").unwrap();
+        assert_eq!(pane.get_cursor_position(), (79, 0));
+        pane.process_output(b"\x1b[2;3H\x1b[20D").unwrap();
+        assert_eq!(pane.get_cursor_position(), (0, 1));
+        
+        // Origin mode makes rectangle coordinates relative to the margins too
+        pane.process_output(b"\x1b[?6h\x1b[88;1;1;1;2$x").unwrap();
+        assert_eq!(pane.get_cursor_position(), (4, 0));
+        assert_eq!(row_text(&pane, 0), "    XXij   Z");
+        
+        // Resetting DECLRMM resets the left and right margins
+        pane.process_output(b"\x1b[?6l\x1b[?69l").unwrap();
+        assert_eq!(pane.screen_buffer.margins, ScrollMargins::full(80, 24));
+        assert_eq!(pane.get_statistics().unhandled_sequences, 0);
+    }
+    
+    #[test]
+    fn test_selective_erase_and_protection() {
+        let mut pane = create_test_pane();
+        let row_text = |pane: &Pane, row: usize| pane.screen_buffer.get_line(row).unwrap().text().trim_end().to_string();
+        pane.process_output(b"\x1b[1\"qAB\x1b[0\"qCD\x1b[2\"qEF").unwrap();
+        pane.process_output(b"\x1b[2;1H\x1b[1\"qP\x1b[0\"qxy\x1b[3;1Hzz").unwrap();
+        assert!(pane.screen_buffer.get_line(0).unwrap().cells[1].attributes.protected);
+        assert!(!pane.screen_buffer.get_line(0).unwrap().cells[4].attributes.protected);
+        
+        // SGR 0 does not clear DECSCA, and DECRQSS reports it
+        pane.process_output(b"\x1b[1\"q\x1b[0m\x1bP$q\"q\x1b\\").unwrap();
+        assert!(pane.modes.current_attributes.protected);
+        assert_eq!(pane.take_responses(), b"\x1bP1$r1\"q\x1b\\");
+        pane.process_output(b"\x1b[0\"q").unwrap();
+        
+        // Protected cells count in the checksum
+        pane.process_output(b"\x1b[9;1;1;1;1;1*y").unwrap();
+        assert_eq!(pane.take_responses(), b"\x1bP9!~FFBB\x1b\\");
+        
+        // DECSEL and DECSED skip protected cells
+        pane.process_output(b"\x1b[1;3H\x1b[?K").unwrap();
+        assert_eq!(row_text(&pane, 0), "AB");
+        pane.process_output(b"\x1b[2;2H\x1b[?1J").unwrap();
+        assert_eq!(row_text(&pane, 1), "P y");
+        assert_eq!(row_text(&pane, 2), "zz");
+        pane.process_output(b"\x1b[?2J").unwrap();
+        assert_eq!(row_text(&pane, 0), "AB");
+        assert_eq!(row_text(&pane, 1), "P");
+        assert_eq!(row_text(&pane, 2), "");
+        
+        // So does DECSERA
+        pane.process_output(b"\x1b[1;1;2;80${").unwrap();
+        assert_eq!(row_text(&pane, 0), "AB");
+        
+        // ED and EL erase everything, protection included
+        pane.process_output(b"\x1b[2J").unwrap();
+        assert_eq!(row_text(&pane, 0), "");
+        assert!(!pane.screen_buffer.get_line(0).unwrap().cells[0].attributes.protected);
+        pane.process_output(b"\x1b[1\"qQ\x1b[0\"q\x1b[1;1H\x1b[2K").unwrap();
+        assert_eq!(row_text(&pane, 0), "");
+        assert_eq!(pane.get_statistics().unhandled_sequences, 0);
+    }
+    
+    #[test]
+    fn test_term_resolved_at_spawn() {
+        // The default config leaves TERM to the spawn, which checks the installed terminfo
+        let mut pane = create_test_pane();
+        assert!(!pane.config.environment_variables.contains_key("TERM"));
+        pane.spawn_process("sh", &[], &[]).unwrap();
+        assert_eq!(pane.config.environment_variables["TERM"], term_name());
+        
+        // An explicit TERM wins and is kept for the TN reply
+        let mut pane = create_test_pane();
+        pane.spawn_process("sh", &[], &[("TERM".to_string(), "ratty-direct".to_string())]).unwrap();
+        assert_eq!(pane.config.environment_variables["TERM"], "ratty-direct");
+    }
+    
+    #[test]
+    fn test_xtgettcap_replies() {
+        let mut pane = create_test_pane_with_config(PaneConfig::default().with_env("TERM", "ratty"));
+        
+        // TN, a flag, and a string by terminfo and termcap name
+        pane.process_output(b"\x1bP+q544E\x1b\\").unwrap();
+        assert_eq!(pane.take_responses(), b"\x1bP1+r544E=7261747479\x1b\\");
+        pane.process_output(b"\x1bP+q616d\x1b\\").unwrap();
+        assert_eq!(pane.take_responses(), b"\x1bP1+r616D\x1b\\");
+        pane.process_output(b"\x1bP+q6B63757531\x1b\\\x1bP+q6B75\x1b\\").unwrap();
+        assert_eq!(
+            pane.take_responses(),
+            b"\x1bP1+r6B63757531=1B4F41\x1b\\\x1bP1+r6B75=1B4F41\x1b\\"
+        );
+        
+        // Each name gets its own reply; unknown and malformed names are refused
+        pane.process_output(b"\x1bP+q5373;7A7A;G\x1b\\").unwrap();
+        assert_eq!(
+            pane.take_responses(),
+            b"\x1bP1+r5373=1B5B25703125642071\x1b\\\x1bP0+r7A7A\x1b\\\x1bP0+rG\x1b\\"
+        );
+        assert_eq!(pane.get_statistics().unhandled_sequences, 0);
+    }
+    
+    #[test]
+    fn test_decrqss_settings() {
+        let mut pane = create_test_pane();
+        
+        pane.process_output(b"\x1b[1;4;7m\x1bP$qm\x1b\\").unwrap();
+        assert_eq!(pane.take_responses(), b"\x1bP1$r0;1;4;7m\x1b\\");
+        pane.process_output(b"\x1b[m\x1bP$qm\x1b\\").unwrap();
+        assert_eq!(pane.take_responses(), b"\x1bP1$r0m\x1b\\");
+        assert_eq!(pane.modes.current_attributes, CellAttributes::default());
+        
+        pane.process_output(b"\x1bP$qr\x1b\\\x1bP$qs\x1b\\\x1bP$q q\x1b\\").unwrap();
+        assert_eq!(pane.take_responses(), b"\x1bP1$r1;24r\x1b\\\x1bP1$r1;80s\x1b\\\x1bP1$r1 q\x1b\\");
+        for setting in StatusSetting::ALL {
+            assert_eq!(StatusSetting::from_request(setting.selector().as_bytes()), Some(setting));
+        }
+        assert_eq!(StatusSetting::from_request(b"t"), None);
+    }
+    
+    #[test]
+    fn test_capability_table_matches_parser() {
+        assert_eq!(decode_terminfo_string("\\E[%p1%d\\sq"), b"\x1b[%p1%d q");
+        assert_eq!(decode_terminfo_string("^?^H^j"), b"\x7f\x08\n");
+        assert_eq!(find_capability("cm").map(|cap| cap.name), Some("cup"));
+        
+        for (index, cap) in CAPABILITIES.iter().enumerate() {
+            assert!(
+                CAPABILITIES[..index].iter().all(|other| other.name != cap.name && (cap.termcap.is_none() || other.termcap != cap.termcap)),
+                "duplicate capability {}",
+                cap.name
+            );
+        }
+        
+        // Every fixed output sequence is understood by the parser
+        for cap in CAPABILITIES {
+            let CapabilityValue::String(source) = cap.value else { continue };
+            if source.contains('%') || cap.name.starts_with('k') {
+                continue;
+            }
+            let mut pane = create_test_pane();
+            pane.process_output(&decode_terminfo_string(source)).unwrap();
+            assert_eq!(pane.get_statistics().unhandled_sequences, 0, "{} is not handled", cap.name);
+        }
+    }
+    
+    /// Expand a terminfo string as tparm would, for the operators the capability table uses
+    fn expand_capability(source: &str, params: &[&str]) -> Vec<u8> {
+        let source = decode_terminfo_string(source);
+        let mut params: Vec<String> = params.iter().map(|param| param.to_string()).collect();
+        let mut stack: Vec<String> = Vec::new();
+        let mut out = Vec::new();
+        let int = |value: Option<String>| value.and_then(|value| value.parse::<i64>().ok()).unwrap_or(0);
+        
+        // Skip to just past the `%e` or `%;` closing the current branch
+        let skip = |mut i: usize, stop_at_else: bool| {
+            let mut depth = 0;
+            while i + 1 < source.len() {
+                if source[i] == b'%' {
+                    match source[i + 1] {
+                        b'?' => depth += 1,
+                        b';' if depth == 0 => return i + 2,
+                        b';' => depth -= 1,
+                        b'e' if depth == 0 && stop_at_else => return i + 2,
+                        _ => {}
+                    }
+                    i += 2;
+                } else {
+                    i += 1;
+                }
+            }
+            source.len()
+        };
+        
+        let mut i = 0;
+        while i < source.len() {
+            if source[i] != b'%' {
+                out.push(source[i]);
+                i += 1;
+                continue;
+            }
+            let op = source[i + 1];
+            i += 2;
+            match op {
+                b'i' => {
+                    for param in params.iter_mut().take(2) {
+                        *param = (int(Some(param.clone())) + 1).to_string();
+                    }
+                }
+                b'p' => {
+                    let index = (source[i] - b'1') as usize;
+                    stack.push(params.get(index).cloned().unwrap_or_default());
+                    i += 1;
+                }
+                b'{' => {
+                    let end = i + source[i..].iter().position(|&byte| byte == b'}').unwrap();
+                    stack.push(String::from_utf8_lossy(&source[i..end]).to_string());
+                    i = end + 1;
+                }
+                b'd' => out.extend(int(stack.pop()).to_string().into_bytes()),
+                b's' => out.extend(stack.pop().unwrap_or_default().into_bytes()),
+                b'-' | b'/' | b'&' | b'<' => {
+                    let (b, a) = (int(stack.pop()), int(stack.pop()));
+                    let result = match op {
+                        b'-' => a - b,
+                        b'/' => a / b,
+                        b'&' => a & b,
+                        _ => (a < b) as i64,
+                    };
+                    stack.push(result.to_string());
+                }
+                b'?' => {}
+                b't' if int(stack.pop()) == 0 => i = skip(i, true),
+                b't' | b';' => {}
+                b'e' => i = skip(i, false),
+                other => panic!("unsupported terminfo operator %{}", other as char),
+            }
+        }
+        out
+    }
+    
+    #[test]
+    fn test_primary_device_attributes() {
+        let mut pane = create_test_pane();
+        
+        pane.process_output(b"\x1b[c\x1b[0c").unwrap();
+        assert_eq!(pane.take_responses(), b"\x1b[?64;4;6;22;28c\x1b[?64;4;6;22;28c");
+        
+        // The reply matches what `u8` tells programs to expect
+        let u8 = find_capability("u8").unwrap();
+        assert_eq!(u8.value, CapabilityValue::String("\\E[?%[;0123456789]c"));
+        
+        // Secondary and tertiary DA are not answered
+        pane.process_output(b"\x1b[>c\x1b[=c").unwrap();
+        assert!(pane.take_responses().is_empty());
+    }
+    
+    #[test]
+    fn test_terminfo_source_matches_parser() {
+        let source = terminfo_source();
+        assert!(source.contains("\nratty|ratty terminal emulator,\n"));
+        assert!(source.contains("\nratty-direct|"));
+        assert_eq!(expand_capability("\\E[%i%p1%d;%p2%dH", &["4", "9"]), b"\x1b[5;10H");
+        
+        let direct = TerminfoEntry::RattyDirect.capabilities();
+        assert!(direct.iter().any(|cap| cap.name == "RGB"));
+        assert!(direct.iter().any(|cap| cap.name == "colors" && cap.value == CapabilityValue::Number(0x1000000)));
+        assert_eq!(direct.iter().filter(|cap| cap.name == "setaf").count(), 1);
+        
+        // Every sequence an entry tells programs to send is parsed and handled
+        for entry in TerminfoEntry::ALL {
+            for cap in entry.capabilities() {
+                let CapabilityValue::String(value) = cap.value else { continue };
+                // Keys, paste markers and the DA1 answer are sent by the terminal, not to it
+                if cap.name.starts_with('k') || matches!(cap.name, "PS" | "PE" | "u8") {
+                    continue;
+                }
+                
+                let colors: &[&str] = match (entry, cap.name) {
+                    (TerminfoEntry::RattyDirect, "setaf" | "setab") => &["1", "16744448"],
+                    (_, "setaf" | "setab") => &["1", "12", "200"],
+                    (_, "Cs") => &["#ff8000"],
+                    _ => &["3"],
+                };
+                for param in colors {
+                    let bytes = expand_capability(value, &[param, "7"]);
+                    let config = PaneConfig { log_terminal_sequences: true, ..PaneConfig::default() };
+                    let mut pane = create_test_pane_with_config(config);
+                    pane.process_output(&bytes).unwrap();
+                    
+                    let trace = pane.sequence_trace().unwrap();
+                    let problems: Vec<String> = trace
+                        .entries()
+                        .filter(|entry| entry.status != TraceStatus::Handled)
+                        .map(|entry| entry.to_string())
+                        .collect();
+                    assert!(problems.is_empty(), "{} {}={} with {}: {:?}", entry.name(), cap.name, value, param, problems);
+                }
+            }
+            
+            // `am` and `xenl` promise that a full row leaves the cursor in the last column until the next
+            // character, so a following CR LF moves down one line rather than two
+            let declares = |name: &str| entry.capabilities().iter().any(|cap| cap.name == name);
+            assert!(declares("am") && declares("xenl"), "{}", entry.name());
+            let mut pane = create_test_pane();
+            pane.process_output(&[b'x'; 80]).unwrap();
+            assert_eq!(pane.get_cursor_position(), (79, 0));
+            pane.process_output(b"\r\n").unwrap();
+            assert_eq!(pane.get_cursor_position(), (0, 1));
+            pane.process_output(&[b'y'; 81]).unwrap();
+            assert_eq!(pane.get_cursor_position(), (1, 2));
+            assert_eq!(pane.screen_buffer.get_line(2).unwrap().cells[0].character, 'y');
+        }
+    }
+    
+    #[test]
+    fn test_sgr_colors() {
+        let mut pane = create_test_pane();
+        
+        pane.process_output(b"\x1b[31;104mA\x1b[38;5;208;48;2;1;2;3mB\x1b[39;49mC\x1b[38;7mD").unwrap();
+        let line = &pane.screen_buffer.lines[0];
+        assert_eq!(line.cells[0].attributes.foreground, Some(SgrColor::Indexed(1)));
+        assert_eq!(line.cells[0].attributes.background, Some(SgrColor::Indexed(12)));
+        assert_eq!(line.cells[0].foreground, Color::from_rgb(205, 49, 49));
+        assert_eq!(line.cells[1].foreground, Color::from_rgb(255, 135, 0));
+        assert_eq!(line.cells[1].background, Color::from_rgb(1, 2, 3));
+        assert_eq!(line.cells[2], Cell { character: 'C', ..Cell::default() });
+        // A malformed extended color leaves the color alone
+        assert_eq!(line.cells[3].attributes.foreground, None);
+        
+        assert_eq!(SgrColor::Indexed(232).to_color(), Color::from_rgb(8, 8, 8));
+        assert_eq!(SgrColor::Indexed(231).to_color(), Color::from_rgb(255, 255, 255));
+        
+        pane.process_output(b"\x1b[1;93;48;5;17m\x1bP$qm\x1b\\\x1b[0;38;2;9;8;7;45m\x1bP$qm\x1b\\").unwrap();
+        assert_eq!(pane.take_responses(), b"\x1bP1$r0;1;93;48;5;17m\x1b\\\x1bP1$r0;38;2;9;8;7;45m\x1b\\");
+    }
+    
+    #[test]
+    fn test_sgr_underline_styles_and_color() {
+        let mut pane = create_test_pane();
+        
+        pane.process_output(b"\x1b[4:3;58:2::255:128:0mA\x1b[4:5;58;5;9mB\x1b[4:0mC\x1b[4;59mD").unwrap();
+        let cells = &pane.screen_buffer.lines[0].cells;
+        assert_eq!(cells[0].attributes.underline, UnderlineType::Curly);
+        assert_eq!(cells[0].attributes.underline_color, Some(SgrColor::Rgb(255, 128, 0)));
+        assert_eq!(cells[1].attributes.underline, UnderlineType::Dashed);
+        assert_eq!(cells[1].attributes.underline_color, Some(SgrColor::Indexed(9)));
+        assert_eq!(cells[2].attributes.underline, UnderlineType::None);
+        assert_eq!(cells[3].attributes.underline, UnderlineType::Single);
+        assert_eq!(cells[3].attributes.underline_color, None);
+        
+        // Unknown styles are ignored, and colon colors work for 38 and 48 too
+        pane.process_output(b"\x1b[0;4:4;4:9;38:2:1:2:3;48:5:17mE").unwrap();
+        let attrs = &pane.screen_buffer.lines[0].cells[4].attributes;
+        assert_eq!(attrs.underline, UnderlineType::Dotted);
+        assert_eq!(attrs.foreground, Some(SgrColor::Rgb(1, 2, 3)));
+        assert_eq!(attrs.background, Some(SgrColor::Indexed(17)));
+        
+        pane.process_output(b"\x1b[0;4:3;58:2::1:2:3m\x1bP$qm\x1b\\").unwrap();
+        assert_eq!(pane.take_responses(), b"\x1bP1$r0;4:3;58;2;1;2;3m\x1b\\");
+        assert_eq!(pane.get_statistics().unhandled_sequences, 0);
+    }
+    
+    #[test]
+    fn test_sgr_attributes_round_trip() {
+        type IsSet = fn(&CellAttributes) -> bool;
+        let mut pane = create_test_pane();
+        let cases: &[(&str, IsSet)] = &[
+            ("1", |a| a.bold),
+            ("2", |a| a.dim),
+            ("3", |a| a.italic),
+            ("4", |a| a.underline == UnderlineType::Single),
+            ("4:2", |a| a.underline == UnderlineType::Double),
+            ("21", |a| a.underline == UnderlineType::Double),
+            ("4:3", |a| a.underline == UnderlineType::Curly),
+            ("4:4", |a| a.underline == UnderlineType::Dotted),
+            ("4:5", |a| a.underline == UnderlineType::Dashed),
+            ("5", |a| a.blink == BlinkType::Slow),
+            ("6", |a| a.blink == BlinkType::Fast),
+            ("7", |a| a.reverse),
+            ("8", |a| a.invisible),
+            ("9", |a| a.strikethrough),
+            ("13", |a| a.font == 3),
+            ("20", |a| a.font == 10),
+            ("51", |a| a.frame == FrameType::Framed),
+            ("52", |a| a.frame == FrameType::Encircled),
+            ("53", |a| a.overline),
+            ("73", |a| a.script == ScriptPosition::Superscript),
+            ("74", |a| a.script == ScriptPosition::Subscript),
+            ("91", |a| a.foreground == Some(SgrColor::Indexed(9))),
+            ("48;5;17", |a| a.background == Some(SgrColor::Indexed(17))),
+            ("58:2::1:2:3", |a| a.underline_color == Some(SgrColor::Rgb(1, 2, 3))),
+        ];
+        
+        // Each attribute is reported by DECRQSS, and the report sets it again
+        for (sgr, is_set) in cases {
+            pane.process_output(format!("\x1b[0;{}m\x1bP$qm\x1b\\", sgr).as_bytes()).unwrap();
+            let attrs = pane.modes.current_attributes.clone();
+            assert!(is_set(&attrs), "SGR {}", sgr);
+            
+            let reply = String::from_utf8(pane.take_responses()).unwrap();
+            let params = reply.strip_prefix("\x1bP1$r").and_then(|rest| rest.strip_suffix("m\x1b\\")).unwrap();
+            pane.process_output(format!("\x1b[0m\x1b[{}m", params).as_bytes()).unwrap();
+            assert_eq!(pane.modes.current_attributes, attrs, "SGR {} reported as {}", sgr, params);
+        }
+        
+        // Each attribute's reset clears it and nothing else
+        let resets = [
+            ("1;2", "22"), ("3;20", "23"), ("4:3", "24"), ("6", "25"), ("7", "27"), ("8", "28"),
+            ("9", "29"), ("15", "10"), ("52", "54"), ("53", "55"), ("74", "75"), ("58;5;1", "59"),
+        ];
+        for (set, reset) in resets {
+            pane.process_output(format!("\x1b[0;{}m\x1b[{}m", set, reset).as_bytes()).unwrap();
+            assert_eq!(pane.modes.current_attributes, CellAttributes::default(), "SGR {} then {}", set, reset);
+        }
+        
+        // Fonts other than Fraktur survive 23
+        pane.process_output(b"\x1b[0;3;12;23m").unwrap();
+        assert_eq!(pane.modes.current_attributes.font, 2);
+        assert_eq!(pane.get_statistics().unhandled_sequences, 0);
+    }
+    
+    #[test]
+    fn test_sgr_21_configurable() {
+        let mut pane = create_test_pane();
+        pane.process_output(b"\x1b[1;21m").unwrap();
+        assert!(pane.modes.current_attributes.bold);
+        assert_eq!(pane.modes.current_attributes.underline, UnderlineType::Double);
+        
+        // As on the Linux console, 21 can mean normal intensity instead
+        let mut config = PaneConfig { sgr21_double_underline: false, ..PaneConfig::default() };
+        let mut pane = create_test_pane_with_config(config.clone());
+        pane.process_output(b"\x1b[1;2;21m").unwrap();
+        assert_eq!(pane.modes.current_attributes, CellAttributes::default());
+        
+        // The setting follows config updates, and DECRQSS never reports 21
+        config.sgr21_double_underline = true;
+        pane.update_config(config).unwrap();
+        pane.process_output(b"\x1b[21m\x1bP$qm\x1b\\").unwrap();
+        assert_eq!(pane.take_responses(), b"\x1bP1$r0;4:2m\x1b\\");
+    }
+    
+    #[test]
+    fn test_bracketed_paste() {
+        let mut pane = create_test_pane();
+        
+        pane.paste("ls\n").unwrap();
+        assert_eq!(pane.take_responses(), b"ls\n");
+        
+        // The pasted text cannot close the bracket itself
+        pane.process_output(b"\x1b[?2004h\x1b[?2004$p").unwrap();
+        assert_eq!(pane.take_responses(), b"\x1b[?2004;1$y");
+        pane.paste("a\x1b[201~b").unwrap();
+        assert_eq!(pane.take_responses(), b"\x1b[200~a[201~b\x1b[201~");
+        
+        pane.process_output(b"\x1b[?2004l").unwrap();
+        pane.paste("x").unwrap();
+        assert_eq!(pane.take_responses(), b"x");
+    }
+    
+    #[test]
+    fn test_vt52_mode() {
+        let mut pane = create_test_pane();
+        let row_text = |pane: &Pane, row: usize| pane.screen_buffer.get_line(row).unwrap().text().trim_end().to_string();
+        
+        pane.process_output(b"\x1b[?2l").unwrap();
+        assert!(pane.get_terminal_modes().vt52);
+        
+        // Direct cursor addressing is offset by 32, and the usual moves are single steps
+        pane.process_output(b"\x1bY%*X\x1bA\x1bCY").unwrap();
+        assert_eq!(row_text(&pane, 5), "          X");
+        assert_eq!(row_text(&pane, 4), "            Y");
+        pane.process_output(b"\x1bH\x1bFqx\x1bGq\x1bK").unwrap();
+        assert_eq!(row_text(&pane, 0), "─│q");
+        pane.process_output(b"\x1bH\x1bK\x1bZ").unwrap();
+        assert_eq!(row_text(&pane, 0), "");
+        assert_eq!(pane.take_responses(), b"\x1b/Z");
+        
+        pane.process_output(b"\x1b=").unwrap();
+        assert!(pane.get_terminal_modes().application_keypad);
+        
+        // ESC < returns to ANSI mode
+        pane.process_output(b"\x1b<\x1b[?2$p\x1b[3;3H").unwrap();
+        assert!(!pane.get_terminal_modes().vt52);
+        assert_eq!(pane.take_responses(), b"\x1b[?2;1$y");
+        assert_eq!(pane.get_cursor_position(), (2, 2));
+        assert_eq!(pane.get_statistics().unhandled_sequences, 0);
+    }
+    
+    // ========== Sequence Handler Tests ==========
+    
+    /// OSC 7777 ; status ST - an in-house build status extension
+    struct BuildStatusHandler;
+    
+    impl VtCommandHandler for BuildStatusHandler {
+        fn handle(&mut self, sequence: &CustomSequence, pane: &mut PaneHandle<'_>) -> PaneResult<()> {
+            let status = String::from_utf8_lossy(sequence.osc_argument().unwrap_or_default()).into_owned();
+            match status.as_str() {
+                "fail" => Err(PaneError::parse("build failed")),
+                "crash" => panic!("handler bug"),
+                _ => {
+                    pane.set_user_var("build".to_string(), status);
+                    pane.print("ok")?;
+                    pane.respond(b"\x1b]7777;ack\x07");
+                    Ok(())
+                }
+            }
+        }
+        
+        fn can_handle(&self, sequence: &CustomSequence) -> bool {
+            sequence.osc_command() == Some(b"7777")
+        }
+    }
+    
+    struct PrivateCsiHandler;
+    
+    impl VtCommandHandler for PrivateCsiHandler {
+        fn handle(&mut self, _sequence: &CustomSequence, pane: &mut PaneHandle<'_>) -> PaneResult<()> {
+            let (cols, rows) = pane.size();
+            pane.respond(format!("{}x{}", cols, rows).as_bytes());
+            Ok(())
+        }
+        
+        fn can_handle(&self, sequence: &CustomSequence) -> bool {
+            matches!(sequence, CustomSequence::Csi { private_marker: Some(b'>'), final_byte: b'Z', .. })
+        }
+    }
+    
+    #[test]
+    fn test_sequence_handlers() {
+        let mut pane = create_test_pane();
+        
+        // Without a handler, private sequences are dropped
+        pane.process_output(b"\x1b]7777;green\x07\x1b[>1Z").unwrap();
+        assert_eq!(pane.user_var("build"), None);
+        
+        assert!(pane.register_sequence_handler("build".to_string(), Box::new(BuildStatusHandler)).is_none());
+        pane.register_sequence_handler("csi".to_string(), Box::new(PrivateCsiHandler));
+        pane.process_output(b"\x1b]7777;green\x07\x1b[>1Z\x1b[1Z").unwrap();
+        assert_eq!(pane.user_var("build"), Some("green"));
+        assert!(pane.get_line(0).unwrap().text().starts_with("ok"));
+        assert_eq!(pane.take_responses(), b"\x1b]7777;ack\x0780x24");
+        
+        // Errors and panics are counted and the stream keeps going
+        pane.process_output(b"\x1b]7777;fail\x07\x1b]7777;crash\x1b\\\x1b]7777;blue\x07").unwrap();
+        assert_eq!(pane.get_statistics().handler_errors, 2);
+        assert_eq!(pane.user_var("build"), Some("blue"));
+        
+        assert!(pane.unregister_sequence_handler("build").is_some());
+        pane.process_output(b"\x1b]7777;red\x07").unwrap();
+        assert_eq!(pane.user_var("build"), Some("blue"));
+    }
+    
+    // ========== Window Operation Tests ==========
+    
+    struct WindowManipulationRecorder {
+        received: std::sync::Arc<std::sync::Mutex<Vec<WindowManipulation>>>,
+    }
+    
+    impl PaneEventListener for WindowManipulationRecorder {
+        fn handle_pane_event(&mut self, event: &PaneEvent) -> PaneResult<()> {
+            if let PaneEvent::WindowManipulationRequested(manipulation) = event {
+                self.received.lock().unwrap().push(*manipulation);
+            }
+            Ok(())
+        }
+        
+        fn can_handle(&self, event_type: PaneEventType) -> bool {
+            event_type == PaneEventType::WindowManipulationRequested
+        }
+    }
+    
+    #[test]
+    fn test_xtwinops_reports() {
+        let mut pane = create_test_pane_with_config(PaneConfig::default().with_size(100, 30).with_cell_size(9, 18));
+        
+        pane.process_output(b"\x1b[18t\x1b[14t\x1b[16t\x1b[11t").unwrap();
+        assert_eq!(pane.take_responses(), b"\x1b[8;30;100t\x1b[4;540;900t\x1b[6;18;9t\x1b[1t");
+        
+        // Title reports are not answered
+        pane.process_output(b"\x1b[21t\x1b[14;2t").unwrap();
+        assert!(pane.take_responses().is_empty());
+        assert_eq!(WindowOperation::from_params(&[9, 2]), None);
+    }
+    
+    #[test]
+    fn test_xtwinops_manipulation_events() {
+        let mut pane = create_test_pane();
+        let received = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        pane.register_event_listener(
+            PaneEventType::WindowManipulationRequested,
+            Box::new(WindowManipulationRecorder { received: received.clone() }),
+        );
+        
+        pane.process_output(b"\x1b[2t\x1b[5t\x1b[8;40;120t\x1b[4;;640t\x1b[10;2t").unwrap();
+        
+        assert_eq!(received.lock().unwrap().as_slice(), &[
+            WindowManipulation::Iconify,
+            WindowManipulation::Raise,
+            WindowManipulation::ResizeCells { cols: 120, rows: 40 },
+            WindowManipulation::ResizePixels { width: 640, height: 0 },
+            WindowManipulation::ToggleFullscreen,
+        ]);
+        assert!(pane.take_responses().is_empty());
+        assert_eq!(pane.get_statistics().unhandled_sequences, 0);
+    }
+    
+    #[test]
+    fn test_sequence_trace() {
+        let mut pane = create_test_pane();
+        assert!(pane.sequence_trace().is_none());
+        
+        let config = PaneConfig { log_terminal_sequences: true, sequence_log_capacity: 6, ..PaneConfig::default() };
+        pane.update_config(config).unwrap();
+        pane.process_output(b"\x1b[?1049hhi\x1b]0;title\x07\x1b[5;3H\x1b[1;31m\x1b[?12345h").unwrap();
+        
+        let trace = pane.sequence_trace().expect("Tracing should be on");
+        let lines = trace.lines();
+        assert_eq!(lines.len(), 6);
+        assert!(lines[0].ends_with("CSI ?1049h  DECSET alt-screen+save-cursor"), "{}", lines[0]);
+        assert!(lines[1].ends_with("hi  TEXT"), "{}", lines[1]);
+        assert!(lines[2].contains("OSC 0;title BEL"), "{}", lines[2]);
+        assert!(lines[3].ends_with("CSI 5;3H  CUP row 5 col 3"), "{}", lines[3]);
+        assert!(lines[4].ends_with("CSI 1;31m  SGR bold+31"), "{}", lines[4]);
+        assert!(lines[5].ends_with("CSI ?12345h  DECSET 12345  [unhandled]"), "{}", lines[5]);
+        
+        // OSC 0 titles are not parsed yet, so they are flagged unknown
+        let statuses: Vec<TraceStatus> = trace.entries().map(|entry| entry.status).collect();
+        assert_eq!(statuses[2], TraceStatus::Unknown);
+        assert!(lines[2].ends_with("[unknown]"), "{}", lines[2]);
+        
+        // Only the newest entries are kept
+        pane.process_output(b"\x1b[A\x1b[B").unwrap();
+        let lines = pane.sequence_trace().unwrap().lines();
+        assert_eq!(lines.len(), 6);
+        assert!(lines[0].contains("OSC 0;title BEL"), "{}", lines[0]);
+        assert!(lines[5].ends_with("CSI B  CUD 1"), "{}", lines[5]);
+        
+        // Long payloads and text runs keep only their start, plus the length received
+        let mut payload = b"\x1b]52;c;".to_vec();
+        payload.extend(std::iter::repeat_n(b'A', 4096));
+        payload.extend(b"\x07");
+        payload.extend(std::iter::repeat_n(b'x', 500));
+        pane.process_output(&payload).unwrap();
+        pane.process_output(b"\r").unwrap();
+        let trace = pane.sequence_trace().unwrap();
+        let entries: Vec<&TraceEntry> = trace.entries().collect();
+        let (osc, text) = (entries[3], entries[4]);
+        assert_eq!((osc.bytes.len(), osc.len), (96, 4104));
+        assert_eq!((text.bytes.len(), text.len), (96, 500));
+        assert!(osc.to_string().contains("… (4104 bytes)  "), "{}", osc);
+        assert!(text.to_string().contains("… (500 bytes)  TEXT"), "{}", text);
+        
+        let config = PaneConfig { log_terminal_sequences: false, ..PaneConfig::default() };
+        pane.update_config(config).unwrap();
+        assert!(pane.sequence_trace().is_none());
+    }
+}
@@ -0,0 +1,509 @@
+use super::*;
+use std::fmt::{self, Write as _};
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// Longest raw or decoded text kept for one trace line
+const TRACE_TEXT_LIMIT: usize = 96;
+
+/// How the emulator dealt with a traced sequence
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceStatus {
+    Handled,
+    /// Not a sequence ratty recognizes; dropped unless an extension claimed it
+    Unknown,
+    /// Recognized but not implemented
+    Unhandled,
+}
+
+/// One line of the sequence trace: a parsed sequence, or a run of printed text
+#[derive(Debug, Clone)]
+pub struct TraceEntry {
+    /// Time since tracing started
+    pub elapsed: Duration,
+    /// The first `TRACE_TEXT_LIMIT` bytes received
+    pub bytes: Vec<u8>,
+    /// Length as received, so payloads of any size cost one short line
+    pub len: usize,
+    pub description: String,
+    pub status: TraceStatus,
+}
+
+impl fmt::Display for TraceEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let flag = match self.status {
+            TraceStatus::Handled => "",
+            TraceStatus::Unknown => "  [unknown]",
+            TraceStatus::Unhandled => "  [unhandled]",
+        };
+        let mut bytes = render_bytes(&self.bytes);
+        if self.len > self.bytes.len() {
+            if !bytes.ends_with('…') {
+                bytes.push('…');
+            }
+            write!(bytes, " ({} bytes)", self.len)?;
+        }
+        write!(
+            f,
+            "{:>12.6}  {}  {}{}",
+            self.elapsed.as_secs_f64(),
+            bytes,
+            truncate(&self.description),
+            flag
+        )
+    }
+}
+
+/// Recent sequences received by a pane, for debugging what a program sent (`log_terminal_sequences`)
+///
+/// Entries are kept in a ring buffer for an in-app inspector and, when a log
+/// directory is configured, appended to a per-pane file as they complete.
+#[derive(Debug)]
+pub struct SequenceTrace {
+    started: Instant,
+    entries: VecDeque<TraceEntry>,
+    capacity: usize,
+    /// Bytes read since the last complete sequence, up to `TRACE_TEXT_LIMIT`
+    pending: Vec<u8>,
+    pending_len: usize,
+    /// Whether the newest entry is a text run that later characters extend
+    text_open: bool,
+    file: Option<BufWriter<File>>,
+}
+
+impl SequenceTrace {
+    /// Create a trace that keeps the most recent `capacity` entries
+    pub fn new(capacity: usize) -> Self {
+        SequenceTrace {
+            started: Instant::now(),
+            entries: VecDeque::new(),
+            capacity: capacity.max(1),
+            pending: Vec::new(),
+            pending_len: 0,
+            text_open: false,
+            file: None,
+        }
+    }
+    
+    /// Create the trace configured for a pane, logging to `pane-<id>.log` in the log directory if one is set
+    pub fn for_pane(id: PaneId, config: &PaneConfig) -> PaneResult<Self> {
+        let mut trace = SequenceTrace::new(config.sequence_log_capacity);
+        if let Some(dir) = &config.sequence_log_dir {
+            let path = Path::new(dir).join(format!("pane-{}.log", id.as_u64()));
+            let file = OpenOptions::new().create(true).append(true).open(&path)?;
+            trace.file = Some(BufWriter::new(file));
+        }
+        Ok(trace)
+    }
+    
+    /// The retained entries, oldest first
+    pub fn entries(&self) -> impl Iterator<Item = &TraceEntry> {
+        self.entries.iter()
+    }
+    
+    /// The retained entries as text, one line each
+    pub fn lines(&self) -> Vec<String> {
+        self.entries.iter().map(TraceEntry::to_string).collect()
+    }
+    
+    fn push_byte(&mut self, byte: u8) {
+        if self.pending.len() < TRACE_TEXT_LIMIT {
+            self.pending.push(byte);
+        }
+        self.pending_len += 1;
+    }
+    
+    /// Record a complete sequence from the bytes read since the last one
+    fn record(&mut self, description: String, status: TraceStatus) {
+        self.close_text();
+        let entry = TraceEntry {
+            elapsed: self.started.elapsed(),
+            bytes: std::mem::take(&mut self.pending),
+            len: std::mem::take(&mut self.pending_len),
+            description,
+            status,
+        };
+        self.write(&entry);
+        self.push(entry);
+    }
+    
+    /// Record a printed character, extending the current text run
+    fn record_text(&mut self) {
+        let bytes = std::mem::take(&mut self.pending);
+        let len = std::mem::take(&mut self.pending_len);
+        if self.text_open {
+            if let Some(entry) = self.entries.back_mut() {
+                let room = TRACE_TEXT_LIMIT.saturating_sub(entry.bytes.len());
+                entry.bytes.extend(bytes.into_iter().take(room));
+                entry.len += len;
+                return;
+            }
+        }
+        let entry = TraceEntry {
+            elapsed: self.started.elapsed(),
+            bytes,
+            len,
+            description: "TEXT".to_string(),
+            status: TraceStatus::Handled,
+        };
+        self.push(entry);
+        self.text_open = true;
+    }
+    
+    /// Finish the open text run and push buffered lines to the log file
+    fn flush(&mut self) {
+        self.close_text();
+        if let Some(file) = self.file.as_mut() {
+            if file.flush().is_err() {
+                self.file = None;
+            }
+        }
+    }
+    
+    fn close_text(&mut self) {
+        if std::mem::take(&mut self.text_open) {
+            if let Some(entry) = self.entries.back().cloned() {
+                self.write(&entry);
+            }
+        }
+    }
+    
+    fn push(&mut self, entry: TraceEntry) {
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry);
+    }
+    
+    /// Append an entry to the log file; a failing file is dropped rather than failing the pane
+    fn write(&mut self, entry: &TraceEntry) {
+        if let Some(file) = self.file.as_mut() {
+            if writeln!(file, "{}", entry).is_err() {
+                self.file = None;
+            }
+        }
+    }
+}
+
+impl Pane {
+    /// Parse, execute and record one byte while sequence tracing is on
+    pub(crate) fn trace_byte(&mut self, byte: u8) -> PaneResult<()> {
+        if let Some(trace) = self.trace.as_mut() {
+            trace.push_byte(byte);
+        }
+        
+        let Some(sequence) = self.terminal.parse_byte(byte)? else {
+            // Back in the ground state with nothing parsed: an unknown escape or an ignored control
+            if self.terminal.is_idle() {
+                if let Some(trace) = self.trace.as_mut().filter(|trace| !trace.pending.is_empty()) {
+                    let description = if trace.pending[0] == 0x1b { "unrecognized escape" } else { "ignored control" };
+                    trace.record(description.to_string(), TraceStatus::Unknown);
+                }
+            }
+            return Ok(());
+        };
+        
+        let is_text = matches!(sequence, VtSequence::Character(_));
+        let unrecognized = unrecognized_kind(&sequence);
+        let parsed = match unrecognized {
+            Some(kind) => format!("unrecognized {}", kind),
+            None if is_text => String::new(),
+            None => truncate(&format!("{:?}", sequence)),
+        };
+        let Some(command) = self.terminal.interpret(sequence)? else {
+            if let Some(trace) = self.trace.as_mut() {
+                let status = if unrecognized.is_some() { TraceStatus::Unknown } else { TraceStatus::Unhandled };
+                trace.record(parsed, status);
+            }
+            return Ok(());
+        };
+        
+        let description = describe_command(&command);
+        let unhandled = self.stats.unhandled_sequences;
+        let result = self.execute_terminal_command(command);
+        
+        if let Some(trace) = self.trace.as_mut() {
+            if is_text {
+                trace.record_text();
+            } else {
+                let status = if self.stats.unhandled_sequences > unhandled {
+                    TraceStatus::Unhandled
+                } else {
+                    TraceStatus::Handled
+                };
+                trace.record(description, status);
+            }
+        }
+        result
+    }
+    
+    /// Close the open text run and flush the log file at the end of a read
+    pub(crate) fn flush_trace(&mut self) {
+        if let Some(trace) = self.trace.as_mut() {
+            trace.flush();
+        }
+    }
+    
+    /// Start or stop tracing to follow `log_terminal_sequences`
+    pub(crate) fn configure_trace(&mut self, config: &PaneConfig) -> PaneResult<()> {
+        let enabled = config.log_terminal_sequences;
+        let changed = config.sequence_log_capacity != self.config.sequence_log_capacity
+            || config.sequence_log_dir != self.config.sequence_log_dir;
+        if !enabled {
+            self.trace = None;
+        } else if self.trace.is_none() || changed {
+            self.trace = Some(SequenceTrace::for_pane(self.id, config)?);
+        }
+        Ok(())
+    }
+}
+
+/// The kind of sequence, if the parser has no variant for it
+fn unrecognized_kind(sequence: &VtSequence) -> Option<&'static str> {
+    match sequence {
+        VtSequence::Csi(CsiCommand::Unknown { .. }) => Some("CSI"),
+        VtSequence::Osc(OscCommand::Unknown(_)) => Some("OSC"),
+        VtSequence::Dcs(DcsCommand::Unknown { .. }) => Some("DCS"),
+        VtSequence::Apc(ApcCommand::Unknown(_)) => Some("APC"),
+        _ => None,
+    }
+}
+
+/// Show raw bytes with control characters spelled out, as in `CSI ?1049h` or `OSC 0;title BEL`
+fn render_bytes(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    let mnemonic = |out: &mut String, name: &str| {
+        if !out.is_empty() && !out.ends_with(' ') {
+            out.push(' ');
+        }
+        out.push_str(name);
+        out.push(' ');
+    };
+    
+    let mut iter = bytes.iter().peekable();
+    while let Some(&byte) = iter.next() {
+        match byte {
+            0x1b => {
+                let introducer = match iter.peek() {
+                    Some(b'[') => Some("CSI"),
+                    Some(b']') => Some("OSC"),
+                    Some(b'P') => Some("DCS"),
+                    Some(b'_') => Some("APC"),
+                    Some(b'\\') => Some("ST"),
+                    _ => None,
+                };
+                if introducer.is_some() {
+                    iter.next();
+                }
+                mnemonic(&mut out, introducer.unwrap_or("ESC"));
+            }
+            0x07 => mnemonic(&mut out, "BEL"),
+            0x08 => mnemonic(&mut out, "BS"),
+            0x09 => mnemonic(&mut out, "HT"),
+            0x0a => mnemonic(&mut out, "LF"),
+            0x0d => mnemonic(&mut out, "CR"),
+            0x0e => mnemonic(&mut out, "SO"),
+            0x0f => mnemonic(&mut out, "SI"),
+            0x00..=0x1f => mnemonic(&mut out, &format!("^{}", (byte + 0x40) as char)),
+            0x7f => mnemonic(&mut out, "DEL"),
+            0x80..=0xff => mnemonic(&mut out, &format!("<{:02x}>", byte)),
+            _ => out.push(byte as char),
+        }
+    }
+    truncate(out.trim_end())
+}
+
+fn truncate(text: &str) -> String {
+    match text.char_indices().nth(TRACE_TEXT_LIMIT) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+/// Decode a command into its mnemonic and meaning, as in `DECSET alt-screen+save-cursor`
+pub fn describe_command(command: &VtCommand) -> String {
+    let join = |params: &[i32]| params.iter().map(i32::to_string).collect::<Vec<_>>().join(";");
+    match command {
+        VtCommand::PrintChar(ch) => format!("TEXT {:?}", ch),
+        VtCommand::Bell => "BEL".to_string(),
+        VtCommand::Backspace => "BS".to_string(),
+        VtCommand::Tab => "HT".to_string(),
+        VtCommand::LineFeed => "LF".to_string(),
+        VtCommand::CarriageReturn => "CR".to_string(),
+        VtCommand::CursorUp(n) => format!("CUU {}", n),
+        VtCommand::CursorDown(n) => format!("CUD {}", n),
+        VtCommand::CursorForward(n) => format!("CUF {}", n),
+        VtCommand::CursorBack(n) => format!("CUB {}", n),
+        VtCommand::CursorPosition(row, col) => format!("CUP row {} col {}", row, col),
+        VtCommand::SaveCursor => "DECSC save-cursor".to_string(),
+        VtCommand::RestoreCursor => "DECRC restore-cursor".to_string(),
+        VtCommand::SetCursorStyle(style) => format!("DECSCUSR {}", style),
+        VtCommand::SetCursorColor(color) => {
+            format!("OSC 12 cursor-color #{:02x}{:02x}{:02x}", color.r, color.g, color.b)
+        }
+        VtCommand::QueryCursorColor => "OSC 12 query cursor-color".to_string(),
+        VtCommand::ResetCursorColor => "OSC 112 reset cursor-color".to_string(),
+        VtCommand::ClearScreen(clear_type) => format!("ED {}", clear_extent(*clear_type)),
+        VtCommand::ClearLine(clear_type) => format!("EL {}", clear_extent(*clear_type)),
+        VtCommand::SelectiveClearScreen(clear_type) => format!("DECSED {}", clear_extent(*clear_type)),
+        VtCommand::SelectiveClearLine(clear_type) => format!("DECSEL {}", clear_extent(*clear_type)),
+        VtCommand::InsertLines(n) => format!("IL {}", n),
+        VtCommand::DeleteLines(n) => format!("DL {}", n),
+        VtCommand::InsertChars(n) => format!("ICH {}", n),
+        VtCommand::DeleteChars(n) => format!("DCH {}", n),
+        VtCommand::ReverseIndex => "RI".to_string(),
+        VtCommand::SetLineSize(size) => match size {
+            LineSize::Single => "DECSWL single-width".to_string(),
+            LineSize::DoubleWidth => "DECDWL double-width".to_string(),
+            LineSize::DoubleHeightTop => "DECDHL double-height top".to_string(),
+            LineSize::DoubleHeightBottom => "DECDHL double-height bottom".to_string(),
+        },
+        VtCommand::ScreenAlignmentTest => "DECALN".to_string(),
+        VtCommand::DesignateCharacterSet(slot, set) => format!("SCS {:?}={:?}", slot, set),
+        VtCommand::InvokeCharacterSet(slot) => format!("invoke {:?}", slot),
+        VtCommand::SetTabStop => "HTS".to_string(),
+        VtCommand::ClearTabStop => "TBC".to_string(),
+        VtCommand::ClearAllTabStops => "TBC all".to_string(),
+        VtCommand::CopyRectangle(params) => format!("DECCRA {}", join(params)),
+        VtCommand::FillRectangle(params) => format!("DECFRA {}", join(params)),
+        VtCommand::EraseRectangle(params) => format!("DECERA {}", join(params)),
+        VtCommand::SelectiveEraseRectangle(params) => format!("DECSERA {}", join(params)),
+        VtCommand::ChangeRectangleAttributes(params) => format!("DECCARA {}", join(params)),
+        VtCommand::ReverseRectangleAttributes(params) => format!("DECRARA {}", join(params)),
+        VtCommand::SetAttributeChangeExtent(extent) => format!("DECSACE {}", extent),
+        VtCommand::RequestRectangleChecksum(params) => format!("DECRQCRA {}", join(params)),
+        VtCommand::SetScrollMargins(top, bottom) => format!("DECSTBM {};{}", top, bottom),
+        VtCommand::SetLeftRightMargins(left, right) => format!("DECSLRM {};{}", left, right),
+        VtCommand::ScrollUp(n) => format!("SU {}", n),
+        VtCommand::ScrollDown(n) => format!("SD {}", n),
+        VtCommand::SetGraphicsRendition(params) => {
+            let names: Vec<String> = params.iter().map(|group| sgr_name(group)).collect();
+            format!("SGR {}", names.join("+"))
+        }
+        VtCommand::SetCharacterProtection(mode) => format!("DECSCA {}", mode),
+        VtCommand::Reset => "RIS reset".to_string(),
+        VtCommand::SetMode(modes) => format!("SM {}", mode_list(modes, |_| None)),
+        VtCommand::ResetMode(modes) => format!("RM {}", mode_list(modes, |_| None)),
+        VtCommand::DecPrivateModeSet(modes) => format!("DECSET {}", mode_list(modes, private_mode_name)),
+        VtCommand::DecPrivateModeReset(modes) => format!("DECRST {}", mode_list(modes, private_mode_name)),
+        VtCommand::RequestPrivateMode(mode) => format!("DECRQM {}", mode_list(&[*mode], private_mode_name)),
+        VtCommand::SetModifyOtherKeys(level) => format!("XTMODKEYS modifyOtherKeys={}", level),
+        VtCommand::QueryKeyboardFlags => "kitty keyboard query".to_string(),
+        VtCommand::PushKeyboardFlags(flags) => format!("kitty keyboard push {}", flags.bits()),
+        VtCommand::PopKeyboardFlags(count) => format!("kitty keyboard pop {}", count),
+        VtCommand::SetKeyboardFlags(flags, mode) => format!("kitty keyboard {:?} {}", mode, flags.bits()),
+        VtCommand::DeviceStatusReport => "DSR".to_string(),
+        VtCommand::PrimaryDeviceAttributes => "DA1".to_string(),
+        VtCommand::IdentifyVt52 => "DECID (VT52)".to_string(),
+        VtCommand::RequestStatusString(request) => format!("DECRQSS {}", String::from_utf8_lossy(request)),
+        VtCommand::RequestTermcap(request) => format!("XTGETTCAP {}", String::from_utf8_lossy(request)),
+        VtCommand::WindowOperation(operation) => format!("XTWINOPS {:?}", operation),
+        VtCommand::SemanticPrompt(mark) => format!("OSC 133 {:?}", mark),
+        VtCommand::Notify(notification) => format!("notify {:?}", notification.title),
+        VtCommand::Iterm2(command) => match command {
+            Iterm2Command::File(image) => format!("OSC 1337 File {:?}", image.name),
+            other => format!("OSC 1337 {:?}", other),
+        },
+        VtCommand::Sixel { data, .. } => format!("sixel image, {} bytes", data.len()),
+        VtCommand::KittyGraphics(graphics) => format!("kitty graphics {:?}", graphics.action),
+        VtCommand::Custom(_) => "extension sequence".to_string(),
+    }
+}
+
+fn clear_extent(clear_type: ClearType) -> &'static str {
+    match clear_type {
+        ClearType::ToEnd => "below",
+        ClearType::ToBeginning => "above",
+        ClearType::All => "all",
+    }
+}
+
+/// Name an SGR parameter; sub-parameters other than underline styles are shown raw
+fn sgr_name(group: &[u16]) -> String {
+    let name = match group {
+        [4, 0] => "no-underline",
+        [4] | [4, 1] => "underline",
+        [4, 2] => "double-underline",
+        [4, 3] => "curly-underline",
+        [4, 4] => "dotted-underline",
+        [4, 5] => "dashed-underline",
+        [param] => match param {
+            0 => "reset",
+            1 => "bold",
+            2 => "dim",
+            3 => "italic",
+            5 => "blink",
+            6 => "rapid-blink",
+            7 => "reverse",
+            8 => "conceal",
+            9 => "strike",
+            10 => "primary-font",
+            20 => "fraktur",
+            21 => "double-underline",
+            22 => "normal-intensity",
+            23 => "no-italic",
+            24 => "no-underline",
+            25 => "no-blink",
+            27 => "no-reverse",
+            28 => "reveal",
+            29 => "no-strike",
+            39 => "default-fg",
+            49 => "default-bg",
+            51 => "framed",
+            52 => "encircled",
+            53 => "overline",
+            54 => "no-frame",
+            55 => "no-overline",
+            59 => "default-underline-color",
+            73 => "superscript",
+            74 => "subscript",
+            75 => "no-script",
+            11..=19 => return format!("font-{}", param - 10),
+            _ => return param.to_string(),
+        },
+        _ => return group.iter().map(u16::to_string).collect::<Vec<_>>().join(":"),
+    };
+    name.to_string()
+}
+
+/// Modes joined with commas, named where known
+fn mode_list(modes: &[u16], name: impl Fn(u16) -> Option<&'static str>) -> String {
+    let names: Vec<String> = modes
+        .iter()
+        .map(|&mode| name(mode).map(str::to_string).unwrap_or_else(|| mode.to_string()))
+        .collect();
+    names.join(", ")
+}
+
+fn private_mode_name(mode: u16) -> Option<&'static str> {
+    let name = match mode {
+        1 => "app-cursor",
+        2 => "ansi",
+        3 => "132-columns",
+        5 => "reverse-video",
+        6 => "origin",
+        7 => "autowrap",
+        9 => "mouse-x10",
+        12 => "cursor-blink",
+        25 => "show-cursor",
+        47 => "alt-screen",
+        66 => "app-keypad",
+        69 => "lr-margins",
+        1000 => "mouse-click",
+        1002 => "mouse-drag",
+        1003 => "mouse-any",
+        1004 => "focus-events",
+        1005 => "mouse-utf8",
+        1006 => "mouse-sgr",
+        1015 => "mouse-urxvt",
+        1016 => "mouse-sgr-pixels",
+        1047 => "alt-screen",
+        1048 => "save-cursor",
+        1049 => "alt-screen+save-cursor",
+        2004 => "bracketed-paste",
+        2026 => "synchronized-update",
+        _ => return None,
+    };
+    Some(name)
+}
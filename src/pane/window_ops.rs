@@ -0,0 +1,94 @@
+use super::*;
+
+/// Window manipulation a program can request with XTWINOPS; the frame decides whether to carry it out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WindowManipulation {
+    Deiconify,
+    Iconify,
+    Move { x: i32, y: i32 },
+    /// Resize the text area in pixels; 0 keeps the current dimension
+    ResizePixels { width: u32, height: u32 },
+    /// Resize the text area in cells; 0 keeps the current dimension
+    ResizeCells { cols: u16, rows: u16 },
+    Raise,
+    Lower,
+    Maximize(bool),
+    Fullscreen(bool),
+    ToggleFullscreen,
+}
+
+/// An XTWINOPS request (`CSI Ps ; Ps ; Ps t`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WindowOperation {
+    /// Changes to the window, carried out by the frame when allowed
+    Manipulate(WindowManipulation),
+    /// `CSI 11 t` - report whether the window is iconified
+    ReportState,
+    /// `CSI 14 t` - report the text area size in pixels
+    ReportTextAreaPixels,
+    /// `CSI 16 t` - report the cell size in pixels
+    ReportCellPixels,
+    /// `CSI 18 t` - report the text area size in characters
+    ReportTextAreaChars,
+}
+
+impl WindowOperation {
+    /// Parse XTWINOPS parameters; title reports, title stacking and screen queries are not supported
+    pub fn from_params(params: &[i32]) -> Option<Self> {
+        let param = |index: usize| params.get(index).copied().unwrap_or(0).max(0);
+        let manipulation = match param(0) {
+            1 => WindowManipulation::Deiconify,
+            2 => WindowManipulation::Iconify,
+            3 => WindowManipulation::Move { x: param(1), y: param(2) },
+            4 => WindowManipulation::ResizePixels { width: param(2) as u32, height: param(1) as u32 },
+            5 => WindowManipulation::Raise,
+            6 => WindowManipulation::Lower,
+            8 => WindowManipulation::ResizeCells {
+                cols: param(2).min(u16::MAX as i32) as u16,
+                rows: param(1).min(u16::MAX as i32) as u16,
+            },
+            // Vertical and horizontal maximizing (9;2 and 9;3) are not supported
+            9 => match param(1) {
+                0 => WindowManipulation::Maximize(false),
+                1 => WindowManipulation::Maximize(true),
+                _ => return None,
+            },
+            10 => match param(1) {
+                0 => WindowManipulation::Fullscreen(false),
+                1 => WindowManipulation::Fullscreen(true),
+                2 => WindowManipulation::ToggleFullscreen,
+                _ => return None,
+            },
+            11 => return Some(WindowOperation::ReportState),
+            14 if param(1) == 0 => return Some(WindowOperation::ReportTextAreaPixels),
+            16 => return Some(WindowOperation::ReportCellPixels),
+            18 => return Some(WindowOperation::ReportTextAreaChars),
+            _ => return None,
+        };
+        Some(WindowOperation::Manipulate(manipulation))
+    }
+}
+
+impl Pane {
+    /// Answer an XTWINOPS report, or pass a manipulation up to the frame
+    pub(crate) fn handle_window_operation(&mut self, operation: WindowOperation) -> PaneResult<()> {
+        let (cols, rows) = self.size();
+        let (cell_width, cell_height) = self.config.cell_size;
+        let reply = match operation {
+            WindowOperation::Manipulate(manipulation) => {
+                return self.emit_event(PaneEvent::WindowManipulationRequested(manipulation));
+            }
+            // Iconified windows do not render panes, so the answer is always "open"
+            WindowOperation::ReportState => "\x1b[1t".to_string(),
+            WindowOperation::ReportTextAreaPixels => format!(
+                "\x1b[4;{};{}t",
+                rows as u32 * cell_height as u32,
+                cols as u32 * cell_width as u32
+            ),
+            WindowOperation::ReportCellPixels => format!("\x1b[6;{};{}t", cell_height, cell_width),
+            WindowOperation::ReportTextAreaChars => format!("\x1b[8;{};{}t", rows, cols),
+        };
+        self.respond(reply.as_bytes());
+        Ok(())
+    }
+}
@@ -0,0 +1,89 @@
+use super::PlatformInterface;
+use crate::frame::{Notification, NotificationError, Notifier};
+use anyhow::Result;
+use std::process::{Command, Stdio};
+
+pub struct LinuxPlatform {
+    // Linux specific state
+}
+
+impl LinuxPlatform {
+    pub fn new() -> Self {
+        LinuxPlatform {}
+    }
+}
+
+impl PlatformInterface for LinuxPlatform {
+    fn initialize(&self) -> Result<()> {
+        println!("Initializing Linux platform...");
+        Ok(())
+    }
+    
+    fn cleanup(&self) -> Result<()> {
+        println!("Cleaning up Linux platform...");
+        Ok(())
+    }
+    
+    fn terminal_size(&self) -> (u16, u16) {
+        // Default size if unable to determine
+        (80, 24)
+    }
+}
+
+/// Desktop notifications via `notify-send` (libnotify / org.freedesktop.Notifications)
+pub struct NotifySendNotifier {
+    program: String,
+    app_name: String,
+}
+
+impl NotifySendNotifier {
+    pub fn new() -> Self {
+        NotifySendNotifier {
+            program: "notify-send".to_string(),
+            app_name: "ratty".to_string(),
+        }
+    }
+    
+    /// Use a different executable with a notify-send compatible command line
+    pub fn with_program(mut self, program: impl Into<String>) -> Self {
+        self.program = program.into();
+        self
+    }
+    
+    /// Build the argument list for a notification
+    pub fn arguments(&self, notification: &Notification) -> Vec<String> {
+        let summary = if notification.title.is_empty() {
+            self.app_name.clone()
+        } else {
+            notification.title.clone()
+        };
+        
+        let mut args = vec![
+            format!("--app-name={}", self.app_name),
+            "--".to_string(),
+            summary,
+        ];
+        if !notification.body.is_empty() {
+            args.push(notification.body.clone());
+        }
+        args
+    }
+}
+
+impl Notifier for NotifySendNotifier {
+    fn notify(&mut self, notification: &Notification) -> Result<(), NotificationError> {
+        let mut child = Command::new(&self.program)
+            .args(self.arguments(notification))
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| NotificationError::BackendUnavailable(format!("{}: {}", self.program, e)))?;
+        
+        // Reap in the background so a slow notification daemon never blocks rendering
+        std::thread::spawn(move || {
+            let _ = child.wait();
+        });
+        Ok(())
+    }
+}
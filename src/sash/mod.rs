@@ -115,6 +115,19 @@ impl Sash {
     pub fn pane_count(&self) -> usize {
         self.panes.len()
     }
+    
+    /// Track window focus; the active pane's program hears about it via focus reporting
+    fn set_window_focus(&mut self, active: bool) {
+        if self.active == active {
+            return;
+        }
+        self.active = active;
+        
+        if let Some(pane) = self.active_pane_id.and_then(|id| self.panes.get_mut(&id)) {
+            let _ = pane.set_focused(active);
+        }
+        let _ = self.event_handler.dispatch(SashEvent::WindowFocusChanged(active));
+    }
 }
 
 // Basic implementation of SashInterface for Frame compatibility
@@ -128,7 +141,7 @@ impl frame::SashInterface for Sash {
     }
     
     fn set_active(&mut self, active: bool) {
-        self.active = active;
+        self.set_window_focus(active);
     }
 }
 
@@ -145,7 +158,7 @@ impl SashInterface for Sash {
     }
     
     fn set_active(&mut self, active: bool) {
-        self.active = active;
+        self.set_window_focus(active);
     }
     
     // ========== Pane Management ==========
@@ -205,18 +218,25 @@ impl SashInterface for Sash {
         if !self.panes.contains_key(&pane_id) {
             return Err(SashError::PaneNotFound(pane_id));
         }
+        if self.active_pane_id == Some(pane_id) {
+            return Ok(());
+        }
         
         // Deactivate current pane if any
         if let Some(current_pane_id) = self.active_pane_id {
             if let Some(current_pane) = self.panes.get_mut(&current_pane_id) {
                 current_pane.set_active(false);
+                let _ = current_pane.set_focused(false);
             }
+            let _ = self.event_handler.dispatch(SashEvent::PaneUnfocused(current_pane_id));
         }
         
-        // Activate new pane
+        // Activate new pane; it only has keyboard focus while the window does
         if let Some(new_pane) = self.panes.get_mut(&pane_id) {
             new_pane.set_active(true);
+            let _ = new_pane.set_focused(self.active);
             self.active_pane_id = Some(pane_id);
+            let _ = self.event_handler.dispatch(SashEvent::PaneFocused(pane_id));
             Ok(())
         } else {
             Err(SashError::PaneNotFound(pane_id))
@@ -6,6 +6,7 @@ mod sash_tests {
         Layout, Tab, TabManager, TabConfig, LayoutManager, Color, NewTabPosition
     };
     use crate::frame::SashId;
+    use crate::pane::{Pane, PaneInterface, PaneConfig as TerminalConfig};
     
     // Helper function to create a test Sash
     fn create_test_sash() -> Sash {
@@ -301,5 +302,51 @@ mod sash_tests {
         // Invalid hex
         assert!(Color::from_hex("invalid").is_err());
         assert!(Color::from_hex("#12345").is_err());
+    }    
+    // ========== Focus Reporting Tests ==========
+    
+    fn add_test_pane(sash: &mut Sash, id: u64) -> PaneId {
+        let pane_id = PaneId::new(id);
+        let mut pane = Pane::new(pane_id, TerminalConfig::default()).expect("Failed to create test pane");
+        pane.process_output(b"\x1b[?1004h").unwrap();
+        sash.panes.insert(pane_id, Box::new(pane));
+        pane_id
+    }
+    
+    fn take_reports(sash: &mut Sash, pane_id: PaneId) -> Vec<u8> {
+        sash.panes.get_mut(&pane_id).unwrap().take_responses()
+    }
+    
+    #[test]
+    fn test_focus_reporting() {
+        let mut sash = create_test_sash();
+        let first = add_test_pane(&mut sash, 1);
+        let second = add_test_pane(&mut sash, 2);
+        SashInterface::set_active(&mut sash, true);
+        
+        sash.set_active_pane(first).unwrap();
+        assert_eq!(take_reports(&mut sash, first), b"\x1b[I");
+        
+        // Switching panes moves focus, and re-selecting the active pane reports nothing
+        sash.set_active_pane(second).unwrap();
+        sash.set_active_pane(second).unwrap();
+        assert_eq!(take_reports(&mut sash, first), b"\x1b[O");
+        assert_eq!(take_reports(&mut sash, second), b"\x1b[I");
+        
+        sash.focus_next_pane().unwrap();
+        assert_eq!(take_reports(&mut sash, first), b"\x1b[I");
+        assert_eq!(take_reports(&mut sash, second), b"\x1b[O");
+        
+        // Window focus reaches the active pane only
+        crate::frame::SashInterface::set_active(&mut sash, false);
+        crate::frame::SashInterface::set_active(&mut sash, true);
+        assert_eq!(take_reports(&mut sash, first), b"\x1b[O\x1b[I");
+        assert!(take_reports(&mut sash, second).is_empty());
+        
+        // Panes that never enabled mode 1004 are not told
+        sash.panes.get_mut(&first).unwrap().process_output(b"\x1b[?1004l").unwrap();
+        sash.set_active_pane(second).unwrap();
+        assert!(take_reports(&mut sash, first).is_empty());
+        assert_eq!(take_reports(&mut sash, second), b"\x1b[I");
     }
 }
\ No newline at end of file
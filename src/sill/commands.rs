@@ -1,5 +1,6 @@
 use super::*;
 use crate::sash::PaneId;
+use std::collections::HashMap;
 use std::time::Instant;
 
 /// Input commands generated from processed input events
@@ -485,6 +486,8 @@ pub struct InputRouter {
     command_dispatcher: CommandDispatcher,
     event_filter: EventFilter,
     focus_manager: FocusManager,
+    keyboard_flags: HashMap<PaneId, KeyboardFlags>,
+    key_modes: HashMap<PaneId, KeyEncodingModes>,
     command_count: u64,
 }
 
@@ -495,10 +498,40 @@ impl InputRouter {
             command_dispatcher: CommandDispatcher::new(),
             event_filter: EventFilter::new(&config.filters),
             focus_manager: FocusManager::new(),
+            keyboard_flags: HashMap::new(),
+            key_modes: HashMap::new(),
             command_count: 0,
         })
     }
     
+    /// Record the kitty keyboard flags for a pane (empty flags restore legacy encoding)
+    pub fn set_keyboard_flags(&mut self, pane_id: PaneId, flags: KeyboardFlags) {
+        if flags.is_empty() {
+            self.keyboard_flags.remove(&pane_id);
+        } else {
+            self.keyboard_flags.insert(pane_id, flags);
+        }
+    }
+    
+    /// Get the kitty keyboard flags in effect for a pane
+    pub fn keyboard_flags(&self, pane_id: PaneId) -> KeyboardFlags {
+        self.keyboard_flags.get(&pane_id).copied().unwrap_or_default()
+    }
+    
+    /// Record the key encoding modes for a pane
+    pub fn set_key_encoding_modes(&mut self, pane_id: PaneId, modes: KeyEncodingModes) {
+        if modes == KeyEncodingModes::default() {
+            self.key_modes.remove(&pane_id);
+        } else {
+            self.key_modes.insert(pane_id, modes);
+        }
+    }
+    
+    /// Get the key encoding modes in effect for a pane
+    pub fn key_encoding_modes(&self, pane_id: PaneId) -> KeyEncodingModes {
+        self.key_modes.get(&pane_id).copied().unwrap_or_default()
+    }
+    
     /// Route a key event to commands
     pub fn route_key_event(
         &mut self,
@@ -511,117 +544,42 @@ impl InputRouter {
         }
         
         let target = self.determine_target(focus);
-        let mut commands = Vec::new();
-
-        let target_clone = target.clone();
         
-        match event.key {
-            Key::Character(c) => {
-                commands.push(InputCommand::InsertText {
-                    text: c.to_string(),
-                    target,
-                });
-            }
-            Key::ArrowUp => {
-                commands.push(InputCommand::MoveCursor {
-                    direction: CursorDirection::Up,
-                    amount: 1,
-                    target,
-                });
-            }
-            Key::ArrowDown => {
-                commands.push(InputCommand::MoveCursor {
-                    direction: CursorDirection::Down,
-                    amount: 1,
-                    target,
-                });
-            }
-            Key::ArrowLeft => {
-                commands.push(InputCommand::MoveCursor {
-                    direction: CursorDirection::Left,
-                    amount: 1,
-                    target,
-                });
-            }
-            Key::ArrowRight => {
-                commands.push(InputCommand::MoveCursor {
-                    direction: CursorDirection::Right,
-                    amount: 1,
-                    target,
-                });
-            }
-            Key::PageUp => {
-                commands.push(InputCommand::Scroll {
-                    direction: ScrollDirection::PageUp,
-                    amount: 1,
-                    target,
-                });
-            }
-            Key::PageDown => {
-                commands.push(InputCommand::Scroll {
-                    direction: ScrollDirection::PageDown,
-                    amount: 1,
-                    target,
-                });
-            }
-            Key::Home => {
-                commands.push(InputCommand::MoveCursor {
-                    direction: CursorDirection::LineStart,
-                    amount: 1,
-                    target,
-                });
-            }
-            Key::End => {
-                commands.push(InputCommand::MoveCursor {
-                    direction: CursorDirection::LineEnd,
-                    amount: 1,
-                    target,
-                });
-            }
-            Key::Enter => {
-                commands.push(InputCommand::InsertText {
-                    text: "\n".to_string(),
-                    target,
-                });
-            }
-            Key::Tab => {
-                commands.push(InputCommand::InsertText {
-                    text: "\t".to_string(),
-                    target,
-                });
-            }
-            Key::Backspace => {
-                commands.push(InputCommand::SendSequence {
-                    sequence: "\x08".to_string(),
-                    target,
-                });
-            }
-            Key::Delete => {
-                commands.push(InputCommand::SendSequence {
-                    sequence: "\x7f".to_string(),
-                    target,
-                });
-            }
-            Key::Escape => {
-                commands.push(InputCommand::SendSequence {
-                    sequence: "\x1b".to_string(),
-                    target,
-                });
-            }
-            _ => {
-                // Handle other keys or key combinations
-                if let Some(sequence) = self.key_to_sequence(&event.key) {
-                    commands.push(InputCommand::SendSequence {
-                        sequence,
-                        target,
-                    });
-                }
+        // Programs that opted into the kitty protocol get CSI u encodings only
+        if let CommandTarget::Pane(pane_id) = target {
+            let flags = self.keyboard_flags(pane_id);
+            if !flags.is_empty() {
+                let commands: Vec<InputCommand> = KittyKeyEncoder::new(flags)
+                    .encode(&event)
+                    .map(|sequence| InputCommand::SendSequence { sequence, target })
+                    .into_iter()
+                    .collect();
+                self.command_count += commands.len() as u64;
+                return Ok(commands);
             }
         }
         
-        // Apply modifier-based commands
-        if event.modifiers.ctrl {
-            commands.extend(self.handle_ctrl_combinations(&event, target_clone)?);
+        let mut commands = Vec::new();
+        
+        if let Some(command) = self.terminal_binding(&event, target.clone()) {
+            // Bindings the terminal keeps for itself never reach the program
+            commands.push(command);
+        } else {
+            let modes = match target {
+                CommandTarget::Pane(pane_id) => self.key_encoding_modes(pane_id),
+                _ => KeyEncodingModes::default(),
+            };
+            let encoder = XtermKeyEncoder::new(modes).with_alt_sends_escape(self.config.alt_sends_escape);
+            
+            if let Some(sequence) = encoder.encode(&event) {
+                let m = &event.modifiers;
+                let plain_text = matches!(event.key, Key::Character(_)) && !m.ctrl && !m.alt && !m.meta;
+                if plain_text {
+                    commands.push(InputCommand::InsertText { text: sequence, target });
+                } else {
+                    commands.push(InputCommand::SendSequence { sequence, target });
+                }
+            }
         }
         
         self.command_count += commands.len() as u64;
@@ -639,6 +597,13 @@ impl InputRouter {
         }
         
         let target = self.determine_target(focus);
+        
+        // The program asked for the mouse: send it the report instead of acting locally
+        if let Some(sequence) = event.terminal_sequence {
+            self.command_count += 1;
+            return Ok(vec![InputCommand::SendSequence { sequence, target }]);
+        }
+        
         let mut commands = Vec::new();
         
         match event.event_type {
@@ -746,41 +711,39 @@ impl InputRouter {
         }
     }
     
-    /// Convert key to terminal sequence
-    fn key_to_sequence(&self, key: &Key) -> Option<String> {
-        match key {
-            Key::Function(n) => Some(format!("\x1b[{}~", n + 10)),
-            Key::ApplicationCursorUp => Some("\x1bOA".to_string()),
-            Key::ApplicationCursorDown => Some("\x1bOB".to_string()),
-            Key::ApplicationCursorRight => Some("\x1bOC".to_string()),
-            Key::ApplicationCursorLeft => Some("\x1bOD".to_string()),
-            _ => None,
+    /// Terminal shortcuts: Ctrl+Shift+C/V/X/A for the clipboard, Shift+PgUp/PgDn for scrollback
+    fn terminal_binding(&self, event: &KeyEvent, target: CommandTarget) -> Option<InputCommand> {
+        if event.state == KeyState::Release {
+            return None;
         }
-    }
-    
-    /// Handle Ctrl key combinations
-    fn handle_ctrl_combinations(
-        &self,
-        event: &KeyEvent,
-        target: CommandTarget,
-    ) -> SillResult<Vec<InputCommand>> {
-        let mut commands = Vec::new();
         
-        if let Key::Character(c) = event.key {
-            match c.to_ascii_lowercase() {
-                'c' => commands.push(InputCommand::Copy { target }),
-                'v' => commands.push(InputCommand::Paste { target }),
-                'x' => commands.push(InputCommand::Cut { target }),
-                'a' => commands.push(InputCommand::StartSelection {
-                    position: SelectionPosition { row: 0, col: 0 },
-                    mode: SelectionMode::All,
-                    target,
-                }),
-                _ => {}
+        let m = &event.modifiers;
+        if m.matches(true, false, true, false) {
+            if let Key::Character(c) = event.key {
+                return match c.to_ascii_lowercase() {
+                    'c' => Some(InputCommand::Copy { target }),
+                    'v' => Some(InputCommand::Paste { target }),
+                    'x' => Some(InputCommand::Cut { target }),
+                    'a' => Some(InputCommand::StartSelection {
+                        position: SelectionPosition { row: 0, col: 0 },
+                        mode: SelectionMode::All,
+                        target,
+                    }),
+                    _ => None,
+                };
             }
         }
         
-        Ok(commands)
+        if m.matches(false, false, true, false) {
+            let direction = match event.key {
+                Key::PageUp => ScrollDirection::PageUp,
+                Key::PageDown => ScrollDirection::PageDown,
+                _ => return None,
+            };
+            return Some(InputCommand::Scroll { direction, amount: 1, target });
+        }
+        
+        None
     }
 }
 
@@ -990,6 +953,8 @@ pub struct RoutingConfig {
     pub max_input_length: usize,
     pub enable_command_queuing: bool,
     pub command_timeout: Duration,
+    /// Alt prefixes keys with ESC (xterm's altSendsEscape); otherwise Alt is dropped
+    pub alt_sends_escape: bool,
 }
 
 impl Default for RoutingConfig {
@@ -999,6 +964,7 @@ impl Default for RoutingConfig {
             max_input_length: 1024,
             enable_command_queuing: true,
             command_timeout: Duration::from_millis(100),
+            alt_sends_escape: true,
         }
     }
 }
\ No newline at end of file
@@ -1,4 +1,5 @@
 use super::*;
+use crate::pane::KeyboardFlags;
 use crate::sash::PaneId;
 
 /// Main interface trait for the Sill layer
@@ -22,6 +23,15 @@ pub trait SillInterface: Send + Sync {
     /// Set focus to a specific pane
     fn set_focus(&mut self, pane_id: Option<PaneId>) -> SillResult<()>;
     
+    /// Update the kitty keyboard flags requested by a pane
+    fn set_keyboard_flags(&mut self, pane_id: PaneId, flags: KeyboardFlags) -> SillResult<()>;
+    
+    /// Update the key encoding modes (DECCKM, DECKPAM, modifyOtherKeys) of a pane
+    fn set_key_encoding_modes(&mut self, pane_id: PaneId, modes: KeyEncodingModes) -> SillResult<()>;
+    
+    /// Update the mouse reporting modes (DECSET 9/1000-1016) of a pane
+    fn set_mouse_state(&mut self, pane_id: PaneId, state: PaneMouseState) -> SillResult<()>;
+    
     /// Start text selection
     fn start_selection(&mut self, position: SelectionPosition, mode: SelectionMode) -> SillResult<()>;
     
@@ -73,6 +83,18 @@ impl SillInterface for SillEngine {
         SillEngine::set_focus(self, pane_id)
     }
     
+    fn set_keyboard_flags(&mut self, pane_id: PaneId, flags: KeyboardFlags) -> SillResult<()> {
+        SillEngine::set_keyboard_flags(self, pane_id, flags)
+    }
+    
+    fn set_key_encoding_modes(&mut self, pane_id: PaneId, modes: KeyEncodingModes) -> SillResult<()> {
+        SillEngine::set_key_encoding_modes(self, pane_id, modes)
+    }
+    
+    fn set_mouse_state(&mut self, pane_id: PaneId, state: PaneMouseState) -> SillResult<()> {
+        SillEngine::set_mouse_state(self, pane_id, state)
+    }
+    
     fn start_selection(&mut self, position: SelectionPosition, mode: SelectionMode) -> SillResult<()> {
         SillEngine::start_selection(self, position, mode)
     }
@@ -224,6 +246,18 @@ impl SillInterface for MockSillInterface {
         Ok(())
     }
     
+    fn set_keyboard_flags(&mut self, _pane_id: PaneId, _flags: KeyboardFlags) -> SillResult<()> {
+        Ok(())
+    }
+    
+    fn set_key_encoding_modes(&mut self, _pane_id: PaneId, _modes: KeyEncodingModes) -> SillResult<()> {
+        Ok(())
+    }
+    
+    fn set_mouse_state(&mut self, _pane_id: PaneId, _state: PaneMouseState) -> SillResult<()> {
+        Ok(())
+    }
+    
     fn start_selection(&mut self, position: SelectionPosition, mode: SelectionMode) -> SillResult<()> {
         self.current_selection = Some(Selection {
             start: position,
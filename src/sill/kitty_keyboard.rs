@@ -0,0 +1,192 @@
+use super::*;
+use crate::pane::KeyboardFlags;
+
+/// Encoder for the kitty keyboard protocol, driven by a pane's enhancement flags
+#[derive(Debug, Clone, Copy)]
+pub struct KittyKeyEncoder {
+    flags: KeyboardFlags,
+}
+
+/// How a key is identified on the wire
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum KittyKeyCode {
+    /// `CSI code ... u`
+    Unicode(u32),
+    /// `CSI number ; modifiers final` (arrows, F1-F12, editing keys)
+    Functional(u32, char),
+}
+
+impl KittyKeyEncoder {
+    pub fn new(flags: KeyboardFlags) -> Self {
+        KittyKeyEncoder { flags }
+    }
+    
+    /// Encode a key event, or `None` if nothing should be sent
+    pub fn encode(&self, event: &KeyEvent) -> Option<String> {
+        let report_all = self.flags.contains(KeyboardFlags::REPORT_ALL_KEYS);
+        let report_events = self.flags.contains(KeyboardFlags::REPORT_EVENT_TYPES);
+        let release = event.state == KeyState::Release;
+        if release && !report_events {
+            return None;
+        }
+        
+        // Keys that still produce plain text or legacy bytes are never reported on release
+        if !report_all {
+            if let Some(text) = self.legacy_bytes(event) {
+                return if release { None } else { Some(text) };
+            }
+        }
+        
+        let code = self.key_code(event.key, report_all)?;
+        let modifiers = modifier_bits(&event.modifiers);
+        let event_type = match event.state {
+            KeyState::Press => 1,
+            KeyState::Repeat => 2,
+            KeyState::Release => 3,
+        };
+        
+        let mut fields = String::new();
+        if modifiers != 0 || (report_events && event_type != 1) {
+            fields.push_str(&(modifiers + 1).to_string());
+            if report_events && event_type != 1 {
+                fields.push_str(&format!(":{}", event_type));
+            }
+        }
+        
+        match code {
+            KittyKeyCode::Functional(number, final_byte) => {
+                if fields.is_empty() {
+                    let number = if number == 1 { String::new() } else { number.to_string() };
+                    Some(format!("\x1b[{}{}", number, final_byte))
+                } else {
+                    Some(format!("\x1b[{};{}{}", number, fields, final_byte))
+                }
+            }
+            KittyKeyCode::Unicode(code) => {
+                let mut key = code.to_string();
+                if let Some(shifted) = self.shifted_key(event, code) {
+                    key.push_str(&format!(":{}", shifted));
+                }
+                
+                match self.associated_text(event, release) {
+                    Some(text) => Some(format!("\x1b[{};{};{}u", key, fields, text)),
+                    None if fields.is_empty() => Some(format!("\x1b[{}u", key)),
+                    None => Some(format!("\x1b[{};{}u", key, fields)),
+                }
+            }
+        }
+    }
+    
+    /// Bytes sent unchanged when keys are only being disambiguated
+    fn legacy_bytes(&self, event: &KeyEvent) -> Option<String> {
+        let m = &event.modifiers;
+        match event.key {
+            Key::Character(_) | Key::Space if !m.ctrl && !m.alt && !m.meta => text_of(event),
+            Key::Enter if !m.any() => Some("\r".to_string()),
+            Key::Tab if !m.any() => Some("\t".to_string()),
+            Key::Backspace if !m.any() => Some("\x7f".to_string()),
+            _ => None,
+        }
+    }
+    
+    fn key_code(&self, key: Key, report_all: bool) -> Option<KittyKeyCode> {
+        use KittyKeyCode::{Functional, Unicode};
+        
+        let code = match key {
+            Key::Character(c) => Unicode(c.to_lowercase().next().unwrap_or(c) as u32),
+            Key::Space => Unicode(32),
+            Key::Enter => Unicode(13),
+            Key::Tab => Unicode(9),
+            Key::Backspace => Unicode(127),
+            Key::Escape => Unicode(27),
+            
+            Key::ArrowUp | Key::ApplicationCursorUp => Functional(1, 'A'),
+            Key::ArrowDown | Key::ApplicationCursorDown => Functional(1, 'B'),
+            Key::ArrowRight | Key::ApplicationCursorRight => Functional(1, 'C'),
+            Key::ArrowLeft | Key::ApplicationCursorLeft => Functional(1, 'D'),
+            Key::Home => Functional(1, 'H'),
+            Key::End => Functional(1, 'F'),
+            Key::Insert => Functional(2, '~'),
+            Key::Delete => Functional(3, '~'),
+            Key::PageUp => Functional(5, '~'),
+            Key::PageDown => Functional(6, '~'),
+            
+            Key::Function(n) => match n {
+                1 => Functional(1, 'P'),
+                2 => Functional(1, 'Q'),
+                3 => Functional(13, '~'),
+                4 => Functional(1, 'S'),
+                5 => Functional(15, '~'),
+                6..=10 => Functional(n as u32 + 11, '~'),
+                11 | 12 => Functional(n as u32 + 12, '~'),
+                13..=35 => Unicode(57376 + n as u32 - 13),
+                _ => return None,
+            },
+            
+            Key::KeypadNumber(digit) if digit <= 9 => Unicode(57399 + digit as u32),
+            Key::KeypadDecimal => Unicode(57409),
+            Key::KeypadDivide => Unicode(57410),
+            Key::KeypadMultiply => Unicode(57411),
+            Key::KeypadMinus => Unicode(57412),
+            Key::KeypadPlus => Unicode(57413),
+            Key::KeypadEnter => Unicode(57414),
+            
+            Key::ScrollLock => Unicode(57359),
+            Key::PrintScreen => Unicode(57361),
+            Key::Pause => Unicode(57362),
+            Key::Menu => Unicode(57363),
+            Key::VolumeDown => Unicode(57438),
+            Key::VolumeUp => Unicode(57439),
+            Key::VolumeMute => Unicode(57440),
+            
+            // Lone modifier presses are only reported when every key is
+            Key::Shift if report_all => Unicode(57441),
+            Key::Control if report_all => Unicode(57442),
+            Key::Alt if report_all => Unicode(57443),
+            Key::Meta if report_all => Unicode(57444),
+            
+            _ => return None,
+        };
+        Some(code)
+    }
+    
+    /// The shifted key, when alternate keys are requested and shift changes it
+    fn shifted_key(&self, event: &KeyEvent, code: u32) -> Option<u32> {
+        if !self.flags.contains(KeyboardFlags::REPORT_ALTERNATE_KEYS) || !event.modifiers.shift {
+            return None;
+        }
+        let shifted = match event.key {
+            Key::Character(c) => event.character.filter(|ch| !ch.is_control()).unwrap_or(c),
+            _ => return None,
+        };
+        (shifted as u32 != code).then_some(shifted as u32)
+    }
+    
+    /// Text codepoints, reported alongside the key when every key is an escape code
+    fn associated_text(&self, event: &KeyEvent, release: bool) -> Option<String> {
+        let wanted = KeyboardFlags::REPORT_ALL_KEYS | KeyboardFlags::REPORT_ASSOCIATED_TEXT;
+        if !self.flags.contains(wanted) || release || event.modifiers.ctrl || event.modifiers.alt {
+            return None;
+        }
+        let text = text_of(event)?;
+        Some(text.chars().map(|ch| (ch as u32).to_string()).collect::<Vec<_>>().join(":"))
+    }
+}
+
+/// The printable text a key produces, if any
+fn text_of(event: &KeyEvent) -> Option<String> {
+    let ch = match event.key {
+        Key::Character(c) => event.character.unwrap_or(c),
+        Key::Space => ' ',
+        _ => return None,
+    };
+    (!ch.is_control()).then(|| ch.to_string())
+}
+
+/// Modifier bits as used by the protocol (shift 1, alt 2, ctrl 4, super 8)
+fn modifier_bits(modifiers: &Modifiers) -> u32 {
+    (modifiers.shift as u32)
+        | (modifiers.alt as u32) << 1
+        | (modifiers.ctrl as u32) << 2
+        | (modifiers.meta as u32) << 3
+}
@@ -1,5 +1,7 @@
 pub mod events;
 pub mod keyboard;
+pub mod kitty_keyboard;
+pub mod xterm_keyboard;
 pub mod mouse;
 pub mod clipboard;
 pub mod selection;
@@ -13,6 +15,8 @@ mod tests;
 
 pub use events::*;
 pub use keyboard::*;
+pub use kitty_keyboard::*;
+pub use xterm_keyboard::*;
 pub use mouse::*;
 pub use clipboard::*;
 pub use selection::*;
@@ -21,7 +25,7 @@ pub use interface::*;
 pub use errors::*;
 pub use config::*;
 
-use crate::pane::CursorPosition;
+use crate::pane::{CursorPosition, KeyboardFlags};
 use crate::sash::PaneId;
 use std::time::{Duration, Instant};
 
@@ -96,14 +100,14 @@ impl SillEngine {
         // Normalize the mouse event
         let normalized_event = self.mouse_processor.normalize_event(event)?;
         
-        // Check if this affects selection
-        if self.mouse_processor.affects_selection(&normalized_event) {
+        // Process through mouse processor, encoding a report if the focused pane tracks the mouse
+        let mouse_event = self.mouse_processor.process_event(normalized_event.clone(), self.current_focus)?;
+        
+        // Reported events belong to the program; only the rest drive selection
+        if mouse_event.terminal_sequence.is_none() && self.mouse_processor.affects_selection(&normalized_event) {
             self.selection_engine.handle_mouse_event(&normalized_event)?;
         }
         
-        // Process through mouse processor
-        let mouse_event = self.mouse_processor.process_event(normalized_event)?;
-        
         // Route through input router
         let commands = self.input_router.route_mouse_event(mouse_event.clone(), self.current_focus)?;
         
@@ -158,6 +162,24 @@ impl SillEngine {
         Ok(())
     }
     
+    /// Record the kitty keyboard flags a pane's program has requested
+    pub fn set_keyboard_flags(&mut self, pane_id: PaneId, flags: KeyboardFlags) -> SillResult<()> {
+        self.input_router.set_keyboard_flags(pane_id, flags);
+        Ok(())
+    }
+    
+    /// Record the cursor, keypad and modifyOtherKeys modes of a pane
+    pub fn set_key_encoding_modes(&mut self, pane_id: PaneId, modes: KeyEncodingModes) -> SillResult<()> {
+        self.input_router.set_key_encoding_modes(pane_id, modes);
+        Ok(())
+    }
+    
+    /// Record the mouse tracking, encoding and scroll modes of a pane
+    pub fn set_mouse_state(&mut self, pane_id: PaneId, state: PaneMouseState) -> SillResult<()> {
+        self.mouse_processor.set_pane_mouse_state(pane_id, state);
+        Ok(())
+    }
+    
     /// Set input mode
     pub fn set_input_mode(&mut self, mode: InputMode) -> SillResult<()> {
         let old_mode = self.input_mode;
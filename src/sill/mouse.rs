@@ -1,4 +1,6 @@
 use super::*;
+use crate::pane::{MouseEncoding, MouseTracking, TerminalModes};
+use std::collections::HashMap;
 use std::time::Instant;
 
 /// Mouse input processor
@@ -55,6 +57,7 @@ impl MouseProcessor {
         
         Ok(NormalizedMouseEvent {
             position,
+            pixel_position: (raw_event.position.0.max(0) as u32, raw_event.position.1.max(0) as u32),
             button,
             event_type,
             modifiers,
@@ -64,19 +67,15 @@ impl MouseProcessor {
         })
     }
     
-    /// Process a normalized mouse event
-    pub fn process_event(&mut self, normalized_event: NormalizedMouseEvent) -> SillResult<MouseEvent> {
+    /// Process a normalized mouse event aimed at `pane`
+    pub fn process_event(&mut self, normalized_event: NormalizedMouseEvent, pane: Option<PaneId>) -> SillResult<MouseEvent> {
         self.processed_count += 1;
         
         // Apply mouse mode transformations
         let transformed_event = self.mouse_mode_manager.transform_event(normalized_event)?;
         
-        // Generate appropriate terminal mouse sequences if needed
-        let terminal_sequence = if self.mouse_mode_manager.should_report_to_terminal() {
-            Some(self.generate_terminal_sequence(&transformed_event)?)
-        } else {
-            None
-        };
+        // Bytes for the program when it tracks the mouse (or wants wheel-as-arrows)
+        let terminal_sequence = self.mouse_mode_manager.report(pane, &transformed_event);
         
         Ok(MouseEvent {
             position: transformed_event.position,
@@ -108,6 +107,16 @@ impl MouseProcessor {
         Ok(())
     }
     
+    /// Record the mouse modes a pane's program has requested
+    pub fn set_pane_mouse_state(&mut self, pane_id: PaneId, state: PaneMouseState) {
+        self.mouse_mode_manager.set_pane_state(pane_id, state);
+    }
+    
+    /// Get the mouse modes in effect for a pane
+    pub fn pane_mouse_state(&self, pane_id: PaneId) -> PaneMouseState {
+        self.mouse_mode_manager.pane_state(pane_id)
+    }
+    
     /// Update configuration
     pub fn update_config(&mut self, config: &MouseConfig) -> SillResult<()> {
         self.config = config.clone();
@@ -153,36 +162,6 @@ impl MouseProcessor {
         
         Ok(click_count)
     }
-    
-    /// Generate terminal mouse sequence for reporting to application
-    fn generate_terminal_sequence(&self, event: &NormalizedMouseEvent) -> SillResult<String> {
-        // Generate SGR (1006) mode mouse sequence
-        let button_code = match (event.button, event.event_type) {
-            (MouseButton::Left, MouseEventType::Press) => 0,
-            (MouseButton::Middle, MouseEventType::Press) => 1,
-            (MouseButton::Right, MouseEventType::Press) => 2,
-            (MouseButton::Left, MouseEventType::Release) => 0,
-            (MouseButton::Middle, MouseEventType::Release) => 1,
-            (MouseButton::Right, MouseEventType::Release) => 2,
-            (MouseButton::WheelUp, MouseEventType::Scroll) => 64,
-            (MouseButton::WheelDown, MouseEventType::Scroll) => 65,
-            _ => 0,
-        };
-        
-        let mut modifiers = 0;
-        if event.modifiers.shift { modifiers += 4; }
-        if event.modifiers.alt { modifiers += 8; }
-        if event.modifiers.ctrl { modifiers += 16; }
-        
-        let final_code = button_code + modifiers;
-        let release_char = if event.event_type == MouseEventType::Release { 'm' } else { 'M' };
-        
-        Ok(format!("\x1b[<{};{};{}{}", 
-                  final_code, 
-                  event.position.col + 1, 
-                  event.position.row + 1, 
-                  release_char))
-    }
 }
 
 /// Event translator for platform-specific mouse events
@@ -265,6 +244,8 @@ pub struct MouseModeManager {
     current_mode: MouseMode,
     input_mode: InputMode,
     tracking_enabled: bool,
+    /// Modes requested by the program in each pane
+    pane_states: HashMap<PaneId, PaneMouseState>,
 }
 
 impl MouseModeManager {
@@ -273,14 +254,71 @@ impl MouseModeManager {
             current_mode: MouseMode::Normal,
             input_mode: InputMode::Normal,
             tracking_enabled: false,
+            pane_states: HashMap::new(),
+        }
+    }
+    
+    /// Record a pane's mouse modes (the default state forgets the pane)
+    pub fn set_pane_state(&mut self, pane_id: PaneId, state: PaneMouseState) {
+        if state == PaneMouseState::default() {
+            self.pane_states.remove(&pane_id);
+        } else {
+            self.pane_states.insert(pane_id, state);
         }
     }
     
+    pub fn pane_state(&self, pane_id: PaneId) -> PaneMouseState {
+        self.pane_states.get(&pane_id).copied().unwrap_or_default()
+    }
+    
+    /// Bytes to send the program for an event, or `None` if it is handled locally
+    pub fn report(&self, pane: Option<PaneId>, event: &NormalizedMouseEvent) -> Option<String> {
+        // Shift always bypasses reporting so text can still be selected
+        if event.modifiers.shift {
+            return None;
+        }
+        
+        let state = self.effective_state(pane);
+        if state.mode.reports_mouse() {
+            return encode_mouse_report(&state, event);
+        }
+        
+        // Alternate scroll: the wheel scrolls full-screen programs with cursor keys
+        if event.event_type == MouseEventType::Scroll && state.alternate_scroll && state.alternate_screen {
+            let up = wheel_button(event) == Some(MouseButton::WheelUp);
+            let final_char = if up { 'A' } else { 'B' };
+            let key = if state.application_cursor {
+                format!("\x1bO{}", final_char)
+            } else {
+                format!("\x1b[{}", final_char)
+            };
+            let lines = (event.scroll_delta.1.abs() as usize).max(1);
+            return Some(key.repeat(lines));
+        }
+        None
+    }
+    
+    /// A pane's own modes, falling back to the global mode in application input mode
+    fn effective_state(&self, pane: Option<PaneId>) -> PaneMouseState {
+        if let Some(state) = pane.and_then(|pane_id| self.pane_states.get(&pane_id)) {
+            return *state;
+        }
+        if self.should_report_to_terminal() {
+            return PaneMouseState {
+                mode: self.current_mode,
+                encoding: MouseEncoding::Sgr,
+                ..Default::default()
+            };
+        }
+        PaneMouseState::default()
+    }
+    
     /// Set mouse mode
     pub fn set_mouse_mode(&mut self, mode: MouseMode) {
         self.current_mode = mode;
         self.tracking_enabled = matches!(mode, 
             MouseMode::ButtonTracking | 
+            MouseMode::DragTracking |
             MouseMode::AnyEventTracking |
             MouseMode::FocusTracking
         );
@@ -312,6 +350,8 @@ impl MouseModeManager {
 #[derive(Debug, Clone)]
 pub struct NormalizedMouseEvent {
     pub position: MousePosition,
+    /// Position in pixels within the pane, for SGR-pixel reports
+    pub pixel_position: (u32, u32),
     pub button: MouseButton,
     pub event_type: MouseEventType,
     pub modifiers: Modifiers,
@@ -366,20 +406,156 @@ pub enum MouseEventType {
 }
 
 /// Mouse modes for terminal compatibility
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
 pub enum MouseMode {
     /// Normal mode - no reporting to terminal
+    #[default]
     Normal,
     /// X10 compatibility mode - button press only
     X10,
     /// Button tracking mode - press and release
     ButtonTracking,
+    /// Button event tracking mode - also motion while a button is held
+    DragTracking,
     /// Any event tracking mode - all mouse events
     AnyEventTracking,
     /// Focus tracking mode - focus in/out events
     FocusTracking,
 }
 
+impl MouseMode {
+    /// Whether mouse events are reported to the program in this mode
+    pub fn reports_mouse(self) -> bool {
+        !matches!(self, MouseMode::Normal | MouseMode::FocusTracking)
+    }
+    
+    /// Whether an event of this type is reported in this mode
+    fn reports(self, event_type: MouseEventType) -> bool {
+        match self {
+            MouseMode::X10 => matches!(event_type, MouseEventType::Press | MouseEventType::Scroll),
+            MouseMode::ButtonTracking => !matches!(event_type, MouseEventType::Drag | MouseEventType::Move),
+            MouseMode::DragTracking => event_type != MouseEventType::Move,
+            MouseMode::AnyEventTracking => true,
+            MouseMode::Normal | MouseMode::FocusTracking => false,
+        }
+    }
+}
+
+impl From<MouseTracking> for MouseMode {
+    fn from(tracking: MouseTracking) -> Self {
+        match tracking {
+            MouseTracking::Off => MouseMode::Normal,
+            MouseTracking::X10 => MouseMode::X10,
+            MouseTracking::Normal => MouseMode::ButtonTracking,
+            MouseTracking::ButtonEvent => MouseMode::DragTracking,
+            MouseTracking::AnyEvent => MouseMode::AnyEventTracking,
+        }
+    }
+}
+
+/// Mouse state of one pane, mirrored from the modes its program set
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PaneMouseState {
+    pub mode: MouseMode,
+    pub encoding: MouseEncoding,
+    /// 1007 - the wheel sends cursor keys on the alternate screen
+    pub alternate_scroll: bool,
+    pub alternate_screen: bool,
+    /// DECCKM, so alternate scroll sends the right cursor keys
+    pub application_cursor: bool,
+}
+
+impl From<&TerminalModes> for PaneMouseState {
+    fn from(modes: &TerminalModes) -> Self {
+        PaneMouseState {
+            mode: modes.mouse.tracking.into(),
+            encoding: modes.mouse.encoding,
+            alternate_scroll: modes.mouse.alternate_scroll,
+            alternate_screen: modes.alternate_screen,
+            application_cursor: modes.application_cursor,
+        }
+    }
+}
+
+/// The wheel button of a scroll event, from the button or the scroll delta
+fn wheel_button(event: &NormalizedMouseEvent) -> Option<MouseButton> {
+    match event.button {
+        MouseButton::WheelUp | MouseButton::WheelDown | MouseButton::WheelLeft | MouseButton::WheelRight => {
+            Some(event.button)
+        }
+        _ if event.scroll_delta.1 > 0.0 => Some(MouseButton::WheelUp),
+        _ if event.scroll_delta.1 < 0.0 => Some(MouseButton::WheelDown),
+        _ if event.scroll_delta.0 < 0.0 => Some(MouseButton::WheelLeft),
+        _ if event.scroll_delta.0 > 0.0 => Some(MouseButton::WheelRight),
+        _ => None,
+    }
+}
+
+/// Encode a mouse event the way xterm reports it, if the pane's mode reports it
+fn encode_mouse_report(state: &PaneMouseState, event: &NormalizedMouseEvent) -> Option<String> {
+    if !state.mode.reports(event.event_type) {
+        return None;
+    }
+    
+    let button = match event.event_type {
+        MouseEventType::Scroll => wheel_button(event)?,
+        _ => event.button,
+    };
+    let mut code: u32 = match button {
+        MouseButton::Left => 0,
+        MouseButton::Middle => 1,
+        MouseButton::Right => 2,
+        MouseButton::WheelUp => 64,
+        MouseButton::WheelDown => 65,
+        MouseButton::WheelLeft => 66,
+        MouseButton::WheelRight => 67,
+        MouseButton::Back => 128,
+        MouseButton::Forward => 129,
+        MouseButton::Other(_) => return None,
+    };
+    
+    let release = event.event_type == MouseEventType::Release;
+    if release && code >= 64 {
+        return None; // The wheel has no release
+    }
+    match event.event_type {
+        MouseEventType::Drag => code += 32,
+        MouseEventType::Move => code = 35, // Motion with no button held
+        _ => {}
+    }
+    
+    // X10 mode reports no modifiers
+    if state.mode != MouseMode::X10 {
+        let m = &event.modifiers;
+        code += (m.shift as u32) * 4 + ((m.alt || m.meta) as u32) * 8 + (m.ctrl as u32) * 16;
+    }
+    
+    let col = event.position.col as u32 + 1;
+    let row = event.position.row as u32 + 1;
+    let sgr_final = if release { 'm' } else { 'M' };
+    // Only SGR can say which button was released
+    let legacy_code = if release { 3 + (code & !3) } else { code };
+    
+    match state.encoding {
+        MouseEncoding::Sgr => Some(format!("\x1b[<{};{};{}{}", code, col, row, sgr_final)),
+        MouseEncoding::SgrPixels => {
+            let (x, y) = event.pixel_position;
+            Some(format!("\x1b[<{};{};{}{}", code, x + 1, y + 1, sgr_final))
+        }
+        MouseEncoding::Urxvt => Some(format!("\x1b[{};{};{}M", legacy_code + 32, col, row)),
+        MouseEncoding::Utf8 => {
+            // Values are sent as UTF-8 characters, up to 2047
+            let encode = |value: u32| char::from_u32(value + 32).filter(|_| value + 32 < 0x800);
+            Some(format!("\x1b[M{}{}{}", encode(legacy_code)?, encode(col)?, encode(row)?))
+        }
+        MouseEncoding::X10 => {
+            // Sequences travel as text, so only values that stay 7-bit can be sent
+            let encode = |value: u32| char::from_u32(value + 32).filter(|ch| ch.is_ascii());
+            Some(format!("\x1b[M{}{}{}", encode(legacy_code)?, encode(col)?, encode(row)?))
+        }
+    }
+}
+
 /// Mouse configuration
 #[derive(Debug, Clone)]
 pub struct MouseConfig {
@@ -1,7 +1,9 @@
 #[cfg(test)]
 mod sill_tests {
     use super::*;
+    use crate::sill::*;
     use crate::sash::PaneId;
+    use crate::pane::{MouseEncoding, MouseTracking, TerminalModes};
     use std::time::{Duration, Instant};
 
     // Helper function to create a test sill engine
@@ -112,11 +114,10 @@ mod sill_tests {
         assert_eq!(commands.len(), 1);
         
         match &commands[0] {
-            InputCommand::MoveCursor { direction, amount, .. } => {
-                assert_eq!(*direction, CursorDirection::Up);
-                assert_eq!(*amount, 1);
+            InputCommand::SendSequence { sequence, .. } => {
+                assert_eq!(sequence, "\x1b[A");
             }
-            _ => panic!("Expected MoveCursor command"),
+            _ => panic!("Expected SendSequence command"),
         }
     }
 
@@ -130,10 +131,10 @@ mod sill_tests {
         assert_eq!(commands.len(), 1);
         
         match &commands[0] {
-            InputCommand::InsertText { text, .. } => {
-                assert_eq!(text, "\n");
+            InputCommand::SendSequence { sequence, .. } => {
+                assert_eq!(sequence, "\r");
             }
-            _ => panic!("Expected InsertText command for Enter"),
+            _ => panic!("Expected SendSequence command for Enter"),
         }
     }
 
@@ -145,9 +146,14 @@ mod sill_tests {
         let mut ctrl_c_event = create_test_key_event(67, Some('C'));
         ctrl_c_event.modifiers.ctrl = true;
         
-        let commands = engine.process_key_event(ctrl_c_event).unwrap();
+        let commands = engine.process_key_event(ctrl_c_event.clone()).unwrap();
+        
+        // Ctrl+C reaches the program as ETX
+        assert!(commands.iter().any(|cmd| matches!(cmd, InputCommand::SendSequence { sequence, .. } if sequence == "\x03")));
         
-        // Should generate copy command
+        // Ctrl+Shift+C copies
+        ctrl_c_event.modifiers.shift = true;
+        let commands = engine.process_key_event(ctrl_c_event).unwrap();
         assert!(commands.iter().any(|cmd| matches!(cmd, InputCommand::Copy { .. })));
     }
 
@@ -519,4 +525,463 @@ mod sill_tests {
         assert_eq!(stats.keys_processed, 1);
         assert_eq!(stats.mouse_events_processed, 1);
     }
-}
\ No newline at end of file
+
+    // ========== Kitty Keyboard Protocol Tests ==========
+
+    fn key(key: Key, character: Option<char>, modifiers: Modifiers, state: KeyState) -> KeyEvent {
+        KeyEvent {
+            key,
+            character,
+            modifiers,
+            state,
+            timestamp: Instant::now(),
+            input_mode: InputMode::Normal,
+        }
+    }
+
+    fn press(key_value: Key, character: Option<char>) -> KeyEvent {
+        key(key_value, character, Modifiers::default(), KeyState::Press)
+    }
+
+    fn with_mods(mut event: KeyEvent, ctrl: bool, alt: bool, shift: bool) -> KeyEvent {
+        event.modifiers = Modifiers { ctrl, alt, shift, meta: false };
+        event
+    }
+
+    #[test]
+    fn test_kitty_disambiguate_encoding() {
+        let encoder = KittyKeyEncoder::new(KeyboardFlags::DISAMBIGUATE);
+        let encode = |event: KeyEvent| encoder.encode(&event);
+        let a = || press(Key::Character('a'), Some('a'));
+        
+        let cases = [
+            (a(), Some("a")),
+            (with_mods(press(Key::Character('a'), Some('A')), false, false, true), Some("A")),
+            (with_mods(a(), true, false, false), Some("\x1b[97;5u")),
+            (with_mods(a(), true, false, true), Some("\x1b[97;6u")),
+            (with_mods(a(), false, true, false), Some("\x1b[97;3u")),
+            (press(Key::Escape, None), Some("\x1b[27u")),
+            (press(Key::Enter, Some('\r')), Some("\r")),
+            (with_mods(press(Key::Enter, None), true, false, false), Some("\x1b[13;5u")),
+            (press(Key::Backspace, None), Some("\x7f")),
+            (press(Key::ArrowUp, None), Some("\x1b[A")),
+            (with_mods(press(Key::ArrowUp, None), true, false, false), Some("\x1b[1;5A")),
+            (press(Key::Function(5), None), Some("\x1b[15~")),
+            (with_mods(press(Key::Function(1), None), false, false, true), Some("\x1b[1;2P")),
+            (press(Key::Function(13), None), Some("\x1b[57376u")),
+            (press(Key::KeypadNumber(1), None), Some("\x1b[57400u")),
+            (press(Key::Shift, None), None),
+            (key(Key::Character('a'), Some('a'), Modifiers::default(), KeyState::Release), None),
+        ];
+        for (event, expected) in cases {
+            assert_eq!(encode(event.clone()).as_deref(), expected, "{:?}", event.key);
+        }
+    }
+
+    #[test]
+    fn test_kitty_enhanced_encoding() {
+        let ctrl = Modifiers { ctrl: true, ..Default::default() };
+        
+        // Event types
+        let encoder = KittyKeyEncoder::new(KeyboardFlags::DISAMBIGUATE | KeyboardFlags::REPORT_EVENT_TYPES);
+        assert_eq!(encoder.encode(&key(Key::Character('a'), None, ctrl, KeyState::Release)).as_deref(), Some("\x1b[97;5:3u"));
+        assert_eq!(encoder.encode(&key(Key::ArrowUp, None, Modifiers::default(), KeyState::Repeat)).as_deref(), Some("\x1b[1;1:2A"));
+        assert_eq!(encoder.encode(&key(Key::Character('a'), Some('a'), Modifiers::default(), KeyState::Release)), None);
+        
+        // Alternate keys
+        let encoder = KittyKeyEncoder::new(KeyboardFlags::DISAMBIGUATE | KeyboardFlags::REPORT_ALTERNATE_KEYS);
+        let event = with_mods(press(Key::Character('a'), Some('A')), true, false, true);
+        assert_eq!(encoder.encode(&event).as_deref(), Some("\x1b[97:65;6u"));
+        
+        // Every key as an escape code, with associated text
+        let encoder = KittyKeyEncoder::new(KeyboardFlags::REPORT_ALL_KEYS | KeyboardFlags::REPORT_ASSOCIATED_TEXT);
+        assert_eq!(encoder.encode(&press(Key::Character('a'), Some('a'))).as_deref(), Some("\x1b[97;;97u"));
+        assert_eq!(encoder.encode(&press(Key::Enter, Some('\r'))).as_deref(), Some("\x1b[13u"));
+        assert_eq!(encoder.encode(&press(Key::Shift, None)).as_deref(), Some("\x1b[57441u"));
+        assert_eq!(encoder.encode(&key(Key::Character('a'), Some('a'), Modifiers::default(), KeyState::Release)), None);
+    }
+
+    #[test]
+    fn test_router_uses_pane_keyboard_flags() {
+        let mut router = InputRouter::new(&RoutingConfig::default()).unwrap();
+        let pane = PaneId::new(1);
+        let ctrl_a = with_mods(press(Key::Character('a'), Some('\x01')), true, false, false);
+        
+        router.set_keyboard_flags(pane, KeyboardFlags::DISAMBIGUATE);
+        let commands = router.route_key_event(ctrl_a.clone(), Some(pane)).unwrap();
+        assert_eq!(commands.len(), 1);
+        match &commands[0] {
+            InputCommand::SendSequence { sequence, target } => {
+                assert_eq!(sequence, "\x1b[97;5u");
+                assert_eq!(*target, CommandTarget::Pane(pane));
+            }
+            _ => panic!("Expected SendSequence command"),
+        }
+        
+        // Other panes, and the pane once it pops its flags, keep legacy handling
+        let commands = router.route_key_event(ctrl_a.clone(), Some(PaneId::new(2))).unwrap();
+        assert!(commands.iter().any(|cmd| matches!(cmd, InputCommand::SendSequence { sequence, .. } if sequence == "\x01")));
+        router.set_keyboard_flags(pane, KeyboardFlags::NONE);
+        assert!(router.keyboard_flags(pane).is_empty());
+    }
+
+    // ========== Xterm Key Encoding Tests ==========
+
+    fn mods(ctrl: bool, alt: bool, shift: bool, meta: bool) -> Modifiers {
+        Modifiers { ctrl, alt, shift, meta }
+    }
+
+    #[test]
+    fn test_xterm_cursor_and_editing_keys() {
+        let normal = XtermKeyEncoder::new(KeyEncodingModes::default());
+        let application = XtermKeyEncoder::new(KeyEncodingModes { application_cursor: true, ..Default::default() });
+        let none = Modifiers::default();
+        let ctrl = mods(true, false, false, false);
+        
+        let cases = [
+            (Key::ArrowUp, none, "\x1b[A", "\x1bOA"),
+            (Key::ArrowDown, none, "\x1b[B", "\x1bOB"),
+            (Key::ArrowRight, none, "\x1b[C", "\x1bOC"),
+            (Key::ArrowLeft, none, "\x1b[D", "\x1bOD"),
+            (Key::Home, none, "\x1b[H", "\x1bOH"),
+            (Key::End, none, "\x1b[F", "\x1bOF"),
+            (Key::ArrowUp, ctrl, "\x1b[1;5A", "\x1b[1;5A"),
+            (Key::ArrowLeft, mods(false, false, true, false), "\x1b[1;2D", "\x1b[1;2D"),
+            (Key::ArrowRight, mods(false, true, false, false), "\x1b[1;3C", "\x1b[1;3C"),
+            (Key::Home, mods(true, true, true, true), "\x1b[1;16H", "\x1b[1;16H"),
+            (Key::Insert, none, "\x1b[2~", "\x1b[2~"),
+            (Key::Delete, none, "\x1b[3~", "\x1b[3~"),
+            (Key::PageUp, none, "\x1b[5~", "\x1b[5~"),
+            (Key::PageDown, ctrl, "\x1b[6;5~", "\x1b[6;5~"),
+            (Key::ApplicationCursorUp, none, "\x1bOA", "\x1bOA"),
+        ];
+        for (key_value, modifiers, expected_normal, expected_application) in cases {
+            let event = key(key_value, None, modifiers, KeyState::Press);
+            assert_eq!(normal.encode(&event).as_deref(), Some(expected_normal), "{:?} {:?}", key_value, modifiers);
+            assert_eq!(application.encode(&event).as_deref(), Some(expected_application), "{:?} {:?}", key_value, modifiers);
+        }
+        
+        // Releases send nothing
+        assert_eq!(normal.encode(&key(Key::ArrowUp, None, none, KeyState::Release)), None);
+    }
+
+    #[test]
+    fn test_xterm_function_keys() {
+        let encoder = XtermKeyEncoder::new(KeyEncodingModes::default());
+        let plain = [
+            "\x1bOP", "\x1bOQ", "\x1bOR", "\x1bOS",
+            "\x1b[15~", "\x1b[17~", "\x1b[18~", "\x1b[19~", "\x1b[20~", "\x1b[21~", "\x1b[23~", "\x1b[24~",
+            "\x1b[1;2P", "\x1b[1;2Q", "\x1b[1;2R", "\x1b[1;2S",
+            "\x1b[15;2~", "\x1b[17;2~", "\x1b[18;2~", "\x1b[19;2~", "\x1b[20;2~", "\x1b[21;2~", "\x1b[23;2~", "\x1b[24;2~",
+        ];
+        for (index, expected) in plain.iter().enumerate() {
+            let n = index as u8 + 1;
+            assert_eq!(encoder.encode(&press(Key::Function(n), None)).as_deref(), Some(*expected), "F{}", n);
+        }
+        
+        let cases = [
+            (1, mods(true, false, false, false), "\x1b[1;5P"),
+            (4, mods(false, true, false, false), "\x1b[1;3S"),
+            (5, mods(true, false, true, false), "\x1b[15;6~"),
+            (12, mods(false, false, false, true), "\x1b[24;9~"),
+            (13, mods(true, false, false, false), "\x1b[1;6P"),
+        ];
+        for (n, modifiers, expected) in cases {
+            assert_eq!(encoder.encode(&key(Key::Function(n), None, modifiers, KeyState::Press)).as_deref(), Some(expected), "F{}", n);
+        }
+        assert_eq!(encoder.encode(&press(Key::Function(25), None)), None);
+    }
+
+    #[test]
+    fn test_xterm_keypad_modes() {
+        let numeric = XtermKeyEncoder::new(KeyEncodingModes::default());
+        let application = XtermKeyEncoder::new(KeyEncodingModes { application_keypad: true, ..Default::default() });
+        
+        let cases = [
+            (Key::KeypadNumber(0), "0", "\x1bOp"),
+            (Key::KeypadNumber(5), "5", "\x1bOu"),
+            (Key::KeypadNumber(9), "9", "\x1bOy"),
+            (Key::KeypadDecimal, ".", "\x1bOn"),
+            (Key::KeypadPlus, "+", "\x1bOk"),
+            (Key::KeypadMinus, "-", "\x1bOm"),
+            (Key::KeypadMultiply, "*", "\x1bOj"),
+            (Key::KeypadDivide, "/", "\x1bOo"),
+            (Key::KeypadEnter, "\r", "\x1bOM"),
+        ];
+        for (key_value, expected_numeric, expected_application) in cases {
+            let event = press(key_value, None);
+            assert_eq!(numeric.encode(&event).as_deref(), Some(expected_numeric), "{:?}", key_value);
+            assert_eq!(application.encode(&event).as_deref(), Some(expected_application), "{:?}", key_value);
+        }
+    }
+
+    #[test]
+    fn test_xterm_control_and_alt() {
+        let encoder = XtermKeyEncoder::new(KeyEncodingModes::default());
+        let no_escape = XtermKeyEncoder::new(KeyEncodingModes::default()).with_alt_sends_escape(false);
+        let ctrl = mods(true, false, false, false);
+        let alt = mods(false, true, false, false);
+        
+        let cases = [
+            (Key::Character('a'), Some('a'), Modifiers::default(), "a"),
+            (Key::Character('a'), Some('A'), mods(false, false, true, false), "A"),
+            (Key::Character('a'), Some('\x01'), ctrl, "\x01"),
+            (Key::Character('z'), None, ctrl, "\x1a"),
+            (Key::Character('@'), None, ctrl, "\0"),
+            (Key::Character('['), None, ctrl, "\x1b"),
+            (Key::Character('\\'), None, ctrl, "\x1c"),
+            (Key::Character(']'), None, ctrl, "\x1d"),
+            (Key::Character('^'), None, ctrl, "\x1e"),
+            (Key::Character('_'), None, ctrl, "\x1f"),
+            (Key::Character('?'), None, ctrl, "\x7f"),
+            (Key::Character('2'), None, ctrl, "\0"),
+            (Key::Character('8'), None, ctrl, "\x7f"),
+            (Key::Space, None, ctrl, "\0"),
+            (Key::Character('x'), Some('x'), alt, "\x1bx"),
+            (Key::Character('c'), None, mods(true, true, false, false), "\x1b\x03"),
+            (Key::Enter, None, Modifiers::default(), "\r"),
+            (Key::Enter, None, alt, "\x1b\r"),
+            (Key::Tab, None, Modifiers::default(), "\t"),
+            (Key::Tab, None, mods(false, false, true, false), "\x1b[Z"),
+            (Key::Backspace, None, Modifiers::default(), "\x7f"),
+            (Key::Backspace, None, ctrl, "\x08"),
+            (Key::Backspace, None, alt, "\x1b\x7f"),
+            (Key::Escape, None, Modifiers::default(), "\x1b"),
+        ];
+        for (key_value, character, modifiers, expected) in cases {
+            let event = key(key_value, character, modifiers, KeyState::Press);
+            assert_eq!(encoder.encode(&event).as_deref(), Some(expected), "{:?} {:?}", key_value, modifiers);
+        }
+        
+        // Without altSendsEscape the Alt modifier is dropped
+        let event = key(Key::Character('x'), Some('x'), alt, KeyState::Press);
+        assert_eq!(no_escape.encode(&event).as_deref(), Some("x"));
+    }
+
+    #[test]
+    fn test_xterm_modify_other_keys() {
+        let level = |modify_other_keys| XtermKeyEncoder::new(KeyEncodingModes { modify_other_keys, ..Default::default() });
+        let ctrl = mods(true, false, false, false);
+        let alt = mods(false, true, false, false);
+        let shift = mods(false, false, true, false);
+        let ctrl_shift = mods(true, false, true, false);
+        
+        // (key, character, modifiers, level 0, level 1, level 2)
+        let cases = [
+            (Key::Character('a'), Some('a'), Modifiers::default(), "a", "a", "a"),
+            (Key::Character('a'), Some('A'), shift, "A", "A", "A"),
+            (Key::Character('a'), None, ctrl, "\x01", "\x01", "\x1b[27;5;97~"),
+            (Key::Character('a'), Some('A'), ctrl_shift, "\x01", "\x1b[27;6;65~", "\x1b[27;6;65~"),
+            (Key::Character('a'), Some('a'), alt, "\x1ba", "\x1ba", "\x1b[27;3;97~"),
+            (Key::Character('1'), Some('1'), ctrl, "1", "\x1b[27;5;49~", "\x1b[27;5;49~"),
+            (Key::Character(','), Some(','), ctrl, ",", "\x1b[27;5;44~", "\x1b[27;5;44~"),
+            (Key::Enter, None, ctrl, "\r", "\x1b[27;5;13~", "\x1b[27;5;13~"),
+            (Key::Enter, None, shift, "\r", "\x1b[27;2;13~", "\x1b[27;2;13~"),
+            (Key::Tab, None, ctrl, "\t", "\x1b[27;5;9~", "\x1b[27;5;9~"),
+            (Key::Tab, None, shift, "\x1b[Z", "\x1b[Z", "\x1b[27;2;9~"),
+            (Key::Backspace, None, ctrl, "\x08", "\x08", "\x1b[27;5;127~"),
+            (Key::Escape, None, shift, "\x1b", "\x1b[27;2;27~", "\x1b[27;2;27~"),
+            (Key::Space, None, shift, " ", "\x1b[27;2;32~", "\x1b[27;2;32~"),
+        ];
+        for (key_value, character, modifiers, expected_0, expected_1, expected_2) in cases {
+            let event = key(key_value, character, modifiers, KeyState::Press);
+            assert_eq!(level(0).encode(&event).as_deref(), Some(expected_0), "level 0 {:?} {:?}", key_value, modifiers);
+            assert_eq!(level(1).encode(&event).as_deref(), Some(expected_1), "level 1 {:?} {:?}", key_value, modifiers);
+            assert_eq!(level(2).encode(&event).as_deref(), Some(expected_2), "level 2 {:?} {:?}", key_value, modifiers);
+        }
+        
+        // Cursor and function keys keep their own encodings at every level
+        let event = key(Key::ArrowUp, None, ctrl, KeyState::Press);
+        assert_eq!(level(2).encode(&event).as_deref(), Some("\x1b[1;5A"));
+    }
+
+    #[test]
+    fn test_vt52_key_encoding() {
+        let vt52 = XtermKeyEncoder::new(KeyEncodingModes { vt52: true, ..Default::default() });
+        let vt52_keypad = XtermKeyEncoder::new(KeyEncodingModes { vt52: true, application_keypad: true, ..Default::default() });
+        let ctrl = mods(true, false, false, false);
+        
+        let cases = [
+            (Key::ArrowUp, Modifiers::default(), Some("\x1bA")),
+            (Key::ArrowLeft, ctrl, Some("\x1bD")),
+            (Key::ApplicationCursorDown, Modifiers::default(), Some("\x1bB")),
+            (Key::Function(1), Modifiers::default(), Some("\x1bP")),
+            (Key::Function(4), ctrl, Some("\x1bS")),
+            (Key::Function(5), Modifiers::default(), None),
+            (Key::Home, Modifiers::default(), None),
+            (Key::Enter, Modifiers::default(), Some("\r")),
+            (Key::Character('c'), ctrl, Some("\x03")),
+        ];
+        for (key_value, modifiers, expected) in cases {
+            let event = key(key_value, None, modifiers, KeyState::Press);
+            assert_eq!(vt52.encode(&event).as_deref(), expected, "{:?} {:?}", key_value, modifiers);
+        }
+        
+        assert_eq!(vt52.encode(&press(Key::KeypadNumber(7), None)).as_deref(), Some("7"));
+        assert_eq!(vt52_keypad.encode(&press(Key::KeypadNumber(7), None)).as_deref(), Some("\x1b?w"));
+        assert_eq!(vt52_keypad.encode(&press(Key::KeypadEnter, None)).as_deref(), Some("\x1b?M"));
+        
+        let mut modes = TerminalModes::default();
+        modes.vt52 = true;
+        assert!(KeyEncodingModes::from(&modes).vt52);
+    }
+
+    #[test]
+    fn test_router_uses_pane_key_modes() {
+        let mut router = InputRouter::new(&RoutingConfig::default()).unwrap();
+        let pane = PaneId::new(1);
+        let up = press(Key::ArrowUp, None);
+        let sequence_of = |commands: Vec<InputCommand>| match commands.as_slice() {
+            [InputCommand::SendSequence { sequence, .. }] => sequence.clone(),
+            other => panic!("Expected one SendSequence command, got {:?}", other),
+        };
+        
+        assert_eq!(sequence_of(router.route_key_event(up.clone(), Some(pane)).unwrap()), "\x1b[A");
+        router.set_key_encoding_modes(pane, KeyEncodingModes { application_cursor: true, ..Default::default() });
+        assert_eq!(sequence_of(router.route_key_event(up.clone(), Some(pane)).unwrap()), "\x1bOA");
+        assert_eq!(sequence_of(router.route_key_event(up.clone(), Some(PaneId::new(2))).unwrap()), "\x1b[A");
+        
+        // Plain text still goes through InsertText, and Shift+PgUp scrolls the pane
+        let commands = router.route_key_event(press(Key::Character('q'), Some('q')), Some(pane)).unwrap();
+        assert!(matches!(commands.as_slice(), [InputCommand::InsertText { text, .. }] if text == "q"));
+        let shift_page_up = key(Key::PageUp, None, mods(false, false, true, false), KeyState::Press);
+        let commands = router.route_key_event(shift_page_up, Some(pane)).unwrap();
+        assert!(matches!(commands.as_slice(), [InputCommand::Scroll { direction: ScrollDirection::PageUp, .. }]));
+        
+        router.set_key_encoding_modes(pane, KeyEncodingModes::default());
+        assert_eq!(router.key_encoding_modes(pane), KeyEncodingModes::default());
+    }
+
+    // ========== Mouse Reporting Tests ==========
+
+    fn mouse(col: u16, row: u16, button: MouseButton, event_type: MouseEventType, modifiers: Modifiers) -> NormalizedMouseEvent {
+        NormalizedMouseEvent {
+            position: MousePosition { row, col },
+            pixel_position: (col as u32 * 8 + 3, row as u32 * 16 + 5),
+            button,
+            event_type,
+            modifiers,
+            click_count: 1,
+            scroll_delta: (0.0, 0.0),
+            timestamp: Instant::now(),
+        }
+    }
+
+    fn reporting(mode: MouseMode, encoding: MouseEncoding) -> MouseModeManager {
+        let mut manager = MouseModeManager::new();
+        manager.set_pane_state(PaneId::new(1), PaneMouseState { mode, encoding, ..Default::default() });
+        manager
+    }
+
+    #[test]
+    fn test_mouse_report_encodings() {
+        let pane = Some(PaneId::new(1));
+        let none = Modifiers::default();
+        let press = mouse(4, 9, MouseButton::Left, MouseEventType::Press, none);
+        let release = mouse(4, 9, MouseButton::Right, MouseEventType::Release, none);
+        let ctrl_middle = mouse(0, 0, MouseButton::Middle, MouseEventType::Press, mods(true, false, false, false));
+        
+        // (encoding, press, release, ctrl+middle press)
+        let cases = [
+            (MouseEncoding::X10, "\x1b[M %*", "\x1b[M#%*", "\x1b[M1!!"),
+            (MouseEncoding::Utf8, "\x1b[M %*", "\x1b[M#%*", "\x1b[M1!!"),
+            (MouseEncoding::Sgr, "\x1b[<0;5;10M", "\x1b[<2;5;10m", "\x1b[<17;1;1M"),
+            (MouseEncoding::Urxvt, "\x1b[32;5;10M", "\x1b[35;5;10M", "\x1b[49;1;1M"),
+            (MouseEncoding::SgrPixels, "\x1b[<0;36;150M", "\x1b[<2;36;150m", "\x1b[<17;4;6M"),
+        ];
+        for (encoding, expected_press, expected_release, expected_ctrl) in cases {
+            let manager = reporting(MouseMode::ButtonTracking, encoding);
+            assert_eq!(manager.report(pane, &press).as_deref(), Some(expected_press), "{:?}", encoding);
+            assert_eq!(manager.report(pane, &release).as_deref(), Some(expected_release), "{:?}", encoding);
+            assert_eq!(manager.report(pane, &ctrl_middle).as_deref(), Some(expected_ctrl), "{:?}", encoding);
+        }
+        
+        // Coordinates past 95 only fit the wider encodings
+        let far = mouse(200, 0, MouseButton::Left, MouseEventType::Press, none);
+        assert_eq!(reporting(MouseMode::ButtonTracking, MouseEncoding::X10).report(pane, &far), None);
+        assert_eq!(reporting(MouseMode::ButtonTracking, MouseEncoding::Utf8).report(pane, &far).as_deref(), Some("\x1b[M \u{e9}!"));
+        assert_eq!(reporting(MouseMode::ButtonTracking, MouseEncoding::Sgr).report(pane, &far).as_deref(), Some("\x1b[<0;201;1M"));
+    }
+
+    #[test]
+    fn test_mouse_tracking_mode_events() {
+        let pane = Some(PaneId::new(1));
+        let none = Modifiers::default();
+        let shift = mods(false, false, true, false);
+        let mut wheel = mouse(0, 0, MouseButton::WheelUp, MouseEventType::Scroll, none);
+        wheel.scroll_delta = (0.0, 1.0);
+        let events = [
+            mouse(0, 0, MouseButton::Left, MouseEventType::Press, shift),
+            mouse(0, 0, MouseButton::Left, MouseEventType::Release, none),
+            mouse(1, 0, MouseButton::Left, MouseEventType::Drag, none),
+            mouse(2, 0, MouseButton::Left, MouseEventType::Move, none),
+            wheel,
+        ];
+        
+        // Shift bypasses every mode so the user can still select text
+        let cases = [
+            (MouseMode::X10, [None, None, None, None, Some("\x1b[<64;1;1M")]),
+            (MouseMode::ButtonTracking, [None, Some("\x1b[<0;1;1m"), None, None, Some("\x1b[<64;1;1M")]),
+            (MouseMode::DragTracking, [None, Some("\x1b[<0;1;1m"), Some("\x1b[<32;2;1M"), None, Some("\x1b[<64;1;1M")]),
+            (MouseMode::AnyEventTracking, [None, Some("\x1b[<0;1;1m"), Some("\x1b[<32;2;1M"), Some("\x1b[<35;3;1M"), Some("\x1b[<64;1;1M")]),
+            (MouseMode::Normal, [None, None, None, None, None]),
+        ];
+        for (mode, expected) in cases {
+            let manager = reporting(mode, MouseEncoding::Sgr);
+            for (event, expected) in events.iter().zip(expected) {
+                assert_eq!(manager.report(pane, event).as_deref(), expected, "{:?} {:?}", mode, event.event_type);
+            }
+        }
+        
+        // X10 mode drops modifiers; other panes are not tracked
+        let ctrl_press = mouse(0, 0, MouseButton::Left, MouseEventType::Press, mods(true, false, false, false));
+        assert_eq!(reporting(MouseMode::X10, MouseEncoding::Sgr).report(pane, &ctrl_press).as_deref(), Some("\x1b[<0;1;1M"));
+        assert_eq!(reporting(MouseMode::X10, MouseEncoding::Sgr).report(Some(PaneId::new(2)), &ctrl_press), None);
+    }
+
+    #[test]
+    fn test_mouse_alternate_scroll() {
+        let pane = PaneId::new(1);
+        let mut manager = MouseModeManager::new();
+        let mut wheel_down = mouse(0, 0, MouseButton::WheelDown, MouseEventType::Scroll, Modifiers::default());
+        wheel_down.scroll_delta = (0.0, -3.0);
+        let state = PaneMouseState { alternate_scroll: true, ..Default::default() };
+        
+        // Only on the alternate screen
+        manager.set_pane_state(pane, state);
+        assert_eq!(manager.report(Some(pane), &wheel_down), None);
+        
+        manager.set_pane_state(pane, PaneMouseState { alternate_screen: true, ..state });
+        assert_eq!(manager.report(Some(pane), &wheel_down).as_deref(), Some("\x1b[B\x1b[B\x1b[B"));
+        
+        manager.set_pane_state(pane, PaneMouseState { alternate_screen: true, application_cursor: true, ..state });
+        assert_eq!(manager.report(Some(pane), &wheel_down).as_deref(), Some("\x1bOB\x1bOB\x1bOB"));
+        
+        // Mouse tracking takes precedence
+        manager.set_pane_state(pane, PaneMouseState { alternate_screen: true, mode: MouseMode::ButtonTracking, encoding: MouseEncoding::Sgr, ..state });
+        assert_eq!(manager.report(Some(pane), &wheel_down).as_deref(), Some("\x1b[<65;1;1M"));
+    }
+
+    #[test]
+    fn test_engine_reports_mouse_to_focused_pane() {
+        let mut engine = create_test_sill_engine();
+        let pane = PaneId::new(7);
+        engine.set_focus(Some(pane)).unwrap();
+        
+        let mut modes = TerminalModes::default();
+        modes.mouse.tracking = MouseTracking::Normal;
+        modes.mouse.encoding = MouseEncoding::Sgr;
+        engine.set_mouse_state(pane, PaneMouseState::from(&modes)).unwrap();
+        
+        let commands = engine.process_mouse_event(create_test_mouse_event(16, 32, MouseButton::Left, MouseEventType::Press)).unwrap();
+        assert!(matches!(commands.as_slice(), [InputCommand::SendSequence { sequence, .. }] if sequence == "\x1b[<0;3;3M"));
+        assert!(engine.selection_engine.get_current_selection().is_none());
+        
+        // Shift+click selects locally instead
+        let mut shift_click = create_test_mouse_event(16, 32, MouseButton::Left, MouseEventType::Press);
+        shift_click.modifiers.shift = true;
+        let commands = engine.process_mouse_event(shift_click).unwrap();
+        assert!(commands.iter().any(|cmd| matches!(cmd, InputCommand::StartSelection { .. })));
+    }
+}
+
@@ -0,0 +1,307 @@
+use super::*;
+use crate::pane::TerminalModes;
+
+/// Pane modes that change the bytes a key sends
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct KeyEncodingModes {
+    /// DECCKM - cursor keys send SS3 instead of CSI
+    pub application_cursor: bool,
+    /// DECKPAM - the keypad sends SS3 sequences instead of its characters
+    pub application_keypad: bool,
+    /// DECANM reset - keys send VT52 sequences
+    pub vt52: bool,
+    /// xterm modifyOtherKeys level (0 = off, 1 or 2)
+    pub modify_other_keys: u8,
+}
+
+impl From<&TerminalModes> for KeyEncodingModes {
+    fn from(modes: &TerminalModes) -> Self {
+        KeyEncodingModes {
+            application_cursor: modes.application_cursor,
+            application_keypad: modes.application_keypad,
+            vt52: modes.vt52,
+            modify_other_keys: modes.modify_other_keys,
+        }
+    }
+}
+
+/// Encoder for xterm-compatible key sequences
+#[derive(Debug, Clone, Copy)]
+pub struct XtermKeyEncoder {
+    modes: KeyEncodingModes,
+    alt_sends_escape: bool,
+}
+
+/// Legacy encoding of a key, and whether it kept every modifier
+struct LegacyBytes {
+    bytes: String,
+    lossless: bool,
+}
+
+impl LegacyBytes {
+    fn exact(bytes: impl Into<String>) -> Self {
+        LegacyBytes { bytes: bytes.into(), lossless: true }
+    }
+    
+    fn lossy(bytes: impl Into<String>) -> Self {
+        LegacyBytes { bytes: bytes.into(), lossless: false }
+    }
+}
+
+impl XtermKeyEncoder {
+    pub fn new(modes: KeyEncodingModes) -> Self {
+        XtermKeyEncoder {
+            modes,
+            alt_sends_escape: true,
+        }
+    }
+    
+    /// Prefix Alt-modified text with ESC instead of dropping Alt
+    pub fn with_alt_sends_escape(mut self, enabled: bool) -> Self {
+        self.alt_sends_escape = enabled;
+        self
+    }
+    
+    /// Encode a key event, or `None` if nothing should be sent
+    pub fn encode(&self, event: &KeyEvent) -> Option<String> {
+        if event.state == KeyState::Release {
+            return None;
+        }
+        if self.modes.vt52 {
+            return self.vt52_key(event);
+        }
+        
+        let param = modifier_param(&event.modifiers);
+        match event.key {
+            Key::ArrowUp => Some(self.cursor_key('A', param, false)),
+            Key::ArrowDown => Some(self.cursor_key('B', param, false)),
+            Key::ArrowRight => Some(self.cursor_key('C', param, false)),
+            Key::ArrowLeft => Some(self.cursor_key('D', param, false)),
+            Key::ApplicationCursorUp => Some(self.cursor_key('A', param, true)),
+            Key::ApplicationCursorDown => Some(self.cursor_key('B', param, true)),
+            Key::ApplicationCursorRight => Some(self.cursor_key('C', param, true)),
+            Key::ApplicationCursorLeft => Some(self.cursor_key('D', param, true)),
+            Key::Home => Some(self.cursor_key('H', param, false)),
+            Key::End => Some(self.cursor_key('F', param, false)),
+            
+            Key::Insert => Some(tilde_key(2, param)),
+            Key::Delete => Some(tilde_key(3, param)),
+            Key::PageUp => Some(tilde_key(5, param)),
+            Key::PageDown => Some(tilde_key(6, param)),
+            
+            Key::Function(n) => function_key(n, &event.modifiers),
+            
+            Key::KeypadNumber(_) | Key::KeypadDecimal | Key::KeypadPlus | Key::KeypadMinus
+            | Key::KeypadMultiply | Key::KeypadDivide | Key::KeypadEnter => self.keypad_key(event),
+            
+            Key::Character(_) | Key::Space | Key::Enter | Key::Tab | Key::Backspace | Key::Escape => {
+                self.other_key(event)
+            }
+            
+            _ => None,
+        }
+    }
+    
+    /// VT52 keys: `ESC x` arrows and PF1-PF4, `ESC ? x` keypad in application mode, no modifiers
+    fn vt52_key(&self, event: &KeyEvent) -> Option<String> {
+        let final_char = match event.key {
+            Key::ArrowUp | Key::ApplicationCursorUp => 'A',
+            Key::ArrowDown | Key::ApplicationCursorDown => 'B',
+            Key::ArrowRight | Key::ApplicationCursorRight => 'C',
+            Key::ArrowLeft | Key::ApplicationCursorLeft => 'D',
+            Key::Function(n @ 1..=4) => (b'P' + n - 1) as char,
+            
+            Key::KeypadNumber(_) | Key::KeypadDecimal | Key::KeypadPlus | Key::KeypadMinus
+            | Key::KeypadMultiply | Key::KeypadDivide | Key::KeypadEnter => {
+                let sequence = self.keypad_key(event)?;
+                // The VT52 keypad sends `ESC ? x` where ANSI mode sends `SS3 x`
+                return Some(match sequence.strip_prefix("\x1bO") {
+                    Some(application) => format!("\x1b?{}", application),
+                    None => sequence,
+                });
+            }
+            
+            Key::Character(_) | Key::Space | Key::Enter | Key::Tab | Key::Backspace | Key::Escape => {
+                let code = key_code(event)?;
+                return self.legacy_bytes(event, code).map(|legacy| legacy.bytes);
+            }
+            
+            // The VT52 has no editing or higher function keys
+            _ => return None,
+        };
+        Some(format!("\x1b{}", final_char))
+    }
+    
+    /// Arrows, Home and End: `CSI x`, `SS3 x` in application mode, `CSI 1 ; m x` when modified
+    fn cursor_key(&self, final_char: char, param: u32, application: bool) -> String {
+        if param > 1 {
+            format!("\x1b[1;{}{}", param, final_char)
+        } else if application || self.modes.application_cursor {
+            format!("\x1bO{}", final_char)
+        } else {
+            format!("\x1b[{}", final_char)
+        }
+    }
+    
+    fn keypad_key(&self, event: &KeyEvent) -> Option<String> {
+        let (text, application) = match event.key {
+            Key::KeypadNumber(digit) if digit <= 9 => {
+                ((b'0' + digit) as char, (b'p' + digit) as char)
+            }
+            Key::KeypadDecimal => ('.', 'n'),
+            Key::KeypadPlus => ('+', 'k'),
+            Key::KeypadMinus => ('-', 'm'),
+            Key::KeypadMultiply => ('*', 'j'),
+            Key::KeypadDivide => ('/', 'o'),
+            Key::KeypadEnter => ('\r', 'M'),
+            _ => return None,
+        };
+        
+        if self.modes.application_keypad {
+            Some(format!("\x1bO{}", application))
+        } else {
+            Some(self.alt_prefix(&event.modifiers, text.to_string()))
+        }
+    }
+    
+    /// Text-producing keys and the C0 keys: Ctrl mapping, Alt prefix and modifyOtherKeys
+    fn other_key(&self, event: &KeyEvent) -> Option<String> {
+        let code = key_code(event)?;
+        let m = &event.modifiers;
+        
+        // Level 2 reports every modified key except plain shifted text
+        let shifted_text = matches!(event.key, Key::Character(_)) && m.matches(false, false, true, false);
+        let legacy = self.legacy_bytes(event, code)?;
+        let modify = match self.modes.modify_other_keys {
+            2 => m.any() && !shifted_text,
+            1 => !legacy.lossless,
+            _ => false,
+        };
+        
+        if modify {
+            Some(format!("\x1b[27;{};{}~", modifier_param(m), code))
+        } else {
+            Some(legacy.bytes)
+        }
+    }
+    
+    fn legacy_bytes(&self, event: &KeyEvent, code: u32) -> Option<LegacyBytes> {
+        let m = &event.modifiers;
+        let base = match event.key {
+            Key::Enter if m.ctrl || m.shift => LegacyBytes::lossy("\r"),
+            Key::Enter => LegacyBytes::exact("\r"),
+            Key::Tab if m.matches(false, false, true, false) => return Some(LegacyBytes::exact("\x1b[Z")),
+            Key::Tab if m.ctrl || m.shift => LegacyBytes::lossy("\t"),
+            Key::Tab => LegacyBytes::exact("\t"),
+            Key::Backspace if m.ctrl && !m.shift => LegacyBytes::exact("\x08"),
+            Key::Backspace if m.ctrl || m.shift => LegacyBytes::lossy("\x7f"),
+            Key::Backspace => LegacyBytes::exact("\x7f"),
+            Key::Escape if m.ctrl || m.shift => LegacyBytes::lossy("\x1b"),
+            Key::Escape => LegacyBytes::exact("\x1b"),
+            Key::Space if m.ctrl && !m.shift => LegacyBytes::exact("\0"),
+            Key::Space if m.ctrl || m.shift => LegacyBytes::lossy(" "),
+            Key::Space => LegacyBytes::exact(" "),
+            Key::Character(c) if m.ctrl => match control_byte(c) {
+                // Ctrl+Shift+letter collapses onto Ctrl+letter
+                Some(byte) if !(m.shift && c.is_ascii_alphabetic()) => LegacyBytes::exact(byte.to_string()),
+                Some(byte) => LegacyBytes::lossy(byte.to_string()),
+                None => LegacyBytes::lossy(char::from_u32(code)?.to_string()),
+            },
+            Key::Character(_) => LegacyBytes::exact(char::from_u32(code)?.to_string()),
+            _ => return None,
+        };
+        
+        // Super has no legacy encoding, and Alt is lost unless it sends ESC
+        let lossless = base.lossless && !m.meta && (!m.alt || self.alt_sends_escape);
+        Some(LegacyBytes {
+            bytes: self.alt_prefix(m, base.bytes),
+            lossless,
+        })
+    }
+    
+    fn alt_prefix(&self, modifiers: &Modifiers, bytes: String) -> String {
+        if modifiers.alt && self.alt_sends_escape {
+            format!("\x1b{}", bytes)
+        } else {
+            bytes
+        }
+    }
+}
+
+/// Editing keys: `CSI n ~`, or `CSI n ; m ~` when modified
+fn tilde_key(number: u32, param: u32) -> String {
+    if param > 1 {
+        format!("\x1b[{};{}~", number, param)
+    } else {
+        format!("\x1b[{}~", number)
+    }
+}
+
+/// F1-F24 as xterm sends them; F13-F24 are Shift+F1-F12
+fn function_key(n: u8, modifiers: &Modifiers) -> Option<String> {
+    let (key, shift) = match n {
+        1..=12 => (n, modifiers.shift),
+        13..=24 => (n - 12, true),
+        _ => return None,
+    };
+    let param = modifier_param(&Modifiers { shift, ..*modifiers });
+    
+    let sequence = match key {
+        1..=4 => {
+            let final_char = (b'P' + key - 1) as char;
+            if param > 1 {
+                format!("\x1b[1;{}{}", param, final_char)
+            } else {
+                format!("\x1bO{}", final_char)
+            }
+        }
+        _ => {
+            let number = match key {
+                5 => 15,
+                6..=10 => key as u32 + 11,
+                _ => key as u32 + 12,
+            };
+            tilde_key(number, param)
+        }
+    };
+    Some(sequence)
+}
+
+/// The character code a key reports in `CSI 27 ; m ; code ~`
+fn key_code(event: &KeyEvent) -> Option<u32> {
+    let code = match event.key {
+        Key::Character(c) => event.character.filter(|ch| !ch.is_control()).unwrap_or(c) as u32,
+        Key::Space => 32,
+        Key::Enter => 13,
+        Key::Tab => 9,
+        Key::Backspace => 127,
+        Key::Escape => 27,
+        _ => return None,
+    };
+    Some(code)
+}
+
+/// The C0 control Ctrl turns a character into, following xterm's table
+fn control_byte(ch: char) -> Option<char> {
+    let byte = match ch {
+        'a'..='z' => ch as u8 - b'a' + 1,
+        'A'..='Z' => ch as u8 - b'A' + 1,
+        '@' | ' ' | '2' => 0x00,
+        '[' | '3' => 0x1b,
+        '\\' | '4' => 0x1c,
+        ']' | '5' => 0x1d,
+        '^' | '~' | '6' => 0x1e,
+        '_' | '/' | '7' => 0x1f,
+        '?' | '8' => 0x7f,
+        _ => return None,
+    };
+    Some(byte as char)
+}
+
+/// The xterm modifier parameter: 1 + (shift 1 | alt 2 | ctrl 4 | meta 8)
+fn modifier_param(modifiers: &Modifiers) -> u32 {
+    1 + ((modifiers.shift as u32)
+        | (modifiers.alt as u32) << 1
+        | (modifiers.ctrl as u32) << 2
+        | (modifiers.meta as u32) << 3)
+}